@@ -0,0 +1,62 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde_json::json;
+
+use super::event_handler::handle_input;
+use super::machine::Input;
+use super::state::{AppState, AppStatus};
+use crate::control::{Command, Reply};
+
+/// Run a control socket command and send back the answer.
+pub fn handle_control(state: &Rc<RefCell<AppState>>, command: Command, reply: Reply) {
+    let answer = match run(state, command) {
+        Ok(Some(answer)) => answer,
+        Ok(None) => {
+            let s = state.borrow();
+            json!({ "ok": true, "status": s.status.name(), "text": s.status_text })
+        }
+        Err(error) => json!({ "ok": false, "error": error }),
+    };
+    let _ = reply.try_send(answer);
+}
+
+/// Carry out `command`. `None` means the reply is just the new status.
+fn run(
+    state: &Rc<RefCell<AppState>>,
+    command: Command,
+) -> Result<Option<serde_json::Value>, String> {
+    match command {
+        Command::Toggle => handle_input(state, Input::Hotkey)?,
+        Command::Start => {
+            handle_input(state, Input::Start)?;
+            // Starting can fail, e.g. without a microphone
+            let s = state.borrow();
+            if s.status != AppStatus::Recording {
+                return Err(s.status_text.clone());
+            }
+        }
+        Command::Stop => handle_input(state, Input::Stop)?,
+        Command::Cancel => handle_input(state, Input::Cancel)?,
+        Command::Status => {
+            let s = state.borrow();
+            return Ok(Some(json!({
+                "ok": true,
+                "status": s.status.name(),
+                "text": s.status_text,
+                "profile": s.config.active_profile,
+            })));
+        }
+        Command::Last => {
+            let s = state.borrow();
+            return Ok(Some(json!({ "ok": true, "prompt": s.stats.history.last() })));
+        }
+        Command::History => {
+            let s = state.borrow();
+            return Ok(Some(json!({ "ok": true, "history": s.stats.history })));
+        }
+        // Handled by the socket thread
+        Command::Subscribe => {}
+    }
+    Ok(None)
+}
@@ -0,0 +1,147 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use super::event_handler::{begin_refinement, dismiss_overlay, handle_input};
+use super::machine::Input;
+use super::recording::start_recording;
+use super::state::{update_status, AppState, AppStatus, Draft, OverlayPhase};
+use crate::stats::PromptDetails;
+
+/// Record a take for the draft, opening a new draft if none is open.
+pub fn start_take(state: &Rc<RefCell<AppState>>) {
+    {
+        let mut s = state.borrow_mut();
+        let draft = s.draft.get_or_insert_with(|| Draft {
+            takes: Vec::new(),
+            details: PromptDetails::default(),
+            finishing: false,
+            finish_source: None,
+        });
+        draft.finishing = false;
+        if let Some(source) = draft.finish_source.take() {
+            source.remove();
+        }
+    }
+    log::info!("Recording draft take");
+    start_recording(state);
+    if state.borrow().status != AppStatus::Recording {
+        resume_draft(state);
+    }
+}
+
+/// Record the last take of the open draft; the whole draft is refined once
+/// it is transcribed.
+pub fn start_final_take(state: &Rc<RefCell<AppState>>) {
+    {
+        let mut s = state.borrow_mut();
+        let Some(draft) = s.draft.as_mut() else {
+            return;
+        };
+        draft.finishing = true;
+        if let Some(source) = draft.finish_source.take() {
+            source.remove();
+        }
+    }
+    log::info!("Recording final draft take");
+    start_recording(state);
+    if state.borrow().status != AppStatus::Recording {
+        resume_draft(state);
+    }
+}
+
+/// Add a transcribed take to the open draft.
+pub fn add_take(state: &Rc<RefCell<AppState>>, transcript: String) {
+    let mut s = state.borrow_mut();
+    let take = s.prompt_details.clone();
+    let Some(draft) = s.draft.as_mut() else {
+        return;
+    };
+    if !transcript.trim().is_empty() {
+        draft.takes.push(transcript);
+    }
+    let details = &mut draft.details;
+    details.recording_ms += take.recording_ms;
+    details.transcription_ms += take.transcription_ms;
+    details.language = take.language;
+}
+
+/// Refine the draft once the take being recorded or transcribed is in.
+pub fn finish_draft_later(state: &Rc<RefCell<AppState>>) {
+    if let Some(ref mut draft) = state.borrow_mut().draft {
+        draft.finishing = true;
+    }
+}
+
+/// Close the draft and refine its takes joined into one transcript.
+pub fn refine_draft(state: &Rc<RefCell<AppState>>) {
+    if let Some(transcript) = take_draft(state) {
+        begin_refinement(state, transcript);
+    }
+}
+
+/// Show the draft on the overlay and (re)start the finish timer. Drops a
+/// draft that has no takes yet.
+pub fn resume_draft(state: &Rc<RefCell<AppState>>) {
+    let (takes, finish_after) = {
+        let mut s = state.borrow_mut();
+        let Some(draft) = s.draft.as_mut() else {
+            return;
+        };
+        draft.finishing = false;
+        if let Some(source) = draft.finish_source.take() {
+            source.remove();
+        }
+        let takes = draft.takes.len();
+        if takes == 0 {
+            s.draft = None;
+            return;
+        }
+        let phase = OverlayPhase::Draft(takes);
+        s.view.show_overlay(&phase);
+        s.overlay_phase = Some(phase);
+        (takes, s.config.append.finish_after_secs)
+    };
+
+    let plural = if takes == 1 { "" } else { "s" };
+    update_status(
+        state,
+        AppStatus::Idle,
+        &format!("Idle — Draft ({takes} take{plural})"),
+    );
+
+    if finish_after > 0 {
+        let state_clone = state.clone();
+        let source = glib::timeout_add_local_once(Duration::from_secs(finish_after), move || {
+            // The source is gone once it has fired
+            if let Some(ref mut draft) = state_clone.borrow_mut().draft {
+                draft.finish_source = None;
+            }
+            log::info!("Draft idle, finishing");
+            let _ = handle_input(&state_clone, Input::FinishDraft);
+        });
+        if let Some(ref mut draft) = state.borrow_mut().draft {
+            draft.finish_source = Some(source);
+        }
+    }
+}
+
+/// Close the draft, returning its takes joined into one transcript.
+fn take_draft(state: &Rc<RefCell<AppState>>) -> Option<String> {
+    let draft = {
+        let mut s = state.borrow_mut();
+        let mut draft = s.draft.take()?;
+        if let Some(source) = draft.finish_source.take() {
+            source.remove();
+        }
+        s.prompt_details = draft.details.clone();
+        draft
+    };
+    if draft.takes.is_empty() {
+        dismiss_overlay(state);
+        update_status(state, AppStatus::Idle, "Idle — Draft was empty");
+        return None;
+    }
+    log::info!("Finishing draft of {} take(s)", draft.takes.len());
+    Some(draft.takes.join(" "))
+}
@@ -1,158 +1,372 @@
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::Duration;
 
-use gtk4::glib;
-use gtk4::prelude::*;
+use super::control;
+use super::draft;
+use super::machine::{self, Effect, Input};
+#[cfg(feature = "ui")]
+use super::model::secret_stored;
+use super::model::{load_whisper_model, secrets_loaded};
+use super::pipeline::{dispatch_paste, dispatch_refinement, dispatch_sink, dispatch_typing};
+use super::recording::{cancel_recording, start_recording, stop_recording};
+use super::review;
+use super::state::{
+    AppState, AppStatus, BackendEvent, OverlayPhase, RefineOutcome, update_status,
+};
+use crate::config::OutputSink;
 
-use super::model::load_whisper_model;
-use super::pipeline::dispatch_refinement;
-use super::recording::{start_recording, stop_recording};
-use super::state::{AppState, AppStatus, BackendEvent, OverlayPhase, update_status};
-use crate::ui::overlay::set_overlay_phase;
-
-/// Handle a backend event. This is the core state machine.
+/// Handle a backend event. User input and the results of background work
+/// go through the state machine in `machine`.
 pub fn handle_backend_event(state: &Rc<RefCell<AppState>>, event: BackendEvent) {
     match event {
         BackendEvent::HotkeyTriggered => {
-            let current_status = state.borrow().status.clone();
-            match current_status {
-                AppStatus::Idle => start_recording(state),
-                AppStatus::Recording => stop_recording(state),
-                _ => {
-                    log::info!("Ignoring hotkey while status={current_status:?}");
-                }
-            }
+            let _ = handle_input(state, Input::Hotkey);
+        }
+        BackendEvent::AppendTriggered => {
+            let _ = handle_input(state, Input::Append);
+        }
+        BackendEvent::FinishDraft => {
+            let _ = handle_input(state, Input::FinishDraft);
+        }
+        BackendEvent::SelectProfile(name) => {
+            let _ = handle_input(state, Input::SelectProfile(name));
         }
-        BackendEvent::TranscriptionComplete(transcript) => {
+        BackendEvent::CycleProfile => {
+            let _ = handle_input(state, Input::CycleProfile);
+        }
+        BackendEvent::TranscriptionComplete(transcript, latency) => {
             log::info!("Transcript: {transcript}");
-            // Transition overlay to Refining
-            {
-                let mut s = state.borrow_mut();
-                s.overlay_phase = Some(OverlayPhase::Refining);
-                if let Some(ref overlay) = s.overlay {
-                    set_overlay_phase(overlay, &OverlayPhase::Refining);
-                }
-            }
-            update_status(state, AppStatus::Processing, "Refining with Gemini...");
-            dispatch_refinement(state, transcript);
+            let _ = handle_input(state, Input::Transcribed(transcript, latency));
+        }
+        BackendEvent::RefinementDelta(delta) => {
+            let _ = handle_input(state, Input::Delta(delta));
+        }
+        BackendEvent::RefinementComplete(outcome, info) => {
+            log::info!("Refined: {}", outcome.text());
+            let _ = handle_input(state, Input::Refined(outcome, info));
         }
-        BackendEvent::RefinementComplete(refined) => {
-            log::info!("Refined: {refined}");
-            on_prompt_ready(state, refined);
+        BackendEvent::RetryRefinement => {
+            let _ = handle_input(state, Input::Retry);
+        }
+        #[cfg(feature = "ui")]
+        BackendEvent::ConnectionTested(provider, result) => {
+            state
+                .borrow()
+                .view
+                .show_connection_status(&provider, &result);
+        }
+        BackendEvent::ReviewConfirmed(text) => {
+            let _ = handle_input(state, Input::ReviewConfirmed(text));
+        }
+        BackendEvent::ReviewDiscarded => {
+            let _ = handle_input(state, Input::ReviewDiscarded);
+        }
+        BackendEvent::ReviewRetake(text, mode) => {
+            let _ = handle_input(state, Input::ReviewRetake(text, mode));
         }
         BackendEvent::ProcessingError(err) => {
-            log::error!("Processing error: {err}");
-            dismiss_overlay(state);
-            update_status(state, AppStatus::Idle, &format!("Error: {err}"));
+            let _ = handle_input(state, Input::Failed(err));
+        }
+        BackendEvent::OutputFailed(err) => output_failed(state, &err),
+        BackendEvent::TypingFailed(text, err) => {
+            let _ = handle_input(state, Input::TypingFailed(text, err));
+        }
+        BackendEvent::SecretsLoaded(secrets) => secrets_loaded(state, secrets),
+        #[cfg(feature = "ui")]
+        BackendEvent::SecretStored(name, value, result) => {
+            secret_stored(state, name, &value, result);
         }
         BackendEvent::ModelDownloadProgress(downloaded, total) => {
-            if let Some(ref dash) = state.borrow().dashboard {
-                dash.progress_bar.set_visible(true);
-                if total > 0 {
-                    dash.progress_bar
-                        .set_fraction(downloaded as f64 / total as f64);
-                    let mb_done = downloaded as f64 / 1_048_576.0;
-                    let mb_total = total as f64 / 1_048_576.0;
-                    dash.progress_bar.set_text(Some(&format!(
-                        "Downloading model: {mb_done:.1} / {mb_total:.1} MB"
-                    )));
-                } else {
-                    dash.progress_bar.pulse();
-                }
-            }
+            let _ = handle_input(state, Input::DownloadProgress(downloaded, total));
         }
         BackendEvent::ModelDownloadComplete => {
-            if let Some(ref dash) = state.borrow().dashboard {
-                dash.progress_bar.set_visible(false);
-            }
-            load_whisper_model(state);
+            let _ = handle_input(state, Input::DownloadComplete);
         }
         BackendEvent::TimerTick => {
-            let s = state.borrow();
-            if let (Some(start), Some(ref overlay)) = (s.recording_start, &s.overlay) {
-                let elapsed = start.elapsed().as_secs();
-                let mins = elapsed / 60;
-                let secs = elapsed % 60;
-                overlay.timer_label.set_text(&format!("{mins:02}:{secs:02}"));
+            let _ = handle_input(state, Input::Tick);
+        }
+        BackendEvent::AudioLevel(level) => state.borrow().view.show_level(level),
+        BackendEvent::OverlayClicked => {
+            let _ = handle_input(state, Input::OverlayClicked);
+        }
+        BackendEvent::Control(command, reply) => control::handle_control(state, command, reply),
+    }
+}
+
+/// Run `input` through the state machine and carry out the effects it
+/// returns. Fails with the reason if the input was rejected.
+pub fn handle_input(state: &Rc<RefCell<AppState>>, input: Input) -> Result<(), String> {
+    let effects = machine::transition(&snapshot(state), input);
+    for effect in effects {
+        apply(state, effect)?;
+    }
+    Ok(())
+}
+
+/// What the state machine needs to know about `state`.
+fn snapshot(state: &Rc<RefCell<AppState>>) -> machine::Snapshot {
+    let s = state.borrow();
+    machine::Snapshot {
+        status: s.status.clone(),
+        phase: s.overlay_phase.clone(),
+        reviewing: s.view.reviewing(),
+        review_held: s.review.is_some(),
+        retaking: s.review.as_ref().is_some_and(|r| r.retake.is_some()),
+        draft_open: s.draft.is_some(),
+        draft_finishing: s.draft.as_ref().is_some_and(|d| d.finishing),
+        has_transcript: !s.prompt_details.raw.is_empty(),
+        retrying: s.retrying,
+        sinks: s.config.output.sinks.clone(),
+        // Headless builds have no review window
+        review_profiles: s
+            .config
+            .profiles
+            .iter()
+            .filter(|p| p.review && cfg!(feature = "ui"))
+            .map(|p| p.name.clone())
+            .collect(),
+        profiles: s.config.profiles.iter().map(|p| p.name.clone()).collect(),
+        active_profile: s.config.active_profile.clone(),
+    }
+}
+
+fn apply(state: &Rc<RefCell<AppState>>, effect: Effect) -> Result<(), String> {
+    match effect {
+        Effect::StartRecording => start_recording(state),
+        Effect::StartTake => draft::start_take(state),
+        Effect::StartFinalTake => draft::start_final_take(state),
+        Effect::RetakeReview { text, mode } => review::retake(state, text, mode),
+        Effect::StopRecording => stop_recording(state),
+        Effect::CancelRecording => cancel_recording(state),
+        Effect::RetryRefinement => retry_refinement(state),
+        Effect::CopyToClipboard(text) => {
+            if let Err(e) = crate::clipboard::copy_to_clipboard(&text) {
+                log::warn!("Clipboard error: {e}");
             }
         }
-        BackendEvent::AudioLevel(level) => {
-            let s = state.borrow();
-            if let Some(ref overlay) = s.overlay {
-                let mut levels = overlay.audio_levels.borrow_mut();
-                if levels.len() >= 24 {
-                    levels.pop_front();
-                }
-                levels.push_back(level);
-                overlay.waveform.queue_draw();
+        Effect::DismissOverlay => dismiss_overlay(state),
+        Effect::AppendPreview(delta) => {
+            let mut s = state.borrow_mut();
+            s.refine_preview.push_str(&delta);
+            s.view.show_preview(&s.refine_preview);
+        }
+        Effect::RecordTranscription(latency) => {
+            let mut s = state.borrow_mut();
+            s.prompt_details.language = s.config.transcription.language.clone();
+            s.prompt_details.transcription_ms = latency.as_millis() as u64;
+        }
+        Effect::AddTake(transcript) => draft::add_take(state, transcript),
+        Effect::RefineDraft => draft::refine_draft(state),
+        Effect::FinishDraftLater => draft::finish_draft_later(state),
+        Effect::ResumeDraft => draft::resume_draft(state),
+        Effect::ResumeReview => review::resume_review(state),
+        Effect::ConfirmReview(text) => review::confirm_review(state, text),
+        Effect::DiscardReview => review::discard_review(state),
+        Effect::SetActiveProfile(name) => {
+            log::info!("Active profile: {name}");
+            let mut s = state.borrow_mut();
+            s.config.active_profile = name;
+            if let Err(e) = s.config.save() {
+                log::warn!("Failed to save config: {e}");
             }
         }
-        BackendEvent::OverlayClicked => {
-            // If Done, re-copy text to clipboard before dismissing
-            let phase = state.borrow().overlay_phase.clone();
-            if let Some(OverlayPhase::Done(ref text)) = phase {
-                let _ = crate::clipboard::copy_to_clipboard(text);
+        Effect::ShowProfile(name, index) => state.borrow().view.show_profile(&name, index),
+        Effect::BeginRefinement(transcript) => begin_refinement(state, transcript),
+        Effect::RecordRefinement { info, refined } => {
+            let mut s = state.borrow_mut();
+            let details = &mut s.prompt_details;
+            details.refined = refined;
+            details.profile = info.profile;
+            details.provider = info.provider;
+            details.model = info.model;
+            details.refinement_ms = info.latency.as_millis() as u64;
+        }
+        Effect::StartReview(outcome) => review::start_review(state, outcome),
+        Effect::CopyPrompt { text, paste } => {
+            if let Err(e) = copy_prompt(state, &text, paste) {
+                log::error!("Clipboard error: {e}");
+                output_failed(state, &format!("clipboard: {e}"));
+            }
+        }
+        Effect::Output(OutputSink::Type, text) => dispatch_typing(state, text),
+        Effect::Output(sink, text) => dispatch_sink(state, sink, text),
+        Effect::RecordPrompt { outcome, replace } => record_prompt(state, &outcome, replace),
+        Effect::ShowPhase(phase) => {
+            let mut s = state.borrow_mut();
+            // Hidden while the prompt was in the review window
+            s.view.show_overlay(&phase);
+            s.overlay_phase = Some(phase);
+        }
+        Effect::NotifyPrompt { summary, text } => {
+            state.borrow().view.notify_prompt(&summary, &text);
+        }
+        Effect::ScheduleDismiss(secs) => {
+            let state_clone = state.clone();
+            let source = glib::timeout_add_local_once(Duration::from_secs(secs), move || {
+                dismiss_overlay(&state_clone);
+            });
+            state.borrow_mut().overlay_dismiss_source = Some(source);
+        }
+        Effect::SetStatus(status, text) => update_status(state, status, &text),
+        Effect::ReportOutputFailure(err) => output_failed(state, &err),
+        Effect::ReportError(err) => {
+            log::error!("Processing error: {err}");
+            let s = state.borrow();
+            s.emit(serde_json::json!({ "event": "error", "message": err }));
+            s.view.notify_error(&err);
+        }
+        Effect::ShowDownload(downloaded, total) => {
+            state.borrow().view.show_download(downloaded, total);
+        }
+        Effect::HideDownload => state.borrow().view.hide_download(),
+        Effect::LoadModel => load_whisper_model(state),
+        Effect::ShowElapsed => {
+            let s = state.borrow();
+            if let Some(start) = s.recording_start {
+                s.view.show_elapsed(start.elapsed());
             }
-            dismiss_overlay(state);
         }
+        Effect::Ignore(reason) => log::info!("{reason}"),
+        Effect::Reject(reason) => return Err(reason),
     }
+    Ok(())
 }
 
-fn on_prompt_ready(state: &Rc<RefCell<AppState>>, text: String) {
-    if let Err(e) = crate::clipboard::copy_to_clipboard(&text) {
-        log::error!("Clipboard error: {e}");
-        dismiss_overlay(state);
-        update_status(state, AppStatus::Idle, &format!("Clipboard error: {e}"));
-        return;
-    }
+/// Refine the last raw transcript again, replacing its history entry.
+fn retry_refinement(state: &Rc<RefCell<AppState>>) {
+    log::info!("Retrying refinement");
+    let transcript = {
+        let mut s = state.borrow_mut();
+        if let Some(source) = s.overlay_dismiss_source.take() {
+            source.remove();
+        }
+        s.overlay_phase = Some(OverlayPhase::Refining);
+        s.refine_preview.clear();
+        s.retrying = true;
+        s.view.show_phase(&OverlayPhase::Refining);
+        s.view.show_preview("");
+        s.prompt_details.raw.clone()
+    };
+    update_status(state, AppStatus::Processing, "Refining...");
+    dispatch_refinement(state, transcript);
+}
 
+/// Transition the overlay to Refining and send `transcript` to the refiner.
+pub fn begin_refinement(state: &Rc<RefCell<AppState>>, transcript: String) {
     {
         let mut s = state.borrow_mut();
-        s.stats.record_prompt(&text);
-        if let Err(e) = s.stats.save() {
-            log::warn!("Failed to save stats: {e}");
-        }
+        s.overlay_phase = Some(OverlayPhase::Refining);
+        s.refine_preview.clear();
+        s.prompt_details.raw = transcript.clone();
+        s.retrying = false;
+        s.view.show_phase(&OverlayPhase::Refining);
+        s.view.show_preview("");
     }
+    update_status(state, AppStatus::Processing, "Refining...");
+    dispatch_refinement(state, transcript);
+}
 
-    {
-        let s = state.borrow();
-        if let Some(ref dash) = s.dashboard {
-            dash.words_label.set_text(&s.stats.total_words.to_string());
-            dash.prompts_label.set_text(&s.stats.total_prompts.to_string());
+/// The shortcut to paste the prompt with, if its profile wants it pasted.
+fn paste_chord(state: &Rc<RefCell<AppState>>) -> Option<String> {
+    let s = state.borrow();
+    s.config
+        .profile(&s.prompt_details.profile)
+        .filter(|p| p.auto_paste)
+        .map(|p| {
+            if p.paste_chord.is_empty() {
+                s.config.paste.chord.clone()
+            } else {
+                p.paste_chord.clone()
+            }
+        })
+}
+
+/// Copy the prompt, and paste it if `paste` and the profile asks.
+fn copy_prompt(
+    state: &Rc<RefCell<AppState>>,
+    text: &str,
+    paste: bool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let paste_chord = if paste { paste_chord(state) } else { None };
+    // Save what the prompt is about to overwrite, to put it back once it
+    // has been pasted
+    let previous = match paste_chord {
+        Some(_) if state.borrow().config.paste.restore_clipboard => {
+            crate::clipboard::snapshot_clipboard().unwrap_or_else(|e| {
+                log::warn!("Could not save the clipboard: {e}");
+                None
+            })
         }
+        _ => None,
+    };
+    crate::clipboard::copy_to_clipboard(text)?;
+    if state.borrow().config.output.primary_selection {
+        if let Err(e) = crate::clipboard::copy_to_primary(text) {
+            log::warn!("Could not set the primary selection: {e}");
+        }
+    }
+    if let Some(chord) = paste_chord {
+        dispatch_paste(state, chord, text.to_string(), previous);
     }
+    Ok(())
+}
 
-    // Transition overlay to Done
+/// Add a prompt to the history, or replace the last entry with a retried
+/// one, and tell subscribers.
+fn record_prompt(state: &Rc<RefCell<AppState>>, outcome: &RefineOutcome, replace: bool) {
+    let text = outcome.text();
     {
         let mut s = state.borrow_mut();
-        let done_phase = OverlayPhase::Done(text);
-        s.overlay_phase = Some(done_phase.clone());
-        if let Some(ref overlay) = s.overlay {
-            set_overlay_phase(overlay, &done_phase);
+        s.retrying = false;
+        let details = s.prompt_details.clone();
+        if replace {
+            s.stats.replace_last_prompt(text, details);
+        } else {
+            s.stats.record_prompt(text, details);
         }
+        if let Err(e) = s.stats.save() {
+            log::warn!("Failed to save stats: {e}");
+        }
+    }
+
+    let s = state.borrow();
+    s.view.show_stats(&s.stats);
+    if let RefineOutcome::Fallback { ref reason, .. } = outcome {
+        let time = chrono::Local::now().format("%H:%M");
+        s.view
+            .show_refinement_error(&format!("{time} \u{2014} {reason}"));
     }
+    s.emit(serde_json::json!({
+        "event": "prompt",
+        "text": text,
+        "refined": outcome.is_refined(),
+        "profile": s.prompt_details.profile,
+    }));
+}
 
-    update_status(state, AppStatus::Idle, "Idle — Prompt copied!");
+/// A sink could not take the prompt.
+fn output_failed(state: &Rc<RefCell<AppState>>, err: &str) {
+    log::warn!("Output failed: {err}");
+    let s = state.borrow();
+    if s.status == AppStatus::Idle {
+        s.view.show_status(&format!("Idle — Output failed: {err}"));
+    }
+    s.view.notify_error(&format!("Output failed: {err}"));
+}
 
-    // Auto-dismiss after 3 seconds
-    let state_clone = state.clone();
-    let source = glib::timeout_add_local_once(
-        std::time::Duration::from_secs(3),
-        move || {
-            dismiss_overlay(&state_clone);
-        },
-    );
-    state.borrow_mut().overlay_dismiss_source = Some(source);
+/// Make `name` the active refinement profile, persist it and refresh the UI.
+#[cfg(feature = "ui")]
+pub fn select_profile(state: &Rc<RefCell<AppState>>, name: &str) {
+    let _ = handle_input(state, Input::SelectProfile(name.to_string()));
 }
 
 /// Hide overlay, clear phase, cancel dismiss timer.
-fn dismiss_overlay(state: &Rc<RefCell<AppState>>) {
+pub fn dismiss_overlay(state: &Rc<RefCell<AppState>>) {
     let mut s = state.borrow_mut();
     s.overlay_phase = None;
     if let Some(source) = s.overlay_dismiss_source.take() {
         source.remove();
     }
-    if let Some(ref overlay) = s.overlay {
-        overlay.window.set_visible(false);
-    }
+    s.view.hide_overlay();
 }
@@ -0,0 +1,1293 @@
+//! The decisions behind user input and background results, kept free of
+//! GTK, audio and I/O: given what the app is doing and what happened, which
+//! effects to carry out. `event_handler` takes a snapshot of `AppState`,
+//! runs `transition` and applies the effects.
+
+use std::time::Duration;
+
+use super::state::{AppStatus, OverlayPhase, RefineInfo, RefineOutcome, Retake};
+use crate::config::OutputSink;
+
+/// The parts of `AppState` that input handling depends on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub status: AppStatus,
+    pub phase: Option<OverlayPhase>,
+    /// The review window is open
+    pub reviewing: bool,
+    /// A prompt is held for review, possibly while a take is added to it
+    pub review_held: bool,
+    /// A take for the prompt under review is being recorded or transcribed
+    pub retaking: bool,
+    /// Takes are being collected in append mode
+    pub draft_open: bool,
+    /// The draft is refined once the current take is in
+    pub draft_finishing: bool,
+    /// A raw transcript is kept for retrying refinement
+    pub has_transcript: bool,
+    /// The refinement under way is a retry of the last prompt
+    pub retrying: bool,
+    /// Where finished prompts go
+    pub sinks: Vec<OutputSink>,
+    /// Profiles whose prompts open the review window
+    pub review_profiles: Vec<String>,
+    /// Names of all refinement profiles, in order
+    pub profiles: Vec<String>,
+    pub active_profile: String,
+}
+
+/// Something the user (or a script) asked for, or a result of background
+/// work.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    /// Main hotkey, or the control socket's `toggle`.
+    Hotkey,
+    /// Append hotkey.
+    Append,
+    /// Control socket `start`: like the hotkey, but only ever starts.
+    Start,
+    /// Control socket `stop`.
+    Stop,
+    /// Control socket `cancel`.
+    Cancel,
+    /// Overlay retry button.
+    Retry,
+    OverlayClicked,
+    /// Overlay Finish button, or the draft's finish timer.
+    FinishDraft,
+    /// Review window: copy the (edited) text.
+    ReviewConfirmed(String),
+    /// Review window closed without copying.
+    ReviewDiscarded,
+    /// Review window: record another take for the (edited) text.
+    ReviewRetake(String, Retake),
+    /// Make the named refinement profile active.
+    SelectProfile(String),
+    /// Switch to the profile after the active one (overlay button).
+    CycleProfile,
+    /// A take was transcribed, taking this long.
+    Transcribed(String, Duration),
+    /// A chunk of streamed refinement.
+    Delta(String),
+    Refined(RefineOutcome, RefineInfo),
+    /// A prompt is ready to send out: refined, or confirmed in review.
+    PromptReady(RefineOutcome),
+    /// Transcription failed, or another error ended the take.
+    Failed(String),
+    /// No keyboard backend could type the prompt (text, reason).
+    TypingFailed(String, String),
+    DownloadProgress(u64, u64),
+    DownloadComplete,
+    /// The recording timer fired.
+    Tick,
+}
+
+/// What to do about an input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Effect {
+    StartRecording,
+    /// Record a take for the draft, opening one if needed.
+    StartTake,
+    /// Record the draft's last take.
+    StartFinalTake,
+    /// Close the review window and record a take that is appended to the
+    /// prompt under review or replaces it. `text` is the prompt as edited;
+    /// `None` reads it from the window.
+    RetakeReview {
+        text: Option<String>,
+        mode: Retake,
+    },
+    /// Stop capturing and transcribe.
+    StopRecording,
+    /// Stop capturing and drop the audio.
+    CancelRecording,
+    /// Refine the kept transcript again.
+    RetryRefinement,
+    CopyToClipboard(String),
+    DismissOverlay,
+    AppendPreview(String),
+    /// Keep the transcription latency and language for the history.
+    RecordTranscription(Duration),
+    /// Add a transcribed take to the open draft.
+    AddTake(String),
+    /// Close the draft and refine its takes.
+    RefineDraft,
+    /// Refine the draft once the take in progress is transcribed.
+    FinishDraftLater,
+    /// Show the open draft again and restart its finish timer.
+    ResumeDraft,
+    /// Reopen the review window on the earlier text after a failed retake.
+    ResumeReview,
+    /// Close the review window and send out the prompt with this text.
+    ConfirmReview(String),
+    /// Close the review window and drop the prompt.
+    DiscardReview,
+    /// Make a profile active, saving the choice if it changed.
+    SetActiveProfile(String),
+    /// Show a profile, by name and position, as the active one.
+    ShowProfile(String, usize),
+    /// Show Refining and send the transcript to the refiner.
+    BeginRefinement(String),
+    /// Keep which refiner ran, how long it took and whether it succeeded,
+    /// for the history.
+    RecordRefinement {
+        info: RefineInfo,
+        refined: bool,
+    },
+    /// Hold the prompt back in the review window.
+    StartReview(RefineOutcome),
+    /// Copy the prompt; `paste` allows the profile's auto-paste.
+    CopyPrompt {
+        text: String,
+        paste: bool,
+    },
+    /// Send the prompt to a sink other than the clipboard.
+    Output(OutputSink, String),
+    /// Add the prompt to the history, or replace the last entry with it.
+    RecordPrompt {
+        outcome: RefineOutcome,
+        replace: bool,
+    },
+    /// Switch the overlay to a phase and show it.
+    ShowPhase(OverlayPhase),
+    NotifyPrompt {
+        summary: String,
+        text: String,
+    },
+    /// Hide the overlay after this many seconds.
+    ScheduleDismiss(u64),
+    SetStatus(AppStatus, String),
+    /// Log the error, notify the user and tell subscribers.
+    ReportError(String),
+    /// Tell the user a prompt didn't reach one of its sinks.
+    ReportOutputFailure(String),
+    ShowDownload(u64, u64),
+    HideDownload,
+    /// Load the downloaded Whisper model.
+    LoadModel,
+    /// Update the recording timer on the overlay.
+    ShowElapsed,
+    /// Nothing to do; the reason is logged.
+    Ignore(String),
+    /// The request can't be carried out; the reason goes back to the caller.
+    Reject(String),
+}
+
+/// Decide what `input` does in the state described by `snapshot`.
+pub fn transition(snapshot: &Snapshot, input: Input) -> Vec<Effect> {
+    use AppStatus::{Idle, ModelDownloading, Processing, Recording};
+
+    let status = &snapshot.status;
+    let append_to_review = Effect::RetakeReview {
+        text: None,
+        mode: Retake::Append,
+    };
+    match (status, input) {
+        // While reviewing, the hotkey records another take; with a draft
+        // open, it records the draft's last take
+        (Idle, Input::Hotkey | Input::Start) => vec![if snapshot.reviewing {
+            append_to_review
+        } else if snapshot.draft_open {
+            Effect::StartFinalTake
+        } else {
+            Effect::StartRecording
+        }],
+        (Idle, Input::Append) => vec![if snapshot.reviewing {
+            append_to_review
+        } else {
+            Effect::StartTake
+        }],
+        (Recording, Input::Hotkey | Input::Append | Input::Stop) => vec![Effect::StopRecording],
+        (Recording, Input::Cancel) => vec![Effect::CancelRecording],
+        (Recording, Input::Start) => vec![],
+        (Processing | ModelDownloading, Input::Hotkey | Input::Append) => {
+            vec![Effect::Ignore(format!(
+                "Ignoring hotkey while {}",
+                status.name()
+            ))]
+        }
+        (Processing | ModelDownloading, Input::Start) => {
+            vec![Effect::Reject(format!("busy ({})", status.name()))]
+        }
+        (Idle | Processing | ModelDownloading, Input::Stop | Input::Cancel) => {
+            vec![Effect::Reject("not recording".into())]
+        }
+        (_, Input::Retry) => match snapshot.phase {
+            Some(OverlayPhase::Fallback(_)) if *status == Idle && snapshot.has_transcript => {
+                vec![Effect::RetryRefinement]
+            }
+            _ => vec![Effect::Ignore("Nothing to retry".into())],
+        },
+        (_, Input::OverlayClicked) => match snapshot.phase {
+            // The draft bar stays up until the draft is finished
+            Some(OverlayPhase::Draft(_)) => vec![Effect::Ignore("Draft still open".into())],
+            // Done copies the text again before dismissing, unless the user
+            // keeps dictation off the clipboard
+            Some(OverlayPhase::Done(ref text) | OverlayPhase::Fallback(ref text))
+                if snapshot.sinks.contains(&OutputSink::Clipboard) =>
+            {
+                vec![
+                    Effect::CopyToClipboard(text.clone()),
+                    Effect::DismissOverlay,
+                ]
+            }
+            _ => vec![Effect::DismissOverlay],
+        },
+        // The prompt goes to the clipboard instead, unless it's there
+        // already or the user keeps dictation off it
+        (_, Input::TypingFailed(text, err)) => {
+            if snapshot.sinks.contains(&OutputSink::Clipboard) {
+                vec![Effect::ReportOutputFailure(format!("typing failed: {err}"))]
+            } else {
+                vec![
+                    Effect::CopyToClipboard(text),
+                    Effect::ReportOutputFailure(format!(
+                        "typing failed: {err}; the prompt was copied instead"
+                    )),
+                ]
+            }
+        }
+        (_, Input::FinishDraft) if !snapshot.draft_open => {
+            vec![Effect::Ignore("No draft to finish".into())]
+        }
+        (Idle, Input::FinishDraft) => vec![Effect::RefineDraft],
+        // A take is still being recorded or transcribed
+        (_, Input::FinishDraft) => vec![Effect::FinishDraftLater],
+        // Stale events from a window that was already closed
+        (_, Input::ReviewConfirmed(_) | Input::ReviewDiscarded | Input::ReviewRetake(..))
+            if !snapshot.reviewing =>
+        {
+            vec![Effect::Ignore("Review window already closed".into())]
+        }
+        (_, Input::ReviewConfirmed(text)) => vec![Effect::ConfirmReview(text)],
+        (_, Input::ReviewDiscarded) => vec![
+            Effect::DiscardReview,
+            Effect::SetStatus(Idle, "Idle — Prompt discarded".into()),
+        ],
+        (Idle, Input::ReviewRetake(text, mode)) => vec![Effect::RetakeReview {
+            text: Some(text),
+            mode,
+        }],
+        (_, Input::ReviewRetake(..)) => vec![Effect::Ignore(format!(
+            "Ignoring retake while {}",
+            status.name()
+        ))],
+        (_, Input::SelectProfile(name)) => select_profile(snapshot, name),
+        (_, Input::CycleProfile) => {
+            let profiles = &snapshot.profiles;
+            let next = profiles
+                .iter()
+                .position(|p| *p == snapshot.active_profile)
+                .map_or(0, |i| (i + 1) % profiles.len());
+            match profiles.get(next) {
+                Some(name) => select_profile(snapshot, name.clone()),
+                None => vec![Effect::Ignore("No profiles to switch between".into())],
+            }
+        }
+        // Late chunks from an abandoned request must not resurrect the preview
+        (_, Input::Delta(delta)) => match snapshot.phase {
+            Some(OverlayPhase::Refining) => vec![Effect::AppendPreview(delta)],
+            _ => vec![],
+        },
+        // A draft take is held back until the draft is finished
+        (_, Input::Transcribed(transcript, latency)) => {
+            let next = match (snapshot.draft_open, snapshot.draft_finishing) {
+                (false, _) => vec![Effect::BeginRefinement(transcript)],
+                (true, true) => vec![Effect::AddTake(transcript), Effect::RefineDraft],
+                (true, false) => vec![Effect::AddTake(transcript), Effect::ResumeDraft],
+            };
+            [vec![Effect::RecordTranscription(latency)], next].concat()
+        }
+        (_, Input::Refined(outcome, info)) => {
+            // A retry replaces a prompt that was already delivered
+            let review = !snapshot.retrying
+                && (snapshot.review_held || snapshot.review_profiles.contains(&info.profile));
+            let record = Effect::RecordRefinement {
+                info,
+                refined: outcome.is_refined(),
+            };
+            let next = if review {
+                vec![Effect::StartReview(outcome)]
+            } else {
+                deliver(snapshot, outcome)
+            };
+            [vec![record], next].concat()
+        }
+        (_, Input::PromptReady(outcome)) => deliver(snapshot, outcome),
+        (_, Input::Failed(error)) => {
+            let error = crate::redact::scrub(&error);
+            let mut effects = vec![Effect::ReportError(error.clone()), Effect::DismissOverlay];
+            // A failed retake returns to the prompt under review or draft
+            if snapshot.retaking {
+                effects.push(Effect::ResumeReview);
+            }
+            if snapshot.draft_open {
+                effects.push(Effect::ResumeDraft);
+            }
+            effects.push(Effect::SetStatus(Idle, format!("Error: {error}")));
+            effects
+        }
+        (_, Input::DownloadProgress(downloaded, total)) => {
+            vec![Effect::ShowDownload(downloaded, total)]
+        }
+        (_, Input::DownloadComplete) => vec![Effect::HideDownload, Effect::LoadModel],
+        (Recording, Input::Tick) => vec![Effect::ShowElapsed],
+        (_, Input::Tick) => vec![],
+    }
+}
+
+/// Make `name` the active profile and show it.
+fn select_profile(snapshot: &Snapshot, name: String) -> Vec<Effect> {
+    let Some(index) = snapshot.profiles.iter().position(|p| *p == name) else {
+        return vec![Effect::Ignore(format!("Unknown profile: {name}"))];
+    };
+    if name == snapshot.active_profile {
+        vec![Effect::ShowProfile(name, index)]
+    } else {
+        vec![
+            Effect::SetActiveProfile(name.clone()),
+            Effect::ShowProfile(name, index),
+        ]
+    }
+}
+
+/// Send a finished prompt to every sink, record it in the history and show
+/// Done.
+fn deliver(snapshot: &Snapshot, outcome: RefineOutcome) -> Vec<Effect> {
+    let text = outcome.text().to_string();
+    // A retry's raw transcript already went everywhere; typing, appending or
+    // running a command again would duplicate it, so only the clipboard is
+    // updated
+    let sinks: Vec<&OutputSink> = snapshot
+        .sinks
+        .iter()
+        .filter(|sink| !snapshot.retrying || **sink == OutputSink::Clipboard)
+        .collect();
+    let verb = if sinks.contains(&&OutputSink::Clipboard) {
+        "copied"
+    } else if sinks.contains(&&OutputSink::Type) {
+        "typed"
+    } else if !sinks.is_empty() {
+        "sent"
+    } else {
+        "saved to history"
+    };
+    // A failed refinement stays up longer to allow a retry
+    let (phase, summary, dismiss_after) = match outcome {
+        RefineOutcome::Refined(_) => (
+            OverlayPhase::Done(text.clone()),
+            format!("Prompt {verb}!"),
+            3,
+        ),
+        RefineOutcome::Fallback { .. } => (
+            OverlayPhase::Fallback(text.clone()),
+            format!("Raw transcript {verb} (refinement failed)"),
+            8,
+        ),
+    };
+
+    let mut effects = Vec::new();
+    if snapshot.sinks.is_empty() {
+        effects.push(Effect::Ignore(
+            "No output sinks configured; the prompt is only kept in the history".into(),
+        ));
+    }
+    effects.extend([
+        Effect::RecordPrompt {
+            outcome,
+            replace: snapshot.retrying,
+        },
+        Effect::ShowPhase(phase),
+        Effect::NotifyPrompt {
+            summary: summary.clone(),
+            text: text.clone(),
+        },
+        Effect::SetStatus(AppStatus::Idle, format!("Idle — {summary}")),
+        Effect::ScheduleDismiss(dismiss_after),
+    ]);
+    // Sinks go last so a failing one reports over the Done status
+    effects.extend(sinks.into_iter().map(|sink| match sink {
+        // Pasting a retry would paste a second copy
+        OutputSink::Clipboard => Effect::CopyPrompt {
+            text: text.clone(),
+            paste: !snapshot.retrying,
+        },
+        sink => Effect::Output(sink.clone(), text.clone()),
+    }));
+    effects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::refiner::RefineError;
+    use AppStatus::{Idle, ModelDownloading, Processing, Recording};
+
+    const STATUSES: [AppStatus; 4] = [Idle, Recording, Processing, ModelDownloading];
+
+    /// Idle with nothing going on, copying prompts to the clipboard.
+    fn idle() -> Snapshot {
+        Snapshot {
+            status: Idle,
+            phase: None,
+            reviewing: false,
+            review_held: false,
+            retaking: false,
+            draft_open: false,
+            draft_finishing: false,
+            has_transcript: false,
+            retrying: false,
+            sinks: vec![OutputSink::Clipboard],
+            review_profiles: Vec::new(),
+            profiles: vec!["default".into(), "commit".into(), "chat".into()],
+            active_profile: "default".into(),
+        }
+    }
+
+    fn in_status(status: AppStatus) -> Snapshot {
+        Snapshot { status, ..idle() }
+    }
+
+    fn reviewing() -> Snapshot {
+        Snapshot {
+            reviewing: true,
+            review_held: true,
+            ..idle()
+        }
+    }
+
+    fn draft(status: AppStatus) -> Snapshot {
+        Snapshot {
+            status,
+            draft_open: true,
+            phase: Some(OverlayPhase::Draft(2)),
+            ..idle()
+        }
+    }
+
+    fn with_sinks(sinks: Vec<OutputSink>) -> Snapshot {
+        Snapshot { sinks, ..idle() }
+    }
+
+    fn retrying(sinks: Vec<OutputSink>) -> Snapshot {
+        Snapshot {
+            retrying: true,
+            has_transcript: true,
+            sinks,
+            ..idle()
+        }
+    }
+
+    /// Assert the effects of `input` in each snapshot.
+    fn check(cases: Vec<(Snapshot, Input, Vec<Effect>)>) {
+        for (snapshot, input, expected) in cases {
+            assert_eq!(
+                transition(&snapshot, input.clone()),
+                expected,
+                "{input:?} in {snapshot:?}"
+            );
+        }
+    }
+
+    /// Assert that `input` has the same effects in every status.
+    fn check_every_status(base: Snapshot, input: Input, expected: Vec<Effect>) {
+        check(
+            STATUSES
+                .into_iter()
+                .map(|status| {
+                    let snapshot = Snapshot {
+                        status,
+                        ..base.clone()
+                    };
+                    (snapshot, input.clone(), expected.clone())
+                })
+                .collect(),
+        );
+    }
+
+    fn ignore(reason: &str) -> Effect {
+        Effect::Ignore(reason.into())
+    }
+
+    fn reject(reason: &str) -> Effect {
+        Effect::Reject(reason.into())
+    }
+
+    fn append_to_review() -> Effect {
+        Effect::RetakeReview {
+            text: None,
+            mode: Retake::Append,
+        }
+    }
+
+    fn refined() -> RefineOutcome {
+        RefineOutcome::Refined("Refined prompt".into())
+    }
+
+    fn fallback() -> RefineOutcome {
+        RefineOutcome::Fallback {
+            text: "raw prompt".into(),
+            reason: RefineError::Timeout,
+        }
+    }
+
+    fn info() -> RefineInfo {
+        RefineInfo {
+            profile: "careful".into(),
+            provider: "openai".into(),
+            model: "gpt-4o-mini".into(),
+            latency: Duration::from_millis(800),
+        }
+    }
+
+    #[test]
+    fn hotkey() {
+        check(vec![
+            (idle(), Input::Hotkey, vec![Effect::StartRecording]),
+            (reviewing(), Input::Hotkey, vec![append_to_review()]),
+            (draft(Idle), Input::Hotkey, vec![Effect::StartFinalTake]),
+            (
+                in_status(Recording),
+                Input::Hotkey,
+                vec![Effect::StopRecording],
+            ),
+            (
+                in_status(Processing),
+                Input::Hotkey,
+                vec![ignore("Ignoring hotkey while processing")],
+            ),
+            (
+                in_status(ModelDownloading),
+                Input::Hotkey,
+                vec![ignore("Ignoring hotkey while model_downloading")],
+            ),
+        ]);
+    }
+
+    #[test]
+    fn append_hotkey() {
+        check(vec![
+            (idle(), Input::Append, vec![Effect::StartTake]),
+            (draft(Idle), Input::Append, vec![Effect::StartTake]),
+            (reviewing(), Input::Append, vec![append_to_review()]),
+            (
+                in_status(Recording),
+                Input::Append,
+                vec![Effect::StopRecording],
+            ),
+            (draft(Recording), Input::Append, vec![Effect::StopRecording]),
+            (
+                in_status(Processing),
+                Input::Append,
+                vec![ignore("Ignoring hotkey while processing")],
+            ),
+            (
+                in_status(ModelDownloading),
+                Input::Append,
+                vec![ignore("Ignoring hotkey while model_downloading")],
+            ),
+        ]);
+    }
+
+    #[test]
+    fn start() {
+        check(vec![
+            (idle(), Input::Start, vec![Effect::StartRecording]),
+            (reviewing(), Input::Start, vec![append_to_review()]),
+            (draft(Idle), Input::Start, vec![Effect::StartFinalTake]),
+            (in_status(Recording), Input::Start, vec![]),
+            (
+                in_status(Processing),
+                Input::Start,
+                vec![reject("busy (processing)")],
+            ),
+            (
+                in_status(ModelDownloading),
+                Input::Start,
+                vec![reject("busy (model_downloading)")],
+            ),
+        ]);
+    }
+
+    #[test]
+    fn stop_and_cancel() {
+        check(vec![
+            (
+                in_status(Recording),
+                Input::Stop,
+                vec![Effect::StopRecording],
+            ),
+            (
+                in_status(Recording),
+                Input::Cancel,
+                vec![Effect::CancelRecording],
+            ),
+            (
+                draft(Recording),
+                Input::Cancel,
+                vec![Effect::CancelRecording],
+            ),
+        ]);
+        for status in [Idle, Processing, ModelDownloading] {
+            check(vec![
+                (
+                    in_status(status.clone()),
+                    Input::Stop,
+                    vec![reject("not recording")],
+                ),
+                (
+                    in_status(status),
+                    Input::Cancel,
+                    vec![reject("not recording")],
+                ),
+            ]);
+        }
+    }
+
+    #[test]
+    fn retry() {
+        let failed = Snapshot {
+            phase: Some(OverlayPhase::Fallback("raw".into())),
+            has_transcript: true,
+            ..idle()
+        };
+        check(vec![
+            (failed.clone(), Input::Retry, vec![Effect::RetryRefinement]),
+            (
+                Snapshot {
+                    has_transcript: false,
+                    ..failed.clone()
+                },
+                Input::Retry,
+                vec![ignore("Nothing to retry")],
+            ),
+            (
+                Snapshot {
+                    phase: Some(OverlayPhase::Done("done".into())),
+                    ..failed.clone()
+                },
+                Input::Retry,
+                vec![ignore("Nothing to retry")],
+            ),
+        ]);
+        for status in [Recording, Processing, ModelDownloading] {
+            let snapshot = Snapshot {
+                status,
+                ..failed.clone()
+            };
+            check(vec![(
+                snapshot,
+                Input::Retry,
+                vec![ignore("Nothing to retry")],
+            )]);
+        }
+    }
+
+    #[test]
+    fn overlay_click() {
+        let done = Snapshot {
+            phase: Some(OverlayPhase::Done("done".into())),
+            ..idle()
+        };
+        let copy_and_dismiss = vec![
+            Effect::CopyToClipboard("done".into()),
+            Effect::DismissOverlay,
+        ];
+        check_every_status(done.clone(), Input::OverlayClicked, copy_and_dismiss);
+        check_every_status(
+            Snapshot {
+                phase: Some(OverlayPhase::Fallback("raw".into())),
+                ..idle()
+            },
+            Input::OverlayClicked,
+            vec![
+                Effect::CopyToClipboard("raw".into()),
+                Effect::DismissOverlay,
+            ],
+        );
+        // Dictation kept off the clipboard is not copied again
+        check_every_status(
+            Snapshot {
+                sinks: vec![OutputSink::Type],
+                ..done
+            },
+            Input::OverlayClicked,
+            vec![Effect::DismissOverlay],
+        );
+        check_every_status(
+            draft(Idle),
+            Input::OverlayClicked,
+            vec![ignore("Draft still open")],
+        );
+        check_every_status(
+            Snapshot {
+                phase: Some(OverlayPhase::Refining),
+                ..idle()
+            },
+            Input::OverlayClicked,
+            vec![Effect::DismissOverlay],
+        );
+    }
+
+    #[test]
+    fn finish_draft() {
+        check(vec![
+            (draft(Idle), Input::FinishDraft, vec![Effect::RefineDraft]),
+            (
+                draft(Recording),
+                Input::FinishDraft,
+                vec![Effect::FinishDraftLater],
+            ),
+            (
+                draft(Processing),
+                Input::FinishDraft,
+                vec![Effect::FinishDraftLater],
+            ),
+            (
+                draft(ModelDownloading),
+                Input::FinishDraft,
+                vec![Effect::FinishDraftLater],
+            ),
+        ]);
+        check_every_status(
+            idle(),
+            Input::FinishDraft,
+            vec![ignore("No draft to finish")],
+        );
+    }
+
+    #[test]
+    fn review_window() {
+        check(vec![
+            (
+                reviewing(),
+                Input::ReviewConfirmed("edited".into()),
+                vec![Effect::ConfirmReview("edited".into())],
+            ),
+            (
+                reviewing(),
+                Input::ReviewDiscarded,
+                vec![
+                    Effect::DiscardReview,
+                    Effect::SetStatus(Idle, "Idle — Prompt discarded".into()),
+                ],
+            ),
+            (
+                reviewing(),
+                Input::ReviewRetake("edited".into(), Retake::Append),
+                vec![Effect::RetakeReview {
+                    text: Some("edited".into()),
+                    mode: Retake::Append,
+                }],
+            ),
+            (
+                reviewing(),
+                Input::ReviewRetake("edited".into(), Retake::Replace),
+                vec![Effect::RetakeReview {
+                    text: Some("edited".into()),
+                    mode: Retake::Replace,
+                }],
+            ),
+        ]);
+        for status in [Recording, Processing, ModelDownloading] {
+            let snapshot = Snapshot {
+                status: status.clone(),
+                ..reviewing()
+            };
+            let reason = format!("Ignoring retake while {}", status.name());
+            check(vec![(
+                snapshot,
+                Input::ReviewRetake("edited".into(), Retake::Append),
+                vec![Effect::Ignore(reason)],
+            )]);
+        }
+    }
+
+    #[test]
+    fn stale_review_events() {
+        // A retake in progress holds the prompt with the window closed
+        let retaking = Snapshot {
+            review_held: true,
+            retaking: true,
+            ..idle()
+        };
+        for snapshot in [idle(), retaking] {
+            for input in [
+                Input::ReviewConfirmed("edited".into()),
+                Input::ReviewDiscarded,
+                Input::ReviewRetake("edited".into(), Retake::Append),
+            ] {
+                check_every_status(
+                    snapshot.clone(),
+                    input,
+                    vec![ignore("Review window already closed")],
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn select_profile() {
+        let show_commit = vec![
+            Effect::SetActiveProfile("commit".into()),
+            Effect::ShowProfile("commit".into(), 1),
+        ];
+        check_every_status(idle(), Input::SelectProfile("commit".into()), show_commit);
+        check_every_status(
+            idle(),
+            Input::SelectProfile("default".into()),
+            vec![Effect::ShowProfile("default".into(), 0)],
+        );
+        check_every_status(
+            idle(),
+            Input::SelectProfile("gone".into()),
+            vec![ignore("Unknown profile: gone")],
+        );
+    }
+
+    #[test]
+    fn cycle_profile() {
+        check_every_status(
+            idle(),
+            Input::CycleProfile,
+            vec![
+                Effect::SetActiveProfile("commit".into()),
+                Effect::ShowProfile("commit".into(), 1),
+            ],
+        );
+        // Wraps around after the last profile
+        check_every_status(
+            Snapshot {
+                active_profile: "chat".into(),
+                ..idle()
+            },
+            Input::CycleProfile,
+            vec![
+                Effect::SetActiveProfile("default".into()),
+                Effect::ShowProfile("default".into(), 0),
+            ],
+        );
+        // An active profile that was deleted restarts at the first
+        check_every_status(
+            Snapshot {
+                active_profile: "gone".into(),
+                ..idle()
+            },
+            Input::CycleProfile,
+            vec![
+                Effect::SetActiveProfile("default".into()),
+                Effect::ShowProfile("default".into(), 0),
+            ],
+        );
+        // A single profile stays active
+        check_every_status(
+            Snapshot {
+                profiles: vec!["default".into()],
+                ..idle()
+            },
+            Input::CycleProfile,
+            vec![Effect::ShowProfile("default".into(), 0)],
+        );
+        check_every_status(
+            Snapshot {
+                profiles: Vec::new(),
+                ..idle()
+            },
+            Input::CycleProfile,
+            vec![ignore("No profiles to switch between")],
+        );
+    }
+
+    #[test]
+    fn transcript() {
+        let latency = Duration::from_secs(1);
+        let input = Input::Transcribed("a take".into(), latency);
+        check_every_status(
+            idle(),
+            input.clone(),
+            vec![
+                Effect::RecordTranscription(latency),
+                Effect::BeginRefinement("a take".into()),
+            ],
+        );
+        check_every_status(
+            draft(Processing),
+            input.clone(),
+            vec![
+                Effect::RecordTranscription(latency),
+                Effect::AddTake("a take".into()),
+                Effect::ResumeDraft,
+            ],
+        );
+        check_every_status(
+            Snapshot {
+                draft_finishing: true,
+                ..draft(Processing)
+            },
+            input,
+            vec![
+                Effect::RecordTranscription(latency),
+                Effect::AddTake("a take".into()),
+                Effect::RefineDraft,
+            ],
+        );
+    }
+
+    #[test]
+    fn delta() {
+        check_every_status(
+            Snapshot {
+                phase: Some(OverlayPhase::Refining),
+                ..idle()
+            },
+            Input::Delta("chunk".into()),
+            vec![Effect::AppendPreview("chunk".into())],
+        );
+        for phase in [
+            None,
+            Some(OverlayPhase::Recording),
+            Some(OverlayPhase::Transcribing),
+            Some(OverlayPhase::Done("done".into())),
+            Some(OverlayPhase::Fallback("raw".into())),
+            Some(OverlayPhase::Draft(2)),
+        ] {
+            check_every_status(
+                Snapshot { phase, ..idle() },
+                Input::Delta("chunk".into()),
+                vec![],
+            );
+        }
+    }
+
+    #[test]
+    fn refinement_opens_review() {
+        let record = Effect::RecordRefinement {
+            info: info(),
+            refined: true,
+        };
+        let review = vec![record.clone(), Effect::StartReview(refined())];
+        check_every_status(
+            Snapshot {
+                review_profiles: vec!["careful".into()],
+                ..idle()
+            },
+            Input::Refined(refined(), info()),
+            review.clone(),
+        );
+        // A retake's result goes back to the prompt under review
+        check_every_status(
+            Snapshot {
+                review_held: true,
+                retaking: true,
+                ..idle()
+            },
+            Input::Refined(refined(), info()),
+            review,
+        );
+        check_every_status(
+            Snapshot {
+                review_profiles: vec!["careful".into()],
+                ..idle()
+            },
+            Input::Refined(fallback(), info()),
+            vec![
+                Effect::RecordRefinement {
+                    info: info(),
+                    refined: false,
+                },
+                Effect::StartReview(fallback()),
+            ],
+        );
+    }
+
+    #[test]
+    fn refinement_is_delivered() {
+        // Another profile's review setting does not apply
+        check_every_status(
+            Snapshot {
+                review_profiles: vec!["other".into()],
+                ..idle()
+            },
+            Input::Refined(refined(), info()),
+            vec![
+                Effect::RecordRefinement {
+                    info: info(),
+                    refined: true,
+                },
+                Effect::RecordPrompt {
+                    outcome: refined(),
+                    replace: false,
+                },
+                Effect::ShowPhase(OverlayPhase::Done("Refined prompt".into())),
+                Effect::NotifyPrompt {
+                    summary: "Prompt copied!".into(),
+                    text: "Refined prompt".into(),
+                },
+                Effect::SetStatus(Idle, "Idle — Prompt copied!".into()),
+                Effect::ScheduleDismiss(3),
+                Effect::CopyPrompt {
+                    text: "Refined prompt".into(),
+                    paste: true,
+                },
+            ],
+        );
+        // A retry replaces a prompt that was delivered without review
+        check_every_status(
+            Snapshot {
+                review_profiles: vec!["careful".into()],
+                ..retrying(vec![OutputSink::Clipboard])
+            },
+            Input::Refined(refined(), info()),
+            vec![
+                Effect::RecordRefinement {
+                    info: info(),
+                    refined: true,
+                },
+                Effect::RecordPrompt {
+                    outcome: refined(),
+                    replace: true,
+                },
+                Effect::ShowPhase(OverlayPhase::Done("Refined prompt".into())),
+                Effect::NotifyPrompt {
+                    summary: "Prompt copied!".into(),
+                    text: "Refined prompt".into(),
+                },
+                Effect::SetStatus(Idle, "Idle — Prompt copied!".into()),
+                Effect::ScheduleDismiss(3),
+                Effect::CopyPrompt {
+                    text: "Refined prompt".into(),
+                    paste: false,
+                },
+            ],
+        );
+    }
+
+    #[test]
+    fn prompt_to_clipboard_and_typed() {
+        let snapshot = Snapshot {
+            sinks: vec![OutputSink::Clipboard, OutputSink::Type],
+            ..idle()
+        };
+        assert_eq!(
+            transition(&snapshot, Input::PromptReady(refined())),
+            vec![
+                Effect::RecordPrompt {
+                    outcome: refined(),
+                    replace: false,
+                },
+                Effect::ShowPhase(OverlayPhase::Done("Refined prompt".into())),
+                Effect::NotifyPrompt {
+                    summary: "Prompt copied!".into(),
+                    text: "Refined prompt".into(),
+                },
+                Effect::SetStatus(AppStatus::Idle, "Idle — Prompt copied!".into()),
+                Effect::ScheduleDismiss(3),
+                Effect::CopyPrompt {
+                    text: "Refined prompt".into(),
+                    paste: true,
+                },
+                Effect::Output(OutputSink::Type, "Refined prompt".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn fallback_typed_and_saved() {
+        let file = OutputSink::File("prompts.md".into());
+        check_every_status(
+            with_sinks(vec![OutputSink::Type, file.clone()]),
+            Input::PromptReady(fallback()),
+            vec![
+                Effect::RecordPrompt {
+                    outcome: fallback(),
+                    replace: false,
+                },
+                Effect::ShowPhase(OverlayPhase::Fallback("raw prompt".into())),
+                Effect::NotifyPrompt {
+                    summary: "Raw transcript typed (refinement failed)".into(),
+                    text: "raw prompt".into(),
+                },
+                Effect::SetStatus(
+                    Idle,
+                    "Idle — Raw transcript typed (refinement failed)".into(),
+                ),
+                Effect::ScheduleDismiss(8),
+                Effect::Output(OutputSink::Type, "raw prompt".into()),
+                Effect::Output(file, "raw prompt".into()),
+            ],
+        );
+    }
+
+    #[test]
+    fn prompt_to_stdout() {
+        check_every_status(
+            with_sinks(vec![OutputSink::Stdout]),
+            Input::PromptReady(refined()),
+            vec![
+                Effect::RecordPrompt {
+                    outcome: refined(),
+                    replace: false,
+                },
+                Effect::ShowPhase(OverlayPhase::Done("Refined prompt".into())),
+                Effect::NotifyPrompt {
+                    summary: "Prompt sent!".into(),
+                    text: "Refined prompt".into(),
+                },
+                Effect::SetStatus(Idle, "Idle — Prompt sent!".into()),
+                Effect::ScheduleDismiss(3),
+                Effect::Output(OutputSink::Stdout, "Refined prompt".into()),
+            ],
+        );
+    }
+
+    #[test]
+    fn prompt_without_sinks() {
+        check_every_status(
+            with_sinks(Vec::new()),
+            Input::PromptReady(refined()),
+            vec![
+                ignore("No output sinks configured; the prompt is only kept in the history"),
+                Effect::RecordPrompt {
+                    outcome: refined(),
+                    replace: false,
+                },
+                Effect::ShowPhase(OverlayPhase::Done("Refined prompt".into())),
+                Effect::NotifyPrompt {
+                    summary: "Prompt saved to history!".into(),
+                    text: "Refined prompt".into(),
+                },
+                Effect::SetStatus(Idle, "Idle — Prompt saved to history!".into()),
+                Effect::ScheduleDismiss(3),
+            ],
+        );
+    }
+
+    #[test]
+    fn retried_prompt_only_updates_the_clipboard() {
+        check_every_status(
+            retrying(vec![OutputSink::Clipboard, OutputSink::Type]),
+            Input::PromptReady(fallback()),
+            vec![
+                Effect::RecordPrompt {
+                    outcome: fallback(),
+                    replace: true,
+                },
+                Effect::ShowPhase(OverlayPhase::Fallback("raw prompt".into())),
+                Effect::NotifyPrompt {
+                    summary: "Raw transcript copied (refinement failed)".into(),
+                    text: "raw prompt".into(),
+                },
+                Effect::SetStatus(
+                    Idle,
+                    "Idle — Raw transcript copied (refinement failed)".into(),
+                ),
+                Effect::ScheduleDismiss(8),
+                Effect::CopyPrompt {
+                    text: "raw prompt".into(),
+                    paste: false,
+                },
+            ],
+        );
+        check_every_status(
+            retrying(vec![OutputSink::Type, OutputSink::Stdout]),
+            Input::PromptReady(refined()),
+            vec![
+                Effect::RecordPrompt {
+                    outcome: refined(),
+                    replace: true,
+                },
+                Effect::ShowPhase(OverlayPhase::Done("Refined prompt".into())),
+                Effect::NotifyPrompt {
+                    summary: "Prompt saved to history!".into(),
+                    text: "Refined prompt".into(),
+                },
+                Effect::SetStatus(Idle, "Idle — Prompt saved to history!".into()),
+                Effect::ScheduleDismiss(3),
+            ],
+        );
+    }
+
+    #[test]
+    fn failure() {
+        let input = Input::Failed("bad key=secret".into());
+        let report = Effect::ReportError("bad key=[REDACTED]".into());
+        let idle_again = Effect::SetStatus(Idle, "Error: bad key=[REDACTED]".into());
+        check_every_status(
+            idle(),
+            input.clone(),
+            vec![report.clone(), Effect::DismissOverlay, idle_again.clone()],
+        );
+        check_every_status(
+            Snapshot {
+                review_held: true,
+                retaking: true,
+                ..idle()
+            },
+            input.clone(),
+            vec![
+                report.clone(),
+                Effect::DismissOverlay,
+                Effect::ResumeReview,
+                idle_again.clone(),
+            ],
+        );
+        check_every_status(
+            draft(Processing),
+            input,
+            vec![
+                report,
+                Effect::DismissOverlay,
+                Effect::ResumeDraft,
+                idle_again,
+            ],
+        );
+    }
+
+    #[test]
+    fn typing_failure_falls_back_to_the_clipboard() {
+        let failed = || Input::TypingFailed("Refined prompt".into(), "wtype: exit 1".into());
+        check_every_status(
+            with_sinks(vec![OutputSink::Type]),
+            failed(),
+            vec![
+                Effect::CopyToClipboard("Refined prompt".into()),
+                Effect::ReportOutputFailure(
+                    "typing failed: wtype: exit 1; the prompt was copied instead".into(),
+                ),
+            ],
+        );
+        // Already on the clipboard
+        check_every_status(
+            with_sinks(vec![OutputSink::Clipboard, OutputSink::Type]),
+            failed(),
+            vec![Effect::ReportOutputFailure(
+                "typing failed: wtype: exit 1".into(),
+            )],
+        );
+    }
+
+    #[test]
+    fn model_download() {
+        check_every_status(
+            in_status(ModelDownloading),
+            Input::DownloadProgress(5, 10),
+            vec![Effect::ShowDownload(5, 10)],
+        );
+        check_every_status(
+            in_status(ModelDownloading),
+            Input::DownloadComplete,
+            vec![Effect::HideDownload, Effect::LoadModel],
+        );
+    }
+
+    #[test]
+    fn timer_tick() {
+        check(vec![
+            (in_status(Recording), Input::Tick, vec![Effect::ShowElapsed]),
+            (draft(Recording), Input::Tick, vec![Effect::ShowElapsed]),
+            (idle(), Input::Tick, vec![]),
+            (in_status(Processing), Input::Tick, vec![]),
+            (in_status(ModelDownloading), Input::Tick, vec![]),
+        ]);
+    }
+}
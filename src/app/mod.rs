@@ -1,9 +1,20 @@
+mod control;
+mod draft;
 mod event_handler;
+mod machine;
 mod model;
 mod pipeline;
 mod recording;
+mod review;
 mod state;
+mod view;
 
 pub use event_handler::handle_backend_event;
-pub use model::ensure_whisper_model;
-pub use state::{AppState, BackendEvent, OverlayPhase};
+#[cfg(feature = "ui")]
+pub use event_handler::select_profile;
+pub use model::init_transcriber;
+#[cfg(feature = "ui")]
+pub use model::dispatch_secret_store;
+#[cfg(feature = "ui")]
+pub use pipeline::{dispatch_connection_test, refine_again};
+pub use state::{AppState, BackendEvent, OverlayPhase, RefineInfo, RefineOutcome, Retake};
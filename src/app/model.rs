@@ -2,12 +2,85 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::Arc;
 
-use gtk4::glib;
-
 use super::state::{AppState, AppStatus, BackendEvent, update_status};
+use crate::config::Secrets;
+use crate::transcriber::{LocalTranscriber, Transcriber};
+
+/// Set up the configured transcription backend.
+/// The local backend downloads and/or loads the whisper model first;
+/// remote backends are ready immediately.
+pub fn init_transcriber(state: &Rc<RefCell<AppState>>) {
+    let config = state.borrow().config.transcription.clone();
+    if crate::transcriber::needs_local_model(&config) {
+        ensure_whisper_model(state);
+    } else {
+        match crate::transcriber::build_remote(&config) {
+            Ok(transcriber) => {
+                log::info!(
+                    "Using remote transcription at {}",
+                    crate::redact::scrub(&config.server_url)
+                );
+                set_transcriber(state, Arc::new(transcriber));
+            }
+            Err(e) => {
+                log::error!("Failed to set up remote transcription: {e}");
+                update_status(
+                    state,
+                    AppStatus::Idle,
+                    &format!("Error: remote transcription unavailable: {e}"),
+                );
+            }
+        }
+    }
+}
+
+/// Start using the API keys read from the secret store. A remote
+/// transcriber set up before they arrived is rebuilt with its token.
+pub fn secrets_loaded(state: &Rc<RefCell<AppState>>, secrets: Secrets) {
+    let mut guard = state.borrow_mut();
+    let s = &mut *guard;
+    s.config.apply_secrets(secrets);
+    s.view.show_api_key(&s.config.gemini_api_key);
+    let config = &s.config.transcription;
+    if s.transcriber.is_some() && !crate::transcriber::needs_local_model(config) {
+        match crate::transcriber::build_remote(config) {
+            Ok(transcriber) => s.transcriber = Some(Arc::new(transcriber)),
+            Err(e) => log::warn!("Keeping the remote transcriber without its token: {e}"),
+        }
+    }
+}
+
+/// Store an API key entered in the dashboard. Like reading the keys at
+/// startup this can block on the keyring, so it runs off the main thread
+/// and reports back with `BackendEvent::SecretStored`.
+#[cfg(feature = "ui")]
+pub fn dispatch_secret_store(state: &Rc<RefCell<AppState>>, name: &'static str, value: String) {
+    let s = state.borrow();
+    let sender = s.backend_sender.clone();
+    s.tokio_rt.spawn_blocking(move || {
+        let result = crate::config::store_secret(name, &value);
+        let _ = sender.send_blocking(BackendEvent::SecretStored(name, value, result));
+    });
+}
+
+/// Start using an API key once the secret store has it.
+#[cfg(feature = "ui")]
+pub fn secret_stored(
+    state: &Rc<RefCell<AppState>>,
+    name: &str,
+    value: &str,
+    result: Result<(), String>,
+) {
+    let mut s = state.borrow_mut();
+    match result {
+        Ok(()) => s.config.set_secret(name, value),
+        Err(ref e) => log::warn!("Failed to store {name}: {e}"),
+    }
+    s.view.show_secret_stored(&result);
+}
 
 /// Attempt to download and/or load the whisper model.
-pub fn ensure_whisper_model(state: &Rc<RefCell<AppState>>) {
+fn ensure_whisper_model(state: &Rc<RefCell<AppState>>) {
     if crate::transcriber::model_exists() {
         load_whisper_model(state);
     } else {
@@ -47,18 +120,19 @@ pub fn load_whisper_model(state: &Rc<RefCell<AppState>>) {
     update_status(state, AppStatus::Processing, "Loading model...");
 
     let sender = state.borrow().backend_sender.clone();
+    let language = state.borrow().config.transcription.language.clone();
 
     // We can't send Rc<RefCell> into tokio, so use a separate channel
-    // to pass the loaded context back to the main thread.
-    let (ctx_tx, ctx_rx) = async_channel::bounded::<whisper_rs::WhisperContext>(1);
+    // to pass the loaded transcriber back to the main thread.
+    let (ctx_tx, ctx_rx) = async_channel::bounded::<LocalTranscriber>(1);
 
     state.borrow().tokio_rt.spawn(async move {
         let result =
-            tokio::task::spawn_blocking(|| crate::transcriber::load_model()).await;
+            tokio::task::spawn_blocking(move || LocalTranscriber::load(&language)).await;
 
         match result {
-            Ok(Ok(ctx)) => {
-                let _ = ctx_tx.send(ctx).await;
+            Ok(Ok(transcriber)) => {
+                let _ = ctx_tx.send(transcriber).await;
             }
             Ok(Err(e)) => {
                 let _ = sender
@@ -77,16 +151,18 @@ pub fn load_whisper_model(state: &Rc<RefCell<AppState>>) {
         }
     });
 
-    // Receive the loaded context on the GTK main thread
+    // Receive the loaded transcriber on the main thread
     let state_clone = state.clone();
     glib::spawn_future_local(async move {
-        if let Ok(ctx) = ctx_rx.recv().await {
-            state_clone.borrow_mut().whisper_ctx = Some(Arc::new(ctx));
-            if let Some(ref dash) = state_clone.borrow().dashboard {
-                dash.status_label.set_text("Idle");
-            }
-            state_clone.borrow_mut().status = AppStatus::Idle;
+        if let Ok(transcriber) = ctx_rx.recv().await {
+            set_transcriber(&state_clone, Arc::new(transcriber));
             log::info!("Whisper model ready");
         }
     });
 }
+
+/// Install a ready transcriber and go idle.
+fn set_transcriber(state: &Rc<RefCell<AppState>>, transcriber: Arc<dyn Transcriber>) {
+    state.borrow_mut().transcriber = Some(transcriber);
+    update_status(state, AppStatus::Idle, "Idle");
+}
@@ -1,42 +1,42 @@
 use std::cell::RefCell;
+use std::future::Future;
 use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use super::state::{AppState, AppStatus, BackendEvent, update_status};
+use super::state::{AppState, AppStatus, BackendEvent, RefineInfo, RefineOutcome, update_status};
+use crate::clipboard::Snapshot;
+use crate::config::{Config, OutputSink, RefinementProfile};
+use crate::keyboard::Chord;
+use crate::refiner::{OnDelta, RefineError};
 
-/// Dispatch whisper transcription on the tokio runtime.
+/// Dispatch transcription on the tokio runtime using the configured backend.
 pub fn dispatch_transcription(state: &Rc<RefCell<AppState>>, samples: Vec<f32>) {
     let s = state.borrow();
-    let ctx = match &s.whisper_ctx {
-        Some(ctx) => ctx.clone(),
+    let transcriber = match &s.transcriber {
+        Some(t) => t.clone(),
         None => {
             drop(s);
-            update_status(state, AppStatus::Idle, "Whisper model not loaded");
+            update_status(state, AppStatus::Idle, "Transcriber not ready");
             return;
         }
     };
     let sender = s.backend_sender.clone();
+    let sample_rate = s.sample_rate;
 
     s.tokio_rt.spawn(async move {
-        let result = tokio::task::spawn_blocking(move || {
-            crate::transcriber::transcribe(&ctx, &samples)
-        })
-        .await;
-
-        match result {
-            Ok(Ok(text)) => {
-                let _ = sender.send(BackendEvent::TranscriptionComplete(text)).await;
-            }
-            Ok(Err(e)) => {
+        let started = Instant::now();
+        match transcriber.transcribe(samples, sample_rate).await {
+            Ok(text) => {
                 let _ = sender
-                    .send(BackendEvent::ProcessingError(format!(
-                        "Transcription failed: {e}"
-                    )))
+                    .send(BackendEvent::TranscriptionComplete(text, started.elapsed()))
                     .await;
             }
             Err(e) => {
                 let _ = sender
                     .send(BackendEvent::ProcessingError(format!(
-                        "Transcription task panicked: {e}"
+                        "Transcription failed ({}): {e}",
+                        transcriber.name()
                     )))
                     .await;
             }
@@ -44,23 +44,252 @@ pub fn dispatch_transcription(state: &Rc<RefCell<AppState>>, samples: Vec<f32>)
     });
 }
 
-/// Dispatch Gemini refinement on the tokio runtime.
+/// Press the paste shortcut `chord` in the focused window once the paste
+/// delay has passed, then put `previous` back on the clipboard unless
+/// something other than `prompt` was copied meanwhile. Failures are
+/// reported as `OutputFailed`.
+pub fn dispatch_paste(
+    state: &Rc<RefCell<AppState>>,
+    chord: String,
+    prompt: String,
+    previous: Option<Snapshot>,
+) {
+    let s = state.borrow();
+    let backend = s.config.paste.backend;
+    let delay = Duration::from_millis(s.config.paste.delay_ms);
+    let restore_delay = Duration::from_millis(s.config.paste.restore_delay_ms);
+    let sender = s.backend_sender.clone();
+    s.tokio_rt.spawn_blocking(move || {
+        std::thread::sleep(delay);
+        let result: Result<(), Box<dyn std::error::Error + Send + Sync>> = Chord::parse(&chord)
+            .map_err(Into::into)
+            .and_then(|chord| crate::keyboard::send_chord(backend, &chord));
+        if let Err(e) = result {
+            // Leave the prompt on the clipboard to paste by hand
+            let _ = sender.send_blocking(BackendEvent::OutputFailed(format!(
+                "auto-paste failed: {e}"
+            )));
+            return;
+        }
+        let Some(previous) = previous else {
+            return;
+        };
+        std::thread::sleep(restore_delay);
+        let result = crate::clipboard::clipboard_holds(&prompt).and_then(|holds| {
+            if holds {
+                crate::clipboard::restore_clipboard(&previous)
+            } else {
+                log::info!("Clipboard changed since pasting, not restoring it");
+                Ok(())
+            }
+        });
+        if let Err(e) = result {
+            let _ = sender.send_blocking(BackendEvent::OutputFailed(format!(
+                "restoring the clipboard failed: {e}"
+            )));
+        }
+    });
+}
+
+/// Send `text` to a file, FIFO, stdout or command sink on its own thread,
+/// so a slow sink doesn't hold up the others. Failures are reported as
+/// `OutputFailed`.
+pub fn dispatch_sink(state: &Rc<RefCell<AppState>>, sink: OutputSink, text: String) {
+    let s = state.borrow();
+    let details = s.prompt_details.clone();
+    let sender = s.backend_sender.clone();
+    s.tokio_rt.spawn_blocking(move || {
+        if let Err(e) = crate::output::deliver(&sink, &text, &details) {
+            let name = crate::output::describe(&sink);
+            let _ = sender.send_blocking(BackendEvent::OutputFailed(format!("{name}: {e}")));
+        }
+    });
+}
+
+/// Type `text` into the focused window once the paste delay has passed.
+/// Failures are reported as `TypingFailed`.
+pub fn dispatch_typing(state: &Rc<RefCell<AppState>>, text: String) {
+    let s = state.borrow();
+    let backend = s.config.paste.backend;
+    let delay = Duration::from_millis(s.config.paste.delay_ms);
+    let newline = s.config.output.newline_chord.clone();
+    let sender = s.backend_sender.clone();
+    s.tokio_rt.spawn_blocking(move || {
+        std::thread::sleep(delay);
+        let result: Result<(), Box<dyn std::error::Error + Send + Sync>> = Chord::parse(&newline)
+            .map_err(|e| format!("invalid newline_chord: {e}").into())
+            .and_then(|newline| crate::keyboard::type_text(backend, &text, &newline));
+        if let Err(e) = result {
+            let _ = sender.send_blocking(BackendEvent::TypingFailed(text, e.to_string()));
+        }
+    });
+}
+
+/// Dispatch refinement on the tokio runtime using the active profile.
 pub fn dispatch_refinement(state: &Rc<RefCell<AppState>>, transcript: String) {
     let s = state.borrow();
-    let api_key = s.config.gemini_api_key.clone();
+    let profile = s.config.active_profile();
     let sender = s.backend_sender.clone();
+    let on_delta = s.config.refinement.stream.then(|| {
+        let delta_sender = sender.clone();
+        let on_delta: OnDelta = Arc::new(move |delta: &str| {
+            let _ = delta_sender.try_send(BackendEvent::RefinementDelta(delta.to_string()));
+        });
+        on_delta
+    });
+    let refine = refine(&s.config, &profile, &transcript, on_delta);
+
+    s.tokio_rt.spawn(async move {
+        let (outcome, info) = refine.await;
+        let _ = sender
+            .send(BackendEvent::RefinementComplete(outcome, info))
+            .await;
+    });
+}
+
+/// Refine `transcript` with the named profile outside the recording
+/// pipeline (history "Refine again…"), optionally with another provider
+/// or model. `on_done` runs on the main loop.
+#[cfg(feature = "ui")]
+pub fn refine_again<F>(
+    state: &Rc<RefCell<AppState>>,
+    transcript: &str,
+    profile: &str,
+    provider: Option<crate::config::RefinementProvider>,
+    model: &str,
+    on_done: F,
+) where
+    F: FnOnce(RefineOutcome, RefineInfo) + 'static,
+{
+    let s = state.borrow();
+    let mut profile = s
+        .config
+        .profile(profile)
+        .cloned()
+        .unwrap_or_else(|| s.config.active_profile());
+    if let Some(provider) = provider.filter(|&p| p != profile.provider) {
+        // The profile's model belongs to its own provider
+        profile.provider = provider;
+        profile.model = String::new();
+    }
+    if !model.is_empty() {
+        profile.model = model.to_string();
+    }
+    let refine = refine(&s.config, &profile, transcript, None);
+    let (tx, rx) = async_channel::bounded(1);
 
     s.tokio_rt.spawn(async move {
-        match crate::refiner::refine(&api_key, &transcript).await {
-            Ok(refined) => {
-                let _ = sender.send(BackendEvent::RefinementComplete(refined)).await;
+        let _ = tx.send(refine.await).await;
+    });
+    glib::spawn_future_local(async move {
+        if let Ok((outcome, info)) = rx.recv().await {
+            on_done(outcome, info);
+        }
+    });
+}
+
+/// Run spoken commands, the optional pre-pass and the profile's refiner,
+/// falling back to the prepared transcript on failure or timeout.
+fn refine(
+    config: &Config,
+    profile: &RefinementProfile,
+    transcript: &str,
+    on_delta: Option<OnDelta>,
+) -> impl Future<Output = (RefineOutcome, RefineInfo)> + Send + 'static {
+    let refiner = crate::refiner::build(config, profile);
+    let transcript = crate::commands::apply(&config.commands, transcript);
+    let transcript = crate::refiner::prepare(config, &transcript);
+    let fallback_after = config.refinement.fallback_after_secs;
+    let profile_name = profile.name.clone();
+
+    async move {
+        let refiner = match refiner {
+            Ok(refiner) => refiner,
+            Err(e) => {
+                log::error!("Refinement ({profile_name}) unavailable, using raw transcript: {e}");
+                let info = RefineInfo {
+                    profile: profile_name,
+                    provider: String::new(),
+                    model: String::new(),
+                    latency: Duration::ZERO,
+                };
+                let outcome = RefineOutcome::Fallback {
+                    text: transcript,
+                    reason: e,
+                };
+                return (outcome, info);
             }
+        };
+        let started = Instant::now();
+        let refine = match on_delta {
+            Some(on_delta) => refiner.refine_streaming(&transcript, on_delta),
+            None => refiner.refine(&transcript),
+        };
+        // Never leave the user waiting on a slow provider
+        let result = if fallback_after > 0 {
+            tokio::time::timeout(Duration::from_secs(fallback_after), refine)
+                .await
+                .unwrap_or(Err(RefineError::Timeout))
+        } else {
+            refine.await
+        };
+
+        let info = RefineInfo {
+            profile: profile_name,
+            provider: refiner.name().to_string(),
+            model: refiner.model().to_string(),
+            latency: started.elapsed(),
+        };
+        let outcome = match result {
+            Ok(refined) => RefineOutcome::Refined(refined),
             Err(e) => {
-                log::warn!("Refinement failed, using raw transcript: {e}");
-                let _ = sender
-                    .send(BackendEvent::RefinementComplete(transcript))
-                    .await;
+                log::warn!(
+                    "Refinement via {} ({}) failed [{}], using raw transcript: {e}",
+                    info.provider,
+                    info.profile,
+                    e.kind()
+                );
+                RefineOutcome::Fallback {
+                    text: transcript,
+                    reason: e,
+                }
             }
-        }
+        };
+        (outcome, info)
+    }
+}
+
+/// Check the active profile's provider with a cheap authenticated request.
+/// `gemini_api_key` is the key currently typed in the dashboard, which may
+/// not be stored yet.
+#[cfg(feature = "ui")]
+pub fn dispatch_connection_test(state: &Rc<RefCell<AppState>>, gemini_api_key: String) {
+    let s = state.borrow();
+    let mut config = s.config.clone();
+    config.gemini_api_key = gemini_api_key;
+    let profile = config.active_profile();
+    let provider = profile.provider;
+    let sender = s.backend_sender.clone();
+
+    if provider == crate::config::RefinementProvider::Gemini && config.gemini_api_key.is_empty() {
+        let _ = sender.try_send(BackendEvent::ConnectionTested(
+            provider.label().to_string(),
+            Err(RefineError::Auth("no API key entered".into())),
+        ));
+        return;
+    }
+
+    let refiner = crate::refiner::build(&config, &profile);
+    s.tokio_rt.spawn(async move {
+        let result = match refiner {
+            Ok(refiner) => refiner.list_models().await,
+            Err(e) => Err(e),
+        };
+        let _ = sender
+            .send(BackendEvent::ConnectionTested(
+                provider.label().to_string(),
+                result,
+            ))
+            .await;
     });
 }
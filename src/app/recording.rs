@@ -3,12 +3,12 @@ use std::rc::Rc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
-use gtk4::glib;
-use gtk4::prelude::*;
-
+use super::draft::resume_draft;
+use super::event_handler::dismiss_overlay;
 use super::pipeline::dispatch_transcription;
+use super::review::resume_review;
 use super::state::{AppState, AppStatus, BackendEvent, OverlayPhase, update_status};
-use crate::ui::overlay::set_overlay_phase;
+use crate::stats::PromptDetails;
 
 /// Start recording audio from the microphone.
 pub fn start_recording(state: &Rc<RefCell<AppState>>) {
@@ -35,17 +35,8 @@ pub fn start_recording(state: &Rc<RefCell<AppState>>) {
             s.cpal_stream = Some(stream);
             s.sample_rate = sample_rate;
             s.recording_start = Some(std::time::Instant::now());
-            s.status = AppStatus::Recording;
             s.overlay_phase = Some(OverlayPhase::Recording);
-
-            if let Some(ref overlay) = s.overlay {
-                overlay.timer_label.set_text("00:00");
-                set_overlay_phase(overlay, &OverlayPhase::Recording);
-                overlay.window.set_visible(true);
-            }
-            if let Some(ref dash) = s.dashboard {
-                dash.status_label.set_text("Recording...");
-            }
+            s.view.show_overlay(&OverlayPhase::Recording);
         }
         Err(e) => {
             log::error!("Failed to start recording: {e}");
@@ -53,6 +44,7 @@ pub fn start_recording(state: &Rc<RefCell<AppState>>) {
             return;
         }
     }
+    update_status(state, AppStatus::Recording, "Recording...");
 
     // Start 80ms tick for waveform updates (~12fps).
     let sender = state.borrow().backend_sender.clone();
@@ -88,6 +80,28 @@ fn compute_rms(buffer: &Arc<std::sync::Mutex<Vec<f32>>>) -> f32 {
     (sum_sq / n as f32).sqrt()
 }
 
+/// Stop recording and throw the audio away.
+pub fn cancel_recording(state: &Rc<RefCell<AppState>>) {
+    log::info!("Cancelling recording");
+
+    if let Some(source) = state.borrow_mut().timer_source.take() {
+        source.remove();
+    }
+    {
+        let mut s = state.borrow_mut();
+        s.cpal_stream = None;
+        s.audio_buffer.lock().unwrap().clear();
+    }
+
+    crate::audio_feedback::play_beep(crate::audio_feedback::BeepType::Stop);
+
+    dismiss_overlay(state);
+    // A cancelled retake or draft take leaves the earlier text as it was
+    resume_review(state);
+    resume_draft(state);
+    update_status(state, AppStatus::Idle, "Recording cancelled");
+}
+
 /// Stop recording and dispatch transcription.
 pub fn stop_recording(state: &Rc<RefCell<AppState>>) {
     log::info!("Stopping recording");
@@ -103,10 +117,13 @@ pub fn stop_recording(state: &Rc<RefCell<AppState>>) {
     // Transition overlay to Transcribing instead of hiding
     {
         let mut s = state.borrow_mut();
+        let recorded = s.recording_start.map(|t| t.elapsed()).unwrap_or_default();
+        s.prompt_details = PromptDetails {
+            recording_ms: recorded.as_millis() as u64,
+            ..PromptDetails::default()
+        };
         s.overlay_phase = Some(OverlayPhase::Transcribing);
-        if let Some(ref overlay) = s.overlay {
-            set_overlay_phase(overlay, &OverlayPhase::Transcribing);
-        }
+        s.view.show_phase(&OverlayPhase::Transcribing);
     }
 
     update_status(state, AppStatus::Processing, "Transcribing...");
@@ -118,10 +135,10 @@ pub fn stop_recording(state: &Rc<RefCell<AppState>>) {
         // Nothing captured — dismiss overlay and go idle
         let mut s = state.borrow_mut();
         s.overlay_phase = None;
-        if let Some(ref overlay) = s.overlay {
-            overlay.window.set_visible(false);
-        }
+        s.view.hide_overlay();
         drop(s);
+        resume_review(state);
+        resume_draft(state);
         update_status(state, AppStatus::Idle, "No audio captured");
         return;
     }
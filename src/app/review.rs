@@ -0,0 +1,156 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use super::event_handler::{dismiss_overlay, handle_input};
+use super::machine::Input;
+use super::recording::start_recording;
+use super::state::{update_status, AppState, AppStatus, RefineOutcome, Retake, Review};
+use crate::stats::PromptDetails;
+
+/// Hold `outcome` back for editing. If it is a retake, it is first appended
+/// to, or replaces, the prompt under review.
+pub fn start_review(state: &Rc<RefCell<AppState>>, outcome: RefineOutcome) {
+    {
+        let mut s = state.borrow_mut();
+        let mut details = s.prompt_details.clone();
+        let outcome = match s.review.take() {
+            Some(Review {
+                outcome: previous,
+                details: previous_details,
+                retake: Some(Retake::Append),
+            }) => {
+                details = append_details(previous_details, details);
+                append_outcome(previous, outcome)
+            }
+            _ => outcome,
+        };
+        s.prompt_details = details.clone();
+        s.review = Some(Review {
+            outcome,
+            details,
+            retake: None,
+        });
+    }
+    open_review(state);
+}
+
+/// Copy the reviewed prompt, with the user's edits.
+pub fn confirm_review(state: &Rc<RefCell<AppState>>, text: String) {
+    let outcome = {
+        let mut s = state.borrow_mut();
+        s.view.close_review();
+        let Some(review) = s.review.take() else {
+            return;
+        };
+        s.prompt_details = review.details;
+        let mut outcome = review.outcome;
+        outcome.set_text(text);
+        outcome
+    };
+    let _ = handle_input(state, Input::PromptReady(outcome));
+}
+
+/// Drop the prompt under review without copying it.
+pub fn discard_review(state: &Rc<RefCell<AppState>>) {
+    let mut s = state.borrow_mut();
+    s.view.close_review();
+    s.review = None;
+    log::info!("Prompt discarded in review");
+}
+
+/// Close the review window and record another take. `text` is the prompt
+/// as edited so far, or `None` to take it from the window; the take's
+/// result is appended to it or replaces it.
+pub fn retake(state: &Rc<RefCell<AppState>>, text: Option<String>, mode: Retake) {
+    {
+        let mut s = state.borrow_mut();
+        let text = text.or_else(|| s.view.review_text());
+        s.view.close_review();
+        let Some(review) = s.review.as_mut() else {
+            return;
+        };
+        if let Some(text) = text {
+            review.outcome.set_text(text);
+        }
+        review.retake = Some(mode);
+    }
+    start_recording(state);
+    if state.borrow().status != AppStatus::Recording {
+        resume_review(state);
+    }
+}
+
+/// Reopen the review window after a retake that produced nothing (no
+/// audio, or an error), so the earlier text is not lost.
+pub fn resume_review(state: &Rc<RefCell<AppState>>) {
+    {
+        let mut s = state.borrow_mut();
+        if s.view.reviewing() {
+            return;
+        }
+        let details = match s.review.as_mut() {
+            Some(review) if review.retake.take().is_some() => review.details.clone(),
+            _ => return,
+        };
+        s.prompt_details = details;
+    }
+    open_review(state);
+}
+
+fn open_review(state: &Rc<RefCell<AppState>>) {
+    dismiss_overlay(state);
+    {
+        let mut s = state.borrow_mut();
+        let Some(ref review) = s.review else {
+            return;
+        };
+        let failure = match review.outcome {
+            RefineOutcome::Fallback { ref reason, .. } => Some(reason.to_string()),
+            RefineOutcome::Refined(_) => None,
+        };
+        let text = review.outcome.text().to_string();
+        let profile = review.details.profile.clone();
+        let sender = s.backend_sender.clone();
+        s.view
+            .open_review(&text, &profile, failure.as_deref(), sender);
+    }
+    update_status(state, AppStatus::Idle, "Idle — Reviewing prompt");
+}
+
+/// Join two takes: a space within a sentence-length prompt, a blank line
+/// once either has structure.
+fn join_takes(first: &str, second: &str) -> String {
+    if first.trim().is_empty() {
+        return second.to_string();
+    }
+    if second.trim().is_empty() {
+        return first.to_string();
+    }
+    let sep = if first.contains('\n') || second.contains('\n') {
+        "\n\n"
+    } else {
+        " "
+    };
+    format!("{}{sep}{}", first.trim_end(), second.trim_start())
+}
+
+/// The combined prompt counts as refined only if both takes were.
+fn append_outcome(previous: RefineOutcome, next: RefineOutcome) -> RefineOutcome {
+    let text = join_takes(previous.text(), next.text());
+    match (previous, next) {
+        (RefineOutcome::Refined(_), RefineOutcome::Refined(_)) => RefineOutcome::Refined(text),
+        (RefineOutcome::Fallback { reason, .. }, _)
+        | (_, RefineOutcome::Fallback { reason, .. }) => RefineOutcome::Fallback { text, reason },
+    }
+}
+
+fn append_details(previous: PromptDetails, next: PromptDetails) -> PromptDetails {
+    PromptDetails {
+        raw: join_takes(&previous.raw, &next.raw),
+        refined: previous.refined && next.refined,
+        recording_ms: previous.recording_ms + next.recording_ms,
+        transcription_ms: previous.transcription_ms + next.transcription_ms,
+        refinement_ms: previous.refinement_ms + next.refinement_ms,
+        ..next
+    }
+}
@@ -1,24 +1,130 @@
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use gtk4::glib;
+use super::view::View;
+use crate::config::{Config, Secrets};
+use crate::control::{Command, Events, Reply};
+use crate::dbus::DbusService;
+use crate::hotkey::HotkeyBindings;
+use crate::refiner::RefineError;
+use crate::stats::{PromptDetails, Stats};
+use crate::transcriber::Transcriber;
 
-use crate::config::{Config, HotkeyConfig};
-use crate::stats::Stats;
-use crate::ui::dashboard::DashboardWidgets;
-use crate::ui::overlay::OverlayWidgets;
-
-/// Events sent from background threads to the GTK main thread.
+/// Events sent from background threads to the main thread.
+// The overlay and review window send some of these, so headless builds
+// handle variants they never construct
+#[cfg_attr(not(feature = "ui"), allow(dead_code))]
 #[derive(Debug, Clone)]
 pub enum BackendEvent {
     HotkeyTriggered,
-    TranscriptionComplete(String),
-    RefinementComplete(String),
+    /// Append hotkey: start or stop a take that is added to the draft.
+    AppendTriggered,
+    /// Refine the draft now (overlay Finish button).
+    FinishDraft,
+    /// Make the named refinement profile active.
+    SelectProfile(String),
+    /// Switch to the next refinement profile (overlay button).
+    CycleProfile,
+    /// Transcript and how long transcription took.
+    TranscriptionComplete(String, Duration),
+    /// A chunk of refined text streamed from the provider.
+    RefinementDelta(String),
+    RefinementComplete(RefineOutcome, RefineInfo),
+    /// Run refinement again on the last transcript (overlay retry button).
+    RetryRefinement,
+    /// Result of the dashboard's connection test for the named provider.
+    #[cfg(feature = "ui")]
+    ConnectionTested(String, Result<Vec<String>, RefineError>),
+    /// Copy the (edited) text from the review window (Enter).
+    ReviewConfirmed(String),
+    /// Review window closed without copying (Esc).
+    ReviewDiscarded,
+    /// Record another take; the current text is kept and extended, or replaced.
+    ReviewRetake(String, Retake),
     ProcessingError(String),
+    /// Auto-paste or a sink failed.
+    OutputFailed(String),
+    /// No keyboard backend could type the prompt (text, reason).
+    TypingFailed(String, String),
+    /// API keys read from the secret store at startup.
+    SecretsLoaded(Secrets),
+    /// An API key entered in the dashboard was stored (or cleared): its
+    /// name, value and whether the secret store accepted it.
+    #[cfg(feature = "ui")]
+    SecretStored(&'static str, String, Result<(), String>),
     ModelDownloadProgress(u64, u64),
     ModelDownloadComplete,
     TimerTick,
     AudioLevel(f32),
     OverlayClicked,
+    /// Command from the control socket or D-Bus; the answer goes to the
+    /// reply channel.
+    Control(Command, Reply),
+}
+
+/// What the refinement step produced.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RefineOutcome {
+    Refined(String),
+    /// Refinement failed; `text` is the raw transcript (after spoken
+    /// commands and the optional pre-pass).
+    Fallback { text: String, reason: RefineError },
+}
+
+impl RefineOutcome {
+    pub fn text(&self) -> &str {
+        match self {
+            Self::Refined(text) | Self::Fallback { text, .. } => text,
+        }
+    }
+
+    pub fn is_refined(&self) -> bool {
+        matches!(self, Self::Refined(_))
+    }
+
+    pub fn set_text(&mut self, new_text: String) {
+        match self {
+            Self::Refined(text) | Self::Fallback { text, .. } => *text = new_text,
+        }
+    }
+}
+
+/// What a new take does to the prompt under review.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Retake {
+    Append,
+    /// Only the review window's Re-record button asks for this
+    #[cfg_attr(not(feature = "ui"), allow(dead_code))]
+    Replace,
+}
+
+/// A prompt held back in the review window before it is copied.
+#[derive(Debug, Clone)]
+pub struct Review {
+    /// Text under review, with the user's edits so far
+    pub outcome: RefineOutcome,
+    pub details: PromptDetails,
+    /// Set while another take is being recorded
+    pub retake: Option<Retake>,
+}
+
+/// Which refiner ran and how long it took.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefineInfo {
+    pub profile: String,
+    pub provider: String,
+    pub model: String,
+    pub latency: Duration,
+}
+
+/// Takes collected in append mode, refined together once finished.
+pub struct Draft {
+    pub takes: Vec<String>,
+    /// Timings summed over the takes
+    pub details: PromptDetails,
+    /// The take being recorded is the last one; refine when it arrives
+    pub finishing: bool,
+    pub finish_source: Option<glib::SourceId>,
 }
 
 /// Application status.
@@ -30,6 +136,18 @@ pub enum AppStatus {
     ModelDownloading,
 }
 
+impl AppStatus {
+    /// Name used by the control socket.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Idle => "idle",
+            Self::Recording => "recording",
+            Self::Processing => "processing",
+            Self::ModelDownloading => "model_downloading",
+        }
+    }
+}
+
 /// Overlay pipeline phase.
 #[derive(Debug, Clone, PartialEq)]
 pub enum OverlayPhase {
@@ -37,18 +155,28 @@ pub enum OverlayPhase {
     Transcribing,
     Refining,
     Done(String),
+    /// Raw transcript copied because refinement failed; offers a retry.
+    Fallback(String),
+    /// Waiting for the next take of a draft; holds the number of takes so far.
+    Draft(usize),
 }
 
-/// Central application state. Lives on the GTK main thread inside Rc<RefCell<>>.
+/// Central application state. Lives on the main thread inside Rc<RefCell<>>.
 pub struct AppState {
     pub status: AppStatus,
+    /// Status line shown in the dashboard
+    pub status_text: String,
     pub config: Config,
     pub stats: Stats,
     pub audio_buffer: Arc<Mutex<Vec<f32>>>,
-    pub shared_hotkey: Arc<Mutex<HotkeyConfig>>,
+    pub shared_hotkey: Arc<Mutex<HotkeyBindings>>,
     pub tokio_rt: tokio::runtime::Runtime,
-    pub whisper_ctx: Option<Arc<whisper_rs::WhisperContext>>,
+    pub transcriber: Option<Arc<dyn Transcriber>>,
     pub backend_sender: async_channel::Sender<BackendEvent>,
+    /// Events for control socket subscribers
+    pub control_events: Option<Events>,
+    /// Exported D-Bus interface, for its StatusChanged signal
+    pub dbus: Option<DbusService>,
 
     // Recording state
     pub cpal_stream: Option<cpal::Stream>,
@@ -58,40 +186,77 @@ pub struct AppState {
 
     // Overlay phase tracking
     pub overlay_phase: Option<OverlayPhase>,
+    /// Refined text received so far while streaming
+    pub refine_preview: String,
+    /// Metadata of the recording in flight (or the last one, for retries)
+    pub prompt_details: PromptDetails,
+    /// The running refinement retries the last history entry
+    pub retrying: bool,
+    /// Prompt waiting for review (profiles with review enabled)
+    pub review: Option<Review>,
+    /// Prompt being dictated over several takes (append mode)
+    pub draft: Option<Draft>,
     pub overlay_dismiss_source: Option<glib::SourceId>,
 
-    // UI handles
-    pub dashboard: Option<DashboardWidgets>,
-    pub overlay: Option<OverlayWidgets>,
+    /// Windows, or notifications in headless builds
+    pub view: View,
 }
 
 impl AppState {
     pub fn new(sender: async_channel::Sender<BackendEvent>) -> Self {
-        let config = Config::load();
+        let mut config = Config::load();
         let stats = Stats::load();
-        let shared_hotkey = Arc::new(Mutex::new(config.hotkey.clone()));
+        let shared_hotkey = Arc::new(Mutex::new(HotkeyBindings::from_config(&config)));
         let tokio_rt = tokio::runtime::Runtime::new()
             .expect("Failed to create tokio runtime");
 
+        // The Secret Service can block, e.g. on an unlock prompt
+        let pending = config.pending_secrets();
+        let secrets_sender = sender.clone();
+        tokio_rt.spawn_blocking(move || {
+            let secrets = crate::config::fetch_secrets(pending);
+            let _ = secrets_sender.send_blocking(BackendEvent::SecretsLoaded(secrets));
+        });
+
         Self {
             status: AppStatus::Idle,
+            status_text: String::new(),
             config,
             stats,
             audio_buffer: Arc::new(Mutex::new(Vec::new())),
             shared_hotkey,
             tokio_rt,
-            whisper_ctx: None,
+            transcriber: None,
             backend_sender: sender,
+            control_events: None,
+            dbus: None,
             cpal_stream: None,
             recording_start: None,
             timer_source: None,
             sample_rate: 16000,
             overlay_phase: None,
+            refine_preview: String::new(),
+            prompt_details: PromptDetails::default(),
+            retrying: false,
+            review: None,
+            draft: None,
             overlay_dismiss_source: None,
-            dashboard: None,
-            overlay: None,
+            view: View::default(),
+        }
+    }
+
+    /// Send an event to control socket subscribers.
+    pub fn emit(&self, event: serde_json::Value) {
+        if let Some(ref events) = self.control_events {
+            events.emit(event);
         }
     }
+
+    /// Push the configured hotkeys to the listener thread.
+    #[cfg(feature = "ui")]
+    pub fn sync_hotkey_bindings(&self) {
+        *self.shared_hotkey.lock().unwrap() = HotkeyBindings::from_config(&self.config);
+    }
 }
 
 /// Helper to update status label and state.
@@ -102,7 +267,14 @@ pub fn update_status(
 ) {
     let mut s = state.borrow_mut();
     s.status = status;
-    if let Some(ref dash) = s.dashboard {
-        dash.status_label.set_text(label_text);
+    s.status_text = label_text.to_string();
+    s.view.show_status(label_text);
+    s.emit(serde_json::json!({
+        "event": "status",
+        "status": s.status.name(),
+        "text": label_text,
+    }));
+    if let Some(ref dbus) = s.dbus {
+        dbus.status_changed(s.status.name(), label_text);
     }
 }
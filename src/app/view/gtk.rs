@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use gtk4::prelude::*;
+
+use crate::app::{BackendEvent, OverlayPhase};
+use crate::refiner::RefineError;
+use crate::stats::Stats;
+use crate::ui::dashboard::{
+    set_api_key_status, set_connection_status, set_last_refinement_error, DashboardWidgets,
+};
+use crate::ui::overlay::{
+    set_overlay_phase, set_overlay_preview, set_overlay_profile, OverlayWidgets,
+};
+use crate::ui::review::{close_review_window, review_text, show_review_window, ReviewWidgets};
+
+/// The app's windows. Empty until the first activation builds them.
+#[derive(Default)]
+pub struct View {
+    pub dashboard: Option<DashboardWidgets>,
+    pub overlay: Option<OverlayWidgets>,
+    pub review_window: Option<ReviewWidgets>,
+}
+
+impl View {
+    /// Dashboard status line.
+    pub fn show_status(&self, text: &str) {
+        if let Some(ref dash) = self.dashboard {
+            dash.status_label.set_text(text);
+        }
+    }
+
+    /// Switch the overlay to `phase` without changing whether it is shown.
+    pub fn show_phase(&self, phase: &OverlayPhase) {
+        if let Some(ref overlay) = self.overlay {
+            if *phase == OverlayPhase::Recording {
+                overlay.timer_label.set_text("00:00");
+            }
+            set_overlay_phase(overlay, phase);
+        }
+    }
+
+    /// Switch the overlay to `phase` and show it.
+    pub fn show_overlay(&self, phase: &OverlayPhase) {
+        self.show_phase(phase);
+        if let Some(ref overlay) = self.overlay {
+            overlay.window.set_visible(true);
+        }
+    }
+
+    pub fn hide_overlay(&self) {
+        if let Some(ref overlay) = self.overlay {
+            overlay.window.set_visible(false);
+        }
+    }
+
+    /// Refined text streamed so far.
+    pub fn show_preview(&self, text: &str) {
+        if let Some(ref overlay) = self.overlay {
+            set_overlay_preview(overlay, text);
+        }
+    }
+
+    /// Time recorded so far.
+    pub fn show_elapsed(&self, elapsed: Duration) {
+        if let Some(ref overlay) = self.overlay {
+            let secs = elapsed.as_secs();
+            overlay
+                .timer_label
+                .set_text(&format!("{:02}:{:02}", secs / 60, secs % 60));
+        }
+    }
+
+    /// Add a microphone level to the waveform.
+    pub fn show_level(&self, level: f32) {
+        if let Some(ref overlay) = self.overlay {
+            let mut levels = overlay.audio_levels.borrow_mut();
+            if levels.len() >= 24 {
+                levels.pop_front();
+            }
+            levels.push_back(level);
+            overlay.waveform.queue_draw();
+        }
+    }
+
+    /// The active profile, at `index` in the configured list.
+    pub fn show_profile(&self, name: &str, index: usize) {
+        if let Some(ref overlay) = self.overlay {
+            set_overlay_profile(overlay, name);
+        }
+        if let Some(ref dash) = self.dashboard {
+            if dash.profile_row.selected() != index as u32 {
+                dash.profile_row.set_selected(index as u32);
+            }
+        }
+    }
+
+    pub fn show_stats(&self, stats: &Stats) {
+        if let Some(ref dash) = self.dashboard {
+            dash.words_label.set_text(&stats.total_words.to_string());
+            dash.prompts_label
+                .set_text(&stats.total_prompts.to_string());
+        }
+    }
+
+    pub fn show_refinement_error(&self, error: &str) {
+        if let Some(ref dash) = self.dashboard {
+            set_last_refinement_error(dash, Some(error));
+        }
+    }
+
+    pub fn show_connection_status(
+        &self,
+        provider: &str,
+        result: &Result<Vec<String>, RefineError>,
+    ) {
+        if let Some(ref dash) = self.dashboard {
+            set_connection_status(dash, provider, result);
+        }
+    }
+
+    /// The Gemini API key, once read from the secret store, unless the user
+    /// has started typing one.
+    pub fn show_api_key(&self, key: &str) {
+        if let Some(ref dash) = self.dashboard {
+            if dash.api_key_row.text().is_empty() {
+                dash.api_key_row.set_text(key);
+            }
+        }
+    }
+
+    /// Whether the API key entered in the dashboard was stored.
+    pub fn show_secret_stored(&self, result: &Result<(), String>) {
+        if let Some(ref dash) = self.dashboard {
+            set_api_key_status(dash, result);
+        }
+    }
+
+    /// Model download progress; `total` is 0 if the size is unknown.
+    pub fn show_download(&self, downloaded: u64, total: u64) {
+        if let Some(ref dash) = self.dashboard {
+            dash.progress_bar.set_visible(true);
+            if total > 0 {
+                dash.progress_bar
+                    .set_fraction(downloaded as f64 / total as f64);
+                let mb_done = downloaded as f64 / 1_048_576.0;
+                let mb_total = total as f64 / 1_048_576.0;
+                dash.progress_bar.set_text(Some(&format!(
+                    "Downloading model: {mb_done:.1} / {mb_total:.1} MB"
+                )));
+            } else {
+                dash.progress_bar.pulse();
+            }
+        }
+    }
+
+    pub fn hide_download(&self) {
+        if let Some(ref dash) = self.dashboard {
+            dash.progress_bar.set_visible(false);
+        }
+    }
+
+    /// A finished prompt; the overlay already shows it.
+    pub fn notify_prompt(&self, _summary: &str, _text: &str) {}
+
+    /// A failure; the dashboard status line already shows it.
+    pub fn notify_error(&self, _message: &str) {}
+
+    /// Whether the review window is open.
+    pub fn reviewing(&self) -> bool {
+        self.review_window.is_some()
+    }
+
+    /// Open the review window on `text`. Its buttons send `BackendEvent`s.
+    pub fn open_review(
+        &mut self,
+        text: &str,
+        profile: &str,
+        failure: Option<&str>,
+        sender: async_channel::Sender<BackendEvent>,
+    ) {
+        self.review_window = Some(show_review_window(text, profile, failure, sender));
+    }
+
+    /// Close the review window. Returns false if none was open.
+    pub fn close_review(&mut self) -> bool {
+        match self.review_window.take() {
+            Some(widgets) => {
+                close_review_window(&widgets);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The text in the review window, with the user's edits.
+    pub fn review_text(&self) -> Option<String> {
+        self.review_window.as_ref().map(review_text)
+    }
+}
@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use gio::prelude::*;
+
+use crate::app::{BackendEvent, OverlayPhase};
+use crate::stats::Stats;
+
+/// No windows: status goes to the log, prompts and failures to desktop
+/// notifications.
+#[derive(Default)]
+pub struct View {
+    /// Sends the notifications; set once the application is running
+    pub app: Option<gio::Application>,
+}
+
+impl View {
+    pub fn show_status(&self, text: &str) {
+        log::info!("Status: {text}");
+    }
+
+    pub fn show_phase(&self, _phase: &OverlayPhase) {}
+
+    pub fn show_overlay(&self, _phase: &OverlayPhase) {}
+
+    pub fn hide_overlay(&self) {}
+
+    pub fn show_preview(&self, _text: &str) {}
+
+    pub fn show_elapsed(&self, _elapsed: Duration) {}
+
+    pub fn show_level(&self, _level: f32) {}
+
+    pub fn show_profile(&self, _name: &str, _index: usize) {}
+
+    pub fn show_stats(&self, _stats: &Stats) {}
+
+    pub fn show_refinement_error(&self, _error: &str) {}
+
+    pub fn show_api_key(&self, _key: &str) {}
+
+    pub fn show_download(&self, _downloaded: u64, _total: u64) {}
+
+    pub fn hide_download(&self) {}
+
+    /// Notify that a prompt was delivered; `summary` is e.g. "Prompt copied!".
+    pub fn notify_prompt(&self, summary: &str, text: &str) {
+        self.notify("prompt", summary, text);
+    }
+
+    pub fn notify_error(&self, message: &str) {
+        self.notify("error", "Voice Prompt error", message);
+    }
+
+    /// Send a notification, replacing the last one with the same `id`.
+    fn notify(&self, id: &str, title: &str, body: &str) {
+        if let Some(ref app) = self.app {
+            let notification = gio::Notification::new(title);
+            notification.set_body(Some(body));
+            app.send_notification(Some(id), &notification);
+        }
+    }
+
+    /// There is no review window; prompts are delivered straight away.
+    pub fn reviewing(&self) -> bool {
+        false
+    }
+
+    pub fn open_review(
+        &mut self,
+        _text: &str,
+        _profile: &str,
+        _failure: Option<&str>,
+        _sender: async_channel::Sender<BackendEvent>,
+    ) {
+    }
+
+    pub fn close_review(&mut self) -> bool {
+        false
+    }
+
+    pub fn review_text(&self) -> Option<String> {
+        None
+    }
+}
@@ -0,0 +1,13 @@
+//! How the app shows what it is doing: the dashboard, overlay and review
+//! windows, or logs and desktop notifications when built without the `ui`
+//! feature. Both define a `View` with the same methods.
+
+#[cfg(feature = "ui")]
+mod gtk;
+#[cfg(feature = "ui")]
+pub use gtk::*;
+
+#[cfg(not(feature = "ui"))]
+mod headless;
+#[cfg(not(feature = "ui"))]
+pub use headless::*;
@@ -0,0 +1,245 @@
+use crate::control::Command;
+
+pub const USAGE: &str = "\
+usage: voice-prompt [--hidden]
+       voice-prompt <toggle|start|stop|cancel|status|last>
+       voice-prompt history [--json]
+       voice-prompt ctl <command>";
+
+/// What the command line asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Invocation {
+    /// Start the app, or show the running one. `hidden` starts it without
+    /// presenting the dashboard.
+    Launch { hidden: bool },
+    /// Run a command in the running instance; `json` prints its raw reply.
+    Forward { command: Command, json: bool },
+}
+
+/// Parse the arguments after the program name.
+pub fn parse(args: &[String]) -> Result<Invocation, String> {
+    let mut hidden = false;
+    let mut json = false;
+    let mut words = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--hidden" => hidden = true,
+            "--json" => json = true,
+            flag if flag.starts_with('-') => return Err(format!("unknown option {flag}")),
+            word => words.push(word),
+        }
+    }
+    let command = match words.as_slice() {
+        [] if json => return Err("--json needs a command".into()),
+        [] => return Ok(Invocation::Launch { hidden }),
+        ["toggle"] => Command::Toggle,
+        ["start"] => Command::Start,
+        ["stop"] => Command::Stop,
+        ["cancel"] => Command::Cancel,
+        ["status"] => Command::Status,
+        ["last"] => Command::Last,
+        ["history"] => Command::History,
+        [word] => return Err(format!("unknown command {word}")),
+        _ => return Err("expected one command".into()),
+    };
+    if hidden {
+        return Err("--hidden only applies when starting the app".into());
+    }
+    Ok(Invocation::Forward { command, json })
+}
+
+/// Render the main loop's answer to a forwarded command: what to print on
+/// stdout, on stderr, and the exit status.
+pub fn render(command: Command, answer: &serde_json::Value, json: bool) -> (String, String, i32) {
+    if answer["ok"] != true {
+        let error = answer["error"].as_str().unwrap_or("failed");
+        return (String::new(), format!("voice-prompt: {error}\n"), 1);
+    }
+    if json {
+        let body = match command {
+            Command::Last => &answer["prompt"],
+            Command::History => &answer["history"],
+            _ => answer,
+        };
+        let out = serde_json::to_string_pretty(body).unwrap_or_default();
+        return (format!("{out}\n"), String::new(), 0);
+    }
+    let text = |value: &serde_json::Value| value.as_str().unwrap_or_default().to_string();
+    let out = match command {
+        Command::Status => format!("{}: {}\n", text(&answer["status"]), text(&answer["text"])),
+        Command::Last => match answer["prompt"]["text"].as_str() {
+            Some(prompt) => format!("{prompt}\n"),
+            None => return (String::new(), "voice-prompt: no prompts yet\n".into(), 1),
+        },
+        Command::History => answer["history"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|entry| {
+                let first_line = text(&entry["text"])
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string();
+                format!("{}  {first_line}\n", text(&entry["timestamp"]))
+            })
+            .collect(),
+        _ => String::new(),
+    };
+    (out, String::new(), 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn forward(command: Command, json: bool) -> Result<Invocation, String> {
+        Ok(Invocation::Forward { command, json })
+    }
+
+    #[test]
+    fn parses_commands() {
+        let cases = [
+            ("", Ok(Invocation::Launch { hidden: false })),
+            ("--hidden", Ok(Invocation::Launch { hidden: true })),
+            ("toggle", forward(Command::Toggle, false)),
+            ("start", forward(Command::Start, false)),
+            ("stop", forward(Command::Stop, false)),
+            ("cancel", forward(Command::Cancel, false)),
+            ("status", forward(Command::Status, false)),
+            ("last", forward(Command::Last, false)),
+            ("history", forward(Command::History, false)),
+            ("history --json", forward(Command::History, true)),
+            ("--json status", forward(Command::Status, true)),
+            ("last --json --json", forward(Command::Last, true)),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(parse(&args(line)), expected, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        let cases = [
+            ("--json", "--json needs a command"),
+            ("--hidden --json", "--json needs a command"),
+            (
+                "--hidden toggle",
+                "--hidden only applies when starting the app",
+            ),
+            (
+                "status --hidden",
+                "--hidden only applies when starting the app",
+            ),
+            ("--verbose", "unknown option --verbose"),
+            ("status -j", "unknown option -j"),
+            ("subscribe", "unknown command subscribe"),
+            ("Toggle", "unknown command Toggle"),
+            ("start stop", "expected one command"),
+            ("history 5", "expected one command"),
+        ];
+        for (line, error) in cases {
+            assert_eq!(parse(&args(line)), Err(error.to_string()), "{line:?}");
+        }
+    }
+
+    fn out(text: &str) -> (String, String, i32) {
+        (text.to_string(), String::new(), 0)
+    }
+
+    fn err(text: &str) -> (String, String, i32) {
+        (String::new(), text.to_string(), 1)
+    }
+
+    #[test]
+    fn renders_answers() {
+        let status = json!({ "ok": true, "status": "recording", "text": "Recording..." });
+        let last = json!({ "ok": true, "prompt": { "text": "Fix the bug\nin main.rs" } });
+        let history = json!({ "ok": true, "history": [
+            { "timestamp": "2026-10-17T09:00:00Z", "text": "First prompt" },
+            { "timestamp": "2026-10-18T10:30:00Z", "text": "Second\nwith two lines" },
+        ] });
+        let cases = [
+            (Command::Status, &status, out("recording: Recording...\n")),
+            (Command::Last, &last, out("Fix the bug\nin main.rs\n")),
+            (
+                Command::Last,
+                &json!({ "ok": true, "prompt": null }),
+                err("voice-prompt: no prompts yet\n"),
+            ),
+            (
+                Command::History,
+                &history,
+                out("2026-10-17T09:00:00Z  First prompt\n\
+                     2026-10-18T10:30:00Z  Second\n"),
+            ),
+            (
+                Command::History,
+                &json!({ "ok": true, "history": [] }),
+                out(""),
+            ),
+            (Command::Toggle, &json!({ "ok": true }), out("")),
+            (
+                Command::Stop,
+                &json!({ "ok": false, "error": "not recording" }),
+                err("voice-prompt: not recording\n"),
+            ),
+            (
+                Command::Start,
+                &json!({ "ok": false }),
+                err("voice-prompt: failed\n"),
+            ),
+        ];
+        for (command, answer, expected) in cases {
+            assert_eq!(
+                render(command, answer, false),
+                expected,
+                "{command:?} {answer}"
+            );
+        }
+    }
+
+    #[test]
+    fn renders_json() {
+        let cases = [
+            (
+                Command::Status,
+                json!({ "ok": true, "status": "idle", "text": "Idle" }),
+                "{\n  \"ok\": true,\n  \"status\": \"idle\",\n  \"text\": \"Idle\"\n}\n",
+            ),
+            (
+                Command::Last,
+                json!({ "ok": true, "prompt": { "text": "Fix the bug", "words": 3 } }),
+                "{\n  \"text\": \"Fix the bug\",\n  \"words\": 3\n}\n",
+            ),
+            (
+                Command::Last,
+                json!({ "ok": true, "prompt": null }),
+                "null\n",
+            ),
+            (
+                Command::History,
+                json!({ "ok": true, "history": [{ "text": "First prompt" }] }),
+                "[\n  {\n    \"text\": \"First prompt\"\n  }\n]\n",
+            ),
+        ];
+        for (command, answer, expected) in cases {
+            assert_eq!(render(command, &answer, true), out(expected), "{command:?}");
+        }
+        // Errors read the same either way
+        assert_eq!(
+            render(
+                Command::Stop,
+                &json!({ "ok": false, "error": "not recording" }),
+                true
+            ),
+            err("voice-prompt: not recording\n")
+        );
+    }
+}
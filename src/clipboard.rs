@@ -1,32 +1,370 @@
 use std::io::Write;
 use std::process::{Command, Stdio};
 
-/// Copy text to the system clipboard.
-/// Uses pbcopy on macOS, wl-copy on Wayland, xclip on X11.
-pub fn copy_to_clipboard(text: &str) -> Result<(), Box<dyn std::error::Error>> {
+#[cfg(feature = "ui")]
+use gtk4::gdk;
+#[cfg(feature = "ui")]
+use gtk4::prelude::*;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// Clipboard contents saved so they can be put back later.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub mime: String,
+    pub data: Vec<u8>,
+}
+
+/// Which selection to set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    /// The regular Ctrl+C / Ctrl+V clipboard
+    Clipboard,
+    /// The X11/Wayland primary selection, pasted with a middle click
+    Primary,
+}
+
+/// Command-line clipboard tools, used when GDK can't set the clipboard (or
+/// in headless builds) and for reading it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tool {
+    #[cfg(target_os = "linux")]
+    WlClipboard,
+    #[cfg(target_os = "linux")]
+    Xclip,
+    #[cfg(target_os = "linux")]
+    Xsel,
+    #[cfg(target_os = "macos")]
+    Pbcopy,
+}
+
+impl Tool {
+    fn name(self) -> &'static str {
+        match self {
+            #[cfg(target_os = "linux")]
+            Self::WlClipboard => "wl-copy",
+            #[cfg(target_os = "linux")]
+            Self::Xclip => "xclip",
+            #[cfg(target_os = "linux")]
+            Self::Xsel => "xsel",
+            #[cfg(target_os = "macos")]
+            Self::Pbcopy => "pbcopy",
+        }
+    }
+
+    /// Whether the tool handles more than plain text.
+    fn typed(self) -> bool {
+        match self {
+            #[cfg(target_os = "linux")]
+            Self::WlClipboard | Self::Xclip => true,
+            _ => false,
+        }
+    }
+
+    /// Command that writes `selection`, offering `mime` if the tool can.
+    fn writer(self, selection: Selection, mime: Option<&str>) -> (&'static str, Vec<String>) {
+        let mut args: Vec<String> = Vec::new();
+        let cmd = match self {
+            #[cfg(target_os = "linux")]
+            Self::WlClipboard => {
+                if selection == Selection::Primary {
+                    args.push("--primary".into());
+                }
+                if let Some(mime) = mime {
+                    args.extend(["--type".into(), mime.into()]);
+                }
+                "wl-copy"
+            }
+            #[cfg(target_os = "linux")]
+            Self::Xclip => {
+                args.extend(["-selection".into(), xclip_selection(selection).into()]);
+                if let Some(mime) = mime {
+                    args.extend(["-t".into(), mime.into()]);
+                }
+                args.push("-i".into());
+                "xclip"
+            }
+            #[cfg(target_os = "linux")]
+            Self::Xsel => {
+                args.extend([xsel_selection(selection).into(), "--input".into()]);
+                "xsel"
+            }
+            #[cfg(target_os = "macos")]
+            Self::Pbcopy => "pbcopy",
+        };
+        (cmd, args)
+    }
+
+    /// Command that prints the clipboard, as `mime` if the tool can.
+    fn reader(self, mime: Option<&str>) -> (&'static str, Vec<String>) {
+        let mut args: Vec<String> = Vec::new();
+        let cmd = match self {
+            #[cfg(target_os = "linux")]
+            Self::WlClipboard => {
+                args.push("--no-newline".into());
+                if let Some(mime) = mime {
+                    args.extend(["--type".into(), mime.into()]);
+                }
+                "wl-paste"
+            }
+            #[cfg(target_os = "linux")]
+            Self::Xclip => {
+                args.extend(["-selection".into(), "clipboard".into(), "-o".into()]);
+                if let Some(mime) = mime {
+                    args.extend(["-t".into(), mime.into()]);
+                }
+                "xclip"
+            }
+            #[cfg(target_os = "linux")]
+            Self::Xsel => {
+                args.extend(["--clipboard".into(), "--output".into()]);
+                "xsel"
+            }
+            #[cfg(target_os = "macos")]
+            Self::Pbcopy => "pbpaste",
+        };
+        (cmd, args)
+    }
+
+    /// Command that lists the clipboard's types, one per line.
+    fn type_lister(self) -> Option<(&'static str, Vec<String>)> {
+        match self {
+            #[cfg(target_os = "linux")]
+            Self::WlClipboard => Some(("wl-paste", vec!["--list-types".into()])),
+            #[cfg(target_os = "linux")]
+            Self::Xclip => Some(self.reader(Some("TARGETS"))),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn xclip_selection(selection: Selection) -> &'static str {
+    match selection {
+        Selection::Clipboard => "clipboard",
+        Selection::Primary => "primary",
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn xsel_selection(selection: Selection) -> &'static str {
+    match selection {
+        Selection::Clipboard => "--clipboard",
+        Selection::Primary => "--primary",
+    }
+}
+
+/// Installed tools that work in this session, in the order they are tried.
+fn tools() -> Vec<Tool> {
     #[cfg(target_os = "macos")]
-    let (cmd, args): (&str, Vec<&str>) = ("pbcopy", vec![]);
+    let candidates = vec![Tool::Pbcopy];
 
+    // xclip and xsel reach Wayland apps through XWayland
     #[cfg(target_os = "linux")]
-    let (cmd, args): (&str, Vec<&str>) = {
-        let session_type = std::env::var("XDG_SESSION_TYPE").unwrap_or_default();
-        if session_type == "wayland" {
-            ("wl-copy", vec![])
-        } else {
-            ("xclip", vec!["-selection", "clipboard"])
+    let candidates = if is_wayland() {
+        vec![Tool::WlClipboard, Tool::Xclip, Tool::Xsel]
+    } else {
+        vec![Tool::Xclip, Tool::Xsel]
+    };
+
+    candidates
+        .into_iter()
+        .filter(|tool| crate::keyboard::on_path(tool.name()))
+        .collect()
+}
+
+/// How the clipboard will be set, for the dashboard.
+pub fn describe() -> String {
+    let tools: Vec<&str> = tools().iter().map(|t| t.name()).collect();
+    if !cfg!(feature = "ui") {
+        if tools.is_empty() {
+            return no_tool_error().to_string();
         }
+        return tools.join(", ");
+    }
+    if !tools.is_empty() {
+        return format!("GTK, falling back to {}", tools.join(", "));
+    }
+    #[cfg(target_os = "linux")]
+    if is_wayland() {
+        return "GTK only \u{2014} install wl-clipboard to copy while another app is focused"
+            .into();
+    }
+    "GTK only".into()
+}
+
+/// Copy text to the system clipboard. Must be called on the main thread.
+pub fn copy_to_clipboard(text: &str) -> Result<(), Error> {
+    copy(Selection::Clipboard, text)
+}
+
+/// Copy text to the primary selection, for middle-click pasting.
+pub fn copy_to_primary(text: &str) -> Result<(), Error> {
+    copy(Selection::Primary, text)
+}
+
+/// Set `selection` through GDK if that takes effect, else through the first
+/// tool that works.
+fn copy(selection: Selection, text: &str) -> Result<(), Error> {
+    #[cfg(feature = "ui")]
+    if let Some(clipboard) = gdk_clipboard(selection) {
+        clipboard.set_text(text);
+        return Ok(());
+    }
+    let mut errors = Vec::new();
+    for tool in tools() {
+        let (cmd, args) = tool.writer(selection, None);
+        match write_to(cmd, &args, text.as_bytes()) {
+            Ok(()) => return Ok(()),
+            Err(e) => errors.push(e.to_string()),
+        }
+    }
+    if errors.is_empty() {
+        return Err(no_tool_error());
+    }
+    Err(errors.join("; ").into())
+}
+
+/// The GDK clipboard for `selection`, if setting it will take effect.
+/// Wayland compositors ignore clipboard changes from unfocused apps, and
+/// the overlay never takes focus, so there GDK is only used while one of
+/// our windows is active.
+#[cfg(feature = "ui")]
+fn gdk_clipboard(selection: Selection) -> Option<gdk::Clipboard> {
+    let display = gdk::Display::default()?;
+    #[cfg(target_os = "linux")]
+    if is_wayland()
+        && !gtk4::Window::list_toplevels().iter().any(|w| {
+            w.downcast_ref::<gtk4::Window>()
+                .is_some_and(|w| w.is_active())
+        })
+    {
+        return None;
+    }
+    match selection {
+        Selection::Clipboard => Some(display.clipboard()),
+        Selection::Primary => Some(display.primary_clipboard()),
+    }
+}
+
+/// Save the clipboard so it can be restored after an auto-paste. Only one
+/// format is kept: plain text if the owner offers it, else its first MIME
+/// type (an image, a file list, ...). Returns `None` if the clipboard is
+/// empty. Must be called on the main thread.
+pub fn snapshot_clipboard() -> Result<Option<Snapshot>, Error> {
+    // If GDK owns the clipboard, a tool asking for it would wait on our
+    // main loop, which is blocked right here
+    #[cfg(feature = "ui")]
+    if let Some(clipboard) = gdk::Display::default().map(|d| d.clipboard()) {
+        if clipboard.is_local() {
+            let text = clipboard
+                .content()
+                .and_then(|content| content.value(gtk4::glib::Type::STRING).ok())
+                .and_then(|value| value.get::<String>().ok());
+            return Ok(text.map(|text| Snapshot {
+                mime: "text/plain".into(),
+                data: text.into_bytes(),
+            }));
+        }
+    }
+
+    let Some(tool) = tools().into_iter().next() else {
+        return Err(no_tool_error());
+    };
+    let mime = match tool.type_lister() {
+        Some((cmd, args)) => {
+            let Some(types) = read_from(cmd, &args)? else {
+                return Ok(None);
+            };
+            let types = String::from_utf8_lossy(&types);
+            let types: Vec<&str> = types.lines().map(str::trim).collect();
+            let Some(mime) = snapshot_type(&types) else {
+                return Ok(None);
+            };
+            mime.to_string()
+        }
+        None => "text/plain".to_string(),
     };
 
+    let (cmd, args) = tool.reader((!is_text(&mime)).then_some(mime.as_str()));
+    let data = read_from(cmd, &args)?;
+    Ok(data
+        .filter(|data| !data.is_empty())
+        .map(|data| Snapshot { mime, data }))
+}
+
+/// The type to save out of those the clipboard owner offers. Text wins,
+/// so rich text copied from a browser or editor comes back as what it
+/// pastes into most places. X11 targets include selection metadata
+/// (TARGETS, TIMESTAMP) and legacy names, hence the check for a '/'.
+fn snapshot_type<'a>(types: &[&'a str]) -> Option<&'a str> {
+    types
+        .iter()
+        .find(|t| is_text(t))
+        .or_else(|| types.iter().find(|t| t.contains('/')))
+        .copied()
+}
+
+/// Whether the clipboard still holds `text`, i.e. nothing else was copied
+/// since we put it there. Call off the main thread: if GDK owns the
+/// clipboard, the main loop has to answer the request.
+pub fn clipboard_holds(text: &str) -> Result<bool, Error> {
+    let Some(tool) = tools().into_iter().next() else {
+        return Err(no_tool_error());
+    };
+    let (cmd, args) = tool.reader(None);
+    let current = read_from(cmd, &args)?;
+    Ok(current.is_some_and(|current| current == text.as_bytes()))
+}
+
+/// Put saved contents back on the clipboard. Safe to call off the main
+/// thread.
+pub fn restore_clipboard(snapshot: &Snapshot) -> Result<(), Error> {
+    let text = is_text(&snapshot.mime);
+    let mut errors = Vec::new();
+    for tool in tools().into_iter().filter(|t| text || t.typed()) {
+        // Text is written untyped so the tool offers every text target
+        let mime = (!text).then_some(snapshot.mime.as_str());
+        let (cmd, args) = tool.writer(Selection::Clipboard, mime);
+        match write_to(cmd, &args, &snapshot.data) {
+            Ok(()) => return Ok(()),
+            Err(e) => errors.push(e.to_string()),
+        }
+    }
+    if errors.is_empty() {
+        return Err(format!("no installed tool can restore {}", snapshot.mime).into());
+    }
+    Err(errors.join("; ").into())
+}
+
+fn is_text(mime: &str) -> bool {
+    mime.starts_with("text/plain") || mime == "UTF8_STRING"
+}
+
+#[cfg(target_os = "linux")]
+fn is_wayland() -> bool {
+    std::env::var("XDG_SESSION_TYPE").unwrap_or_default() == "wayland"
+}
+
+fn no_tool_error() -> Error {
+    if cfg!(target_os = "macos") {
+        "pbcopy not found".into()
+    } else {
+        "no clipboard tool found; install wl-clipboard, xclip or xsel".into()
+    }
+}
+
+fn write_to(cmd: &str, args: &[String], data: &[u8]) -> Result<(), Error> {
     let mut child = Command::new(cmd)
-        .args(&args)
+        .args(args)
         .stdin(Stdio::piped())
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .spawn()
         .map_err(|e| format!("Failed to spawn {cmd}: {e}"))?;
 
-    if let Some(ref mut stdin) = child.stdin {
-        stdin.write_all(text.as_bytes())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(data)?;
     }
 
     let status = child.wait()?;
@@ -36,3 +374,36 @@ pub fn copy_to_clipboard(text: &str) -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Run a clipboard reader. `None` if it fails, which wl-paste and xclip do
+/// when the clipboard is empty or lacks the requested type.
+fn read_from(cmd: &str, args: &[String]) -> Result<Option<Vec<u8>>, Error> {
+    let output = Command::new(cmd)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to spawn {cmd}: {e}"))?;
+    Ok(output.status.success().then_some(output.stdout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_prefers_plain_text() {
+        let browser = ["TIMESTAMP", "TARGETS", "text/html", "text/plain;charset=utf-8", "UTF8_STRING"];
+        assert_eq!(snapshot_type(&browser), Some("text/plain;charset=utf-8"));
+        assert_eq!(snapshot_type(&["TARGETS", "text/html", "UTF8_STRING"]), Some("UTF8_STRING"));
+    }
+
+    #[test]
+    fn snapshot_falls_back_to_first_mime_type() {
+        assert_eq!(
+            snapshot_type(&["TARGETS", "image/png", "image/bmp"]),
+            Some("image/png")
+        );
+        assert_eq!(snapshot_type(&["TARGETS", "TIMESTAMP"]), None);
+    }
+}
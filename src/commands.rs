@@ -0,0 +1,387 @@
+use crate::config::{CommandAction, CommandsConfig, SpokenCommand};
+
+/// Words that end a case-conversion run ("snake case user id in the config").
+const CASE_STOP_WORDS: &[&str] = &[
+    "and", "or", "in", "on", "at", "to", "of", "for", "from", "with", "the", "a", "an", "then",
+    "is", "as", "please",
+];
+
+/// Most words a case-conversion run takes without an explicit "end case".
+const MAX_CASE_WORDS: usize = 3;
+
+/// Turn spoken formatting commands in `transcript` into text structure.
+/// Returns the transcript unchanged when commands are disabled.
+pub fn apply(config: &CommandsConfig, transcript: &str) -> String {
+    if !config.enabled || config.commands.is_empty() {
+        return transcript.to_string();
+    }
+
+    let words: Vec<&str> = transcript.split_whitespace().collect();
+    let mut out = Output::default();
+    let mut i = 0;
+
+    while i < words.len() {
+        let Some((command, len)) = match_command(&config.commands, &words[i..]) else {
+            out.push_word(words[i]);
+            i += 1;
+            continue;
+        };
+        // Punctuation Whisper attached to the command's last word
+        let trail = trailing_punct(words[i + len - 1]);
+        i += len;
+
+        match &command.action {
+            CommandAction::Insert(text) if text.starts_with(char::is_whitespace) => {
+                // "done, new line." — the punctuation closes the line being ended
+                if out.text.ends_with(char::is_alphanumeric) {
+                    out.push_glued(trail);
+                }
+                out.push_raw(text);
+            }
+            CommandAction::Insert(text) => {
+                out.push_word(text);
+                out.push_glued(trail);
+            }
+            CommandAction::OpenCodeBlock => {
+                out.push_raw("\n```\n");
+                out.in_code_block = true;
+            }
+            CommandAction::CloseCodeBlock => {
+                out.push_raw("\n```\n");
+                out.in_code_block = false;
+            }
+            CommandAction::InlineCode => {
+                let closing = out.in_inline_code;
+                out.toggle_inline_code();
+                if closing {
+                    out.push_glued(trail);
+                }
+            }
+            // Outside a case-conversion run there is nothing to end
+            CommandAction::EndCase => {}
+            action => {
+                // "snake case, ..." has nothing to convert
+                let (identifier, used, ident_trail) = if trail.is_empty() {
+                    convert_case(action, &config.commands, &words[i..])
+                } else {
+                    (String::new(), 0, "")
+                };
+                if identifier.is_empty() {
+                    // Nothing to convert — the phrase was probably meant literally
+                    for word in &words[i - len..i] {
+                        out.push_word(word);
+                    }
+                    continue;
+                }
+                out.push_word(&identifier);
+                out.push_glued(ident_trail);
+                i += used;
+            }
+        }
+    }
+
+    out.finish()
+}
+
+/// Builds the formatted text, tracking where spaces belong.
+#[derive(Default)]
+struct Output {
+    text: String,
+    /// Next word attaches without a space (after a newline or an opening backtick)
+    glue_next: bool,
+    in_inline_code: bool,
+    in_code_block: bool,
+}
+
+impl Output {
+    fn push_word(&mut self, word: &str) {
+        let needs_space =
+            !self.glue_next && !self.text.is_empty() && !self.text.ends_with(char::is_whitespace);
+        if needs_space {
+            self.text.push(' ');
+        }
+        self.text.push_str(word);
+        self.glue_next = false;
+    }
+
+    fn push_glued(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    /// Insert literal text; line breaks swallow the space before them,
+    /// and nothing is inserted before the first word.
+    fn push_raw(&mut self, raw: &str) {
+        let raw = if self.text.is_empty() {
+            raw.trim_start_matches('\n')
+        } else {
+            raw
+        };
+        if raw.starts_with('\n') {
+            let trimmed = self.text.trim_end_matches(' ').len();
+            self.text.truncate(trimmed);
+        }
+        self.text.push_str(raw);
+        self.glue_next = raw.ends_with(char::is_whitespace);
+    }
+
+    fn toggle_inline_code(&mut self) {
+        if self.in_inline_code {
+            self.text.push('`');
+        } else {
+            self.push_word("`");
+            self.glue_next = true;
+        }
+        self.in_inline_code = !self.in_inline_code;
+    }
+
+    fn finish(mut self) -> String {
+        if self.in_inline_code {
+            self.text.push('`');
+        }
+        if self.in_code_block {
+            self.push_raw("\n```");
+        }
+        self.text.trim().to_string()
+    }
+}
+
+/// Lowercase a word and strip the punctuation Whisper adds around it.
+fn normalize(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
+
+fn trailing_punct(word: &str) -> &str {
+    let end = word
+        .rfind(char::is_alphanumeric)
+        .map(|i| i + word[i..].chars().next().map_or(1, char::len_utf8))
+        .unwrap_or(0);
+    &word[end..]
+}
+
+/// Find the longest command phrase starting at `words[0]`.
+fn match_command<'a>(
+    commands: &'a [SpokenCommand],
+    words: &[&str],
+) -> Option<(&'a SpokenCommand, usize)> {
+    commands
+        .iter()
+        .filter_map(|cmd| {
+            let phrase: Vec<String> = cmd.phrase.split_whitespace().map(normalize).collect();
+            let candidate = words.get(..phrase.len())?;
+            let matches = !phrase.is_empty()
+                && candidate.iter().zip(&phrase).enumerate().all(|(j, (w, p))| {
+                    // A sentence break inside the phrase means it wasn't a command
+                    normalize(w) == *p && (j + 1 == phrase.len() || trailing_punct(w).is_empty())
+                });
+            matches.then_some((cmd, phrase.len()))
+        })
+        .max_by_key(|(_, len)| *len)
+}
+
+/// Collect the words following a case command and join them.
+/// The run ends at "end case", which is consumed; otherwise at punctuation,
+/// another command or a connective, and after at most `MAX_CASE_WORDS`.
+/// Returns the identifier, how many words were consumed and any punctuation
+/// that ended the run.
+fn convert_case<'a>(
+    action: &CommandAction,
+    commands: &[SpokenCommand],
+    words: &[&'a str],
+) -> (String, usize, &'a str) {
+    let mut parts: Vec<String> = Vec::new();
+    let mut trail = "";
+    let mut ended = None;
+    for (k, word) in words.iter().enumerate() {
+        if let Some((cmd, len)) = match_command(commands, &words[k..]) {
+            if cmd.action == CommandAction::EndCase {
+                ended = Some(k + len);
+            }
+            break;
+        }
+        let norm = normalize(word);
+        if norm.is_empty() {
+            break;
+        }
+        parts.push(norm);
+        let punct = trailing_punct(word);
+        if !punct.is_empty() {
+            trail = punct;
+            break;
+        }
+    }
+
+    let used = match ended {
+        Some(used) => {
+            trail = trailing_punct(words[used - 1]);
+            used
+        }
+        None => {
+            // Without an end word, guess where the identifier stops
+            let limit = parts
+                .iter()
+                .position(|p| CASE_STOP_WORDS.contains(&p.as_str()))
+                .unwrap_or(parts.len())
+                .min(MAX_CASE_WORDS);
+            if limit < parts.len() {
+                // The punctuation belongs to a word left out of the run
+                parts.truncate(limit);
+                trail = "";
+            }
+            parts.len()
+        }
+    };
+
+    let identifier = match action {
+        CommandAction::SnakeCase => parts.join("_"),
+        CommandAction::KebabCase => parts.join("-"),
+        CommandAction::ConstantCase => parts.join("_").to_uppercase(),
+        CommandAction::CamelCase => parts
+            .iter()
+            .enumerate()
+            .map(|(i, p)| if i == 0 { p.clone() } else { capitalize(p) })
+            .collect(),
+        CommandAction::PascalCase => parts.iter().map(|p| capitalize(p)).collect(),
+        _ => parts.join(" "),
+    };
+    (identifier, used, trail)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(transcript: &str) -> String {
+        apply(&CommandsConfig::default(), transcript)
+    }
+
+    #[test]
+    fn disabled_commands_leave_the_transcript_alone() {
+        let config = CommandsConfig {
+            enabled: false,
+            ..CommandsConfig::default()
+        };
+        assert_eq!(apply(&config, "snake case user id"), "snake case user id");
+    }
+
+    #[test]
+    fn line_breaks() {
+        assert_eq!(format("first new line second"), "first\nsecond");
+        assert_eq!(format("first newline second"), "first\nsecond");
+        assert_eq!(format("first new paragraph second"), "first\n\nsecond");
+        assert_eq!(format("new line first"), "first");
+    }
+
+    #[test]
+    fn punctuation_after_a_line_break_ends_the_line() {
+        assert_eq!(format("Fix it, new line. Then test"), "Fix it,\nThen test");
+        assert_eq!(format("Fix it new line. Then test"), "Fix it.\nThen test");
+        assert_eq!(format("Fix it. New line. Then test"), "Fix it.\nThen test");
+    }
+
+    #[test]
+    fn bullet_points() {
+        assert_eq!(
+            format("todo bullet point milk bullet point eggs"),
+            "todo\n- milk\n- eggs"
+        );
+    }
+
+    #[test]
+    fn inserted_text_keeps_its_punctuation() {
+        let mut config = CommandsConfig::default();
+        config.commands.push(SpokenCommand {
+            phrase: "smiley".into(),
+            action: CommandAction::Insert(":)".into()),
+        });
+        assert_eq!(apply(&config, "thanks smiley, bye"), "thanks :), bye");
+    }
+
+    #[test]
+    fn code_blocks() {
+        assert_eq!(
+            format("run open code block cargo test close code block then push"),
+            "run\n```\ncargo test\n```\nthen push"
+        );
+        assert_eq!(format("open code block ls"), "```\nls\n```");
+    }
+
+    #[test]
+    fn inline_code() {
+        assert_eq!(format("call backtick foo backtick now"), "call `foo` now");
+        assert_eq!(format("call backtick foo backtick, now"), "call `foo`, now");
+        assert_eq!(format("call backtick foo"), "call `foo`");
+    }
+
+    #[test]
+    fn case_conversions() {
+        assert_eq!(format("snake case user id"), "user_id");
+        assert_eq!(format("camel case get user name"), "getUserName");
+        assert_eq!(format("pascal case http client"), "HttpClient");
+        assert_eq!(format("kebab case dry run"), "dry-run");
+        assert_eq!(format("constant case max retries"), "MAX_RETRIES");
+    }
+
+    #[test]
+    fn case_runs_stop_at_punctuation_connectives_and_commands() {
+        assert_eq!(
+            format("rename snake case user id, then stop"),
+            "rename user_id, then stop"
+        );
+        assert_eq!(
+            format("set snake case user id in the config"),
+            "set user_id in the config"
+        );
+        assert_eq!(format("snake case user id new line done"), "user_id\ndone");
+        assert_eq!(
+            format("snake case user id please fix it"),
+            "user_id please fix it"
+        );
+    }
+
+    #[test]
+    fn case_runs_are_bounded_without_an_end_word() {
+        assert_eq!(
+            format("snake case max retry count was wrong"),
+            "max_retry_count was wrong"
+        );
+        assert_eq!(
+            format("snake case max retry count was wrong."),
+            "max_retry_count was wrong."
+        );
+    }
+
+    #[test]
+    fn end_case_closes_the_run() {
+        assert_eq!(
+            format("snake case user id end case fix it"),
+            "user_id fix it"
+        );
+        assert_eq!(
+            format("snake case number of active users end case."),
+            "number_of_active_users."
+        );
+        assert_eq!(format("kebab case dry end case run"), "dry run");
+        // A stray end word is dropped
+        assert_eq!(format("fix end case it"), "fix it");
+    }
+
+    #[test]
+    fn case_commands_without_words_are_literal() {
+        assert_eq!(format("I like snake case."), "I like snake case.");
+        assert_eq!(format("snake case, really"), "snake case, really");
+    }
+
+    #[test]
+    fn commands_split_by_a_sentence_break_are_words() {
+        assert_eq!(format("a new. line"), "a new. line");
+    }
+}
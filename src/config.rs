@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::secrets::SecretStore;
 
 /// Key codes for the hotkey combination.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,11 +25,510 @@ impl Default for HotkeyConfig {
     }
 }
 
+/// Dictating one prompt over several takes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppendConfig {
+    /// Records a take and adds it to the draft instead of refining it
+    pub hotkey: Option<HotkeyConfig>,
+    /// Refine the draft when no new take starts within this many seconds;
+    /// 0 waits for the overlay's Finish button or the record hotkey
+    pub finish_after_secs: u64,
+}
+
+impl Default for AppendConfig {
+    fn default() -> Self {
+        Self {
+            hotkey: Some(HotkeyConfig {
+                modifiers: vec![29, 56], // KEY_LEFTCTRL, KEY_LEFTALT
+                trigger: 57,             // KEY_SPACE
+                display_name: "Ctrl+Alt+Space".into(),
+            }),
+            finish_after_secs: 30,
+        }
+    }
+}
+
+/// How synthetic key presses reach the focused window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyboardBackend {
+    /// The tool for the session type if installed, else uinput, else ydotool
+    #[default]
+    Auto,
+    /// Kernel virtual keyboard (Linux, needs write access to `/dev/uinput`).
+    /// Presses keys by their position on a US layout.
+    Uinput,
+    /// `wtype` (wlroots-based Wayland compositors)
+    Wtype,
+    /// `ydotool` (needs the `ydotoold` daemon)
+    Ydotool,
+    /// `xdotool` (X11)
+    Xdotool,
+}
+
+/// A place finished prompts are sent to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputSink {
+    /// Copy to the clipboard (and auto-paste, if the profile asks)
+    Clipboard,
+    /// Type into the focused window
+    Type,
+    /// Append to a file; a leading "~/" is the home directory
+    File(String),
+    /// Write to a named pipe, if something is reading it
+    Fifo(String),
+    /// Print to standard output
+    Stdout,
+    /// Run a shell command with the prompt on stdin
+    Command(String),
+}
+
+/// Delivery of finished prompts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputConfig {
+    /// Every sink gets each prompt; one failing doesn't stop the others
+    pub sinks: Vec<OutputSink>,
+    /// Shortcut typed for each line break. "shift+enter" keeps chat inputs
+    /// from sending a half-typed prompt; use "enter" for editors.
+    pub newline_chord: String,
+    /// Also copy prompts to the primary selection (middle-click paste)
+    pub primary_selection: bool,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            sinks: vec![OutputSink::Clipboard],
+            newline_chord: "shift+enter".into(),
+            primary_selection: false,
+        }
+    }
+}
+
+/// Pasting into the focused window after the prompt is copied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PasteConfig {
+    /// Also used by the type-out sink
+    pub backend: KeyboardBackend,
+    /// Shortcut that pastes, e.g. "ctrl+v"; profiles can override it
+    pub chord: String,
+    /// Wait this long before pasting or typing, so the clipboard is ready
+    /// and focus is back on the target window
+    pub delay_ms: u64,
+    /// Put back what was on the clipboard before the prompt was copied
+    pub restore_clipboard: bool,
+    /// Wait this long after pasting before restoring, so the target
+    /// window has read the prompt
+    pub restore_delay_ms: u64,
+}
+
+impl Default for PasteConfig {
+    fn default() -> Self {
+        Self {
+            backend: KeyboardBackend::Auto,
+            chord: if cfg!(target_os = "macos") {
+                "cmd+v".into()
+            } else {
+                "ctrl+v".into()
+            },
+            delay_ms: 150,
+            restore_clipboard: true,
+            restore_delay_ms: 500,
+        }
+    }
+}
+
+/// Which speech-to-text engine to use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptionBackend {
+    /// In-process whisper-rs with a downloaded ggml model.
+    #[default]
+    Local,
+    /// OpenAI-compatible `/v1/audio/transcriptions` server.
+    Remote,
+}
+
+/// Speech-to-text settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranscriptionConfig {
+    pub backend: TranscriptionBackend,
+    /// API root of the remote server, including the version (e.g. "http://host:8080/v1")
+    pub server_url: String,
+    /// Model name sent to the remote server
+    pub model: String,
+    /// Bearer token for the remote server; empty to send none.
+    /// Kept in the secret store, never written to `config.json`.
+    #[serde(skip_serializing)]
+    pub api_key: String,
+    /// Spoken language as an ISO-639-1 code
+    pub language: String,
+    /// Give up on the remote server after this long, upload included
+    pub timeout_secs: u64,
+}
+
+impl Default for TranscriptionConfig {
+    fn default() -> Self {
+        Self {
+            backend: TranscriptionBackend::Local,
+            server_url: "http://127.0.0.1:8080/v1".into(),
+            model: "whisper-1".into(),
+            api_key: String::new(),
+            language: "en".into(),
+            timeout_secs: 60,
+        }
+    }
+}
+
+/// Which LLM service refines transcripts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefinementProvider {
+    #[default]
+    Gemini,
+    /// Any OpenAI-compatible `/chat/completions` server
+    #[serde(rename = "openai")]
+    OpenAi,
+    Ollama,
+}
+
+impl RefinementProvider {
+    #[cfg(feature = "ui")]
+    pub const ALL: [RefinementProvider; 3] = [Self::Gemini, Self::OpenAi, Self::Ollama];
+
+    fn default_base_url(self) -> &'static str {
+        match self {
+            Self::Gemini => "https://generativelanguage.googleapis.com/v1beta",
+            Self::OpenAi => "https://api.openai.com/v1",
+            Self::Ollama => "http://127.0.0.1:11434",
+        }
+    }
+
+    fn default_model(self) -> &'static str {
+        match self {
+            Self::Gemini => "gemini-2.5-flash",
+            Self::OpenAi => "gpt-4o-mini",
+            Self::Ollama => "llama3.2",
+        }
+    }
+
+    /// Human-readable name for the dashboard.
+    #[cfg(feature = "ui")]
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Gemini => "Gemini",
+            Self::OpenAi => "OpenAI-compatible",
+            Self::Ollama => "Ollama",
+        }
+    }
+}
+
+/// Provider endpoints shared by all profiles. Empty strings fall back to
+/// the provider's public/default endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RefinementConfig {
+    pub gemini_base_url: String,
+    /// API root of an OpenAI-compatible server, including the version
+    pub openai_base_url: String,
+    /// Bearer token for the OpenAI-compatible server (Gemini uses `gemini_api_key`).
+    /// Kept in the secret store, never written to `config.json`.
+    #[serde(skip_serializing)]
+    pub openai_api_key: String,
+    pub ollama_base_url: String,
+    /// Stream tokens to the overlay as they are generated
+    pub stream: bool,
+    /// Timeout for a single request attempt, in seconds
+    pub timeout_secs: u64,
+    /// Retries on rate limiting (429), server errors (5xx) and network errors
+    pub max_retries: u32,
+    /// Copy the raw transcript if refinement has not finished after this
+    /// many seconds, retries included. 0 waits for the provider.
+    pub fallback_after_secs: u64,
+}
+
+impl Default for RefinementConfig {
+    fn default() -> Self {
+        Self {
+            gemini_base_url: String::new(),
+            openai_base_url: String::new(),
+            openai_api_key: String::new(),
+            ollama_base_url: String::new(),
+            stream: true,
+            timeout_secs: 30,
+            max_retries: 2,
+            fallback_after_secs: 20,
+        }
+    }
+}
+
+impl RefinementConfig {
+    pub fn base_url(&self, provider: RefinementProvider) -> String {
+        let configured = match provider {
+            RefinementProvider::Gemini => &self.gemini_base_url,
+            RefinementProvider::OpenAi => &self.openai_base_url,
+            RefinementProvider::Ollama => &self.ollama_base_url,
+        };
+        if configured.is_empty() {
+            provider.default_base_url().to_string()
+        } else {
+            configured.clone()
+        }
+    }
+}
+
+/// A user-defined replacement applied by the rule-based cleanup,
+/// e.g. "get hub" → "GitHub". Matching is case-insensitive and whole-word.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Substitution {
+    pub from: String,
+    pub to: String,
+}
+
+/// Offline, rule-based transcript cleanup.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CleanupConfig {
+    /// Also run the cleanup before sending text to an LLM
+    pub pre_pass: bool,
+    pub substitutions: Vec<Substitution>,
+}
+
+/// What a spoken formatting command turns into.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandAction {
+    /// Literal text, e.g. "\n" for "new line" or "\n- " for "bullet point"
+    Insert(String),
+    /// Start a fenced Markdown code block on its own line
+    OpenCodeBlock,
+    /// End a fenced Markdown code block
+    CloseCodeBlock,
+    /// Open or close inline code: "backtick foo backtick" → `foo`
+    InlineCode,
+    /// Join the following words as snake_case
+    SnakeCase,
+    /// Join the following words as camelCase
+    CamelCase,
+    /// Join the following words as PascalCase
+    PascalCase,
+    /// Join the following words as kebab-case
+    KebabCase,
+    /// Join the following words as CONSTANT_CASE
+    ConstantCase,
+    /// End a case-conversion run early: "snake case user id end case"
+    EndCase,
+}
+
+/// A phrase that, when dictated, is replaced by formatting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpokenCommand {
+    pub phrase: String,
+    pub action: CommandAction,
+}
+
+impl SpokenCommand {
+    fn new(phrase: &str, action: CommandAction) -> Self {
+        Self {
+            phrase: phrase.into(),
+            action,
+        }
+    }
+}
+
+fn default_commands() -> Vec<SpokenCommand> {
+    use CommandAction::*;
+    vec![
+        SpokenCommand::new("new line", Insert("\n".into())),
+        SpokenCommand::new("newline", Insert("\n".into())),
+        SpokenCommand::new("new paragraph", Insert("\n\n".into())),
+        SpokenCommand::new("bullet point", Insert("\n- ".into())),
+        SpokenCommand::new("open code block", OpenCodeBlock),
+        SpokenCommand::new("close code block", CloseCodeBlock),
+        SpokenCommand::new("backtick", InlineCode),
+        SpokenCommand::new("snake case", SnakeCase),
+        SpokenCommand::new("camel case", CamelCase),
+        SpokenCommand::new("pascal case", PascalCase),
+        SpokenCommand::new("kebab case", KebabCase),
+        SpokenCommand::new("constant case", ConstantCase),
+        SpokenCommand::new("end case", EndCase),
+    ]
+}
+
+/// Spoken formatting commands, interpreted before refinement.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandsConfig {
+    pub enabled: bool,
+    pub commands: Vec<SpokenCommand>,
+}
+
+impl Default for CommandsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            commands: default_commands(),
+        }
+    }
+}
+
+/// A named refinement style: its own prompt, sampling settings and provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RefinementProfile {
+    pub name: String,
+    pub provider: RefinementProvider,
+    /// Model name; "" uses the provider default
+    pub model: String,
+    pub system_prompt: String,
+    pub temperature: f32,
+    pub max_tokens: u32,
+    /// Optional hotkey that selects this profile and toggles recording
+    pub hotkey: Option<HotkeyConfig>,
+    /// Show the prompt in an editable review window before copying it
+    pub review: bool,
+    /// Press the paste shortcut in the focused window after copying
+    pub auto_paste: bool,
+    /// Paste shortcut for this profile, e.g. "ctrl+shift+v" for terminals;
+    /// "" uses `paste.chord`
+    pub paste_chord: String,
+}
+
+impl Default for RefinementProfile {
+    fn default() -> Self {
+        Self {
+            name: "Claude Code".into(),
+            provider: RefinementProvider::Gemini,
+            model: String::new(),
+            system_prompt: crate::refiner::SYSTEM_PROMPT.into(),
+            temperature: 0.1,
+            max_tokens: 2048,
+            hotkey: None,
+            review: false,
+            auto_paste: false,
+            paste_chord: String::new(),
+        }
+    }
+}
+
+impl RefinementProfile {
+    pub fn effective_model(&self) -> String {
+        if self.model.is_empty() {
+            self.provider.default_model().to_string()
+        } else {
+            self.model.clone()
+        }
+    }
+}
+
+fn default_profiles() -> Vec<RefinementProfile> {
+    vec![
+        RefinementProfile::default(),
+        RefinementProfile {
+            name: "Commit message".into(),
+            system_prompt: crate::refiner::COMMIT_PROMPT.into(),
+            ..RefinementProfile::default()
+        },
+        RefinementProfile {
+            name: "Chat reply".into(),
+            system_prompt: crate::refiner::CHAT_PROMPT.into(),
+            temperature: 0.3,
+            ..RefinementProfile::default()
+        },
+    ]
+}
+
+fn default_active_profile() -> String {
+    RefinementProfile::default().name
+}
+
+/// API keys read from the secret store, for `Config::apply_secrets`.
+#[derive(Debug, Clone, Default)]
+pub struct Secrets {
+    keys: Vec<(&'static str, String)>,
+    /// Keys from `config.json` were moved to the store
+    migrated: bool,
+    /// Some keys from `config.json` could not be moved
+    failed: bool,
+    store: &'static str,
+}
+
+/// Move the keys in `pending` that have a value into the secret store and
+/// read the others from it. Talks to the Secret Service over D-Bus and may
+/// wait for the keyring to unlock, so keep it off the main thread.
+pub fn fetch_secrets(pending: Vec<(&'static str, String)>) -> Secrets {
+    fetch_secrets_from(crate::secrets::store(), pending)
+}
+
+fn fetch_secrets_from(store: &dyn SecretStore, pending: Vec<(&'static str, String)>) -> Secrets {
+    let mut secrets = Secrets {
+        store: store.name(),
+        ..Secrets::default()
+    };
+    for (name, value) in pending {
+        if !value.is_empty() {
+            match store.set(name, &value) {
+                Ok(()) => secrets.migrated = true,
+                Err(e) => {
+                    log::warn!("Failed to move {name} to the {}: {e}", store.name());
+                    secrets.failed = true;
+                }
+            }
+            continue;
+        }
+        match store.get(name) {
+            Ok(Some(secret)) => secrets.keys.push((name, secret)),
+            Ok(None) => {}
+            Err(e) => log::warn!("Failed to read {name} from the {}: {e}", store.name()),
+        }
+    }
+    secrets
+}
+
+/// Store or clear an API key, then `Config::set_secret` it. Blocks like
+/// `fetch_secrets`.
+#[cfg(feature = "ui")]
+pub fn store_secret(name: &str, value: &str) -> Result<(), String> {
+    let store = crate::secrets::store();
+    store
+        .set(name, value)
+        .map_err(|e| format!("could not save to the {}: {e}", store.name()))
+}
+
 /// Top-level application configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub hotkey: HotkeyConfig,
+    /// Kept in the secret store; read from `config.json` only to migrate it.
+    #[serde(default, skip_serializing)]
     pub gemini_api_key: String,
+    #[serde(default)]
+    pub append: AppendConfig,
+    #[serde(default)]
+    pub output: OutputConfig,
+    #[serde(default)]
+    pub paste: PasteConfig,
+    #[serde(default)]
+    pub transcription: TranscriptionConfig,
+    #[serde(default)]
+    pub refinement: RefinementConfig,
+    #[serde(default)]
+    pub cleanup: CleanupConfig,
+    #[serde(default)]
+    pub commands: CommandsConfig,
+    #[serde(default = "default_profiles")]
+    pub profiles: Vec<RefinementProfile>,
+    #[serde(default = "default_active_profile")]
+    pub active_profile: String,
+    /// Set while API keys in `config.json` have not been (or could not be)
+    /// moved to the secret store, so `save` keeps writing them there.
+    #[serde(skip)]
+    plaintext_secrets: bool,
 }
 
 impl Default for Config {
@@ -35,6 +536,16 @@ impl Default for Config {
         Self {
             hotkey: HotkeyConfig::default(),
             gemini_api_key: String::new(),
+            append: AppendConfig::default(),
+            output: OutputConfig::default(),
+            paste: PasteConfig::default(),
+            transcription: TranscriptionConfig::default(),
+            refinement: RefinementConfig::default(),
+            cleanup: CleanupConfig::default(),
+            commands: CommandsConfig::default(),
+            profiles: default_profiles(),
+            active_profile: default_active_profile(),
+            plaintext_secrets: false,
         }
     }
 }
@@ -52,20 +563,242 @@ impl Config {
     }
 
     /// Load from disk, returning defaults if file doesn't exist or is invalid.
+    /// API keys come later, from `fetch_secrets` and `apply_secrets`.
     pub fn load() -> Self {
-        let path = Self::path();
-        match fs::read_to_string(&path) {
+        Self::load_from(&Self::path())
+    }
+
+    fn load_from(path: &Path) -> Self {
+        let mut config: Self = match fs::read_to_string(path) {
             Ok(data) => serde_json::from_str(&data).unwrap_or_default(),
             Err(_) => Self::default(),
+        };
+        // Until `apply_secrets`, keys still in the file must survive a save
+        config.plaintext_secrets = config.secrets_mut().iter().any(|(_, v)| !v.is_empty());
+        config
+    }
+
+    /// API keys paired with their secret store names.
+    fn secrets_mut(&mut self) -> [(&'static str, &mut String); 3] {
+        [
+            (crate::secrets::GEMINI_API_KEY, &mut self.gemini_api_key),
+            (crate::secrets::OPENAI_API_KEY, &mut self.refinement.openai_api_key),
+            (crate::secrets::TRANSCRIPTION_API_KEY, &mut self.transcription.api_key),
+        ]
+    }
+
+    /// What `fetch_secrets` should look up: every API key's name, with the
+    /// value still in `config.json` (older versions stored them there) or
+    /// an empty string.
+    pub fn pending_secrets(&mut self) -> Vec<(&'static str, String)> {
+        self.secrets_mut()
+            .into_iter()
+            .map(|(name, value)| (name, value.clone()))
+            .collect()
+    }
+
+    /// Fill in the API keys read by `fetch_secrets`, leaving alone any the
+    /// user entered in the meantime. If keys were moved out of
+    /// `config.json`, the file is rewritten without them.
+    pub fn apply_secrets(&mut self, secrets: Secrets) {
+        if self.take_secrets(secrets) {
+            if let Err(e) = self.save() {
+                log::warn!("Failed to save config: {e}");
+            }
+        }
+    }
+
+    /// The in-memory part of `apply_secrets`; true if `config.json` still
+    /// holds keys that are now in the store.
+    fn take_secrets(&mut self, secrets: Secrets) -> bool {
+        for (name, value) in self.secrets_mut() {
+            if let Some((_, secret)) = secrets.keys.iter().find(|(n, _)| *n == name) {
+                if value.is_empty() {
+                    *value = secret.clone();
+                }
+            }
+        }
+        // Keep the plaintext copy until every key is safely stored
+        self.plaintext_secrets = secrets.failed;
+        let migrated = secrets.migrated && !secrets.failed;
+        if migrated {
+            log::info!("Moved API keys from config.json to the {}", secrets.store);
         }
+        migrated
+    }
+
+    /// Use an API key that `store_secret` has stored or cleared; `name` is
+    /// one of the `crate::secrets` constants.
+    #[cfg(feature = "ui")]
+    pub fn set_secret(&mut self, name: &str, value: &str) {
+        if let Some((_, field)) = self.secrets_mut().into_iter().find(|(n, _)| *n == name) {
+            *field = value.to_string();
+        }
+    }
+
+    /// The selected profile, falling back to the first one (or the built-in default).
+    pub fn active_profile(&self) -> RefinementProfile {
+        self.profile(&self.active_profile)
+            .or_else(|| self.profiles.first())
+            .cloned()
+            .unwrap_or_default()
     }
 
-    /// Persist to disk.
+    pub fn profile(&self, name: &str) -> Option<&RefinementProfile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    /// Persist to disk. API keys are left out unless they could not be
+    /// moved to the secret store.
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let dir = Self::dir();
-        fs::create_dir_all(&dir)?;
-        let data = serde_json::to_string_pretty(self)?;
-        fs::write(Self::path(), data)?;
+        fs::create_dir_all(Self::dir())?;
+        self.save_to(&Self::path())
+    }
+
+    fn save_to(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut value = serde_json::to_value(self)?;
+        if self.plaintext_secrets {
+            value["gemini_api_key"] = self.gemini_api_key.clone().into();
+            value["refinement"]["openai_api_key"] = self.refinement.openai_api_key.clone().into();
+            value["transcription"]["api_key"] = self.transcription.api_key.clone().into();
+        }
+        let data = serde_json::to_string_pretty(&value)?;
+        fs::write(path, data)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::EncryptedFileStore;
+    use crate::temp_dir::temp_dir;
+
+    /// A secret store that cannot be written to, like a locked keyring
+    /// whose unlock prompt was dismissed.
+    struct BrokenStore;
+
+    impl SecretStore for BrokenStore {
+        fn name(&self) -> &'static str {
+            "broken store"
+        }
+
+        fn get(
+            &self,
+            _key: &str,
+        ) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+            Err("locked".into())
+        }
+
+        fn set(
+            &self,
+            _key: &str,
+            _value: &str,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Err("locked".into())
+        }
+    }
+
+    /// A `config.json` as older versions wrote it, with keys in plain text.
+    fn write_plaintext_config(path: &Path) {
+        let mut value = serde_json::to_value(Config::default()).unwrap();
+        value["gemini_api_key"] = "AIza-plain".into();
+        value["refinement"]["openai_api_key"] = "sk-plain".into();
+        fs::write(path, value.to_string()).unwrap();
+    }
+
+    fn saved_json(path: &Path) -> serde_json::Value {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn plaintext_keys_move_to_the_store() {
+        let dir = temp_dir("config-migrate");
+        let path = dir.join("config.json");
+        write_plaintext_config(&path);
+
+        let mut config = Config::load_from(&path);
+        assert!(config.plaintext_secrets);
+        let pending = config.pending_secrets();
+        assert_eq!(
+            pending,
+            [
+                (crate::secrets::GEMINI_API_KEY, "AIza-plain".to_string()),
+                (crate::secrets::OPENAI_API_KEY, "sk-plain".to_string()),
+                (crate::secrets::TRANSCRIPTION_API_KEY, String::new()),
+            ]
+        );
+
+        let store = EncryptedFileStore::new(dir.join("secrets"));
+        let secrets = fetch_secrets_from(&store, pending);
+        assert!(secrets.migrated && !secrets.failed);
+        assert_eq!(
+            store
+                .get(crate::secrets::GEMINI_API_KEY)
+                .unwrap()
+                .as_deref(),
+            Some("AIza-plain")
+        );
+        assert_eq!(
+            store
+                .get(crate::secrets::OPENAI_API_KEY)
+                .unwrap()
+                .as_deref(),
+            Some("sk-plain")
+        );
+
+        assert!(config.take_secrets(secrets));
+        assert!(!config.plaintext_secrets);
+        assert_eq!(config.gemini_api_key, "AIza-plain");
+        config.save_to(&path).unwrap();
+        let saved = saved_json(&path);
+        assert!(saved.get("gemini_api_key").is_none());
+        assert!(saved["refinement"].get("openai_api_key").is_none());
+
+        // The next start reads the keys back from the store
+        let mut config = Config::load_from(&path);
+        assert!(!config.plaintext_secrets);
+        let secrets = fetch_secrets_from(&store, config.pending_secrets());
+        assert!(!secrets.migrated);
+        assert!(!config.take_secrets(secrets));
+        assert_eq!(config.gemini_api_key, "AIza-plain");
+        assert_eq!(config.refinement.openai_api_key, "sk-plain");
+    }
+
+    #[test]
+    fn plaintext_keys_stay_when_the_store_fails() {
+        let dir = temp_dir("config-store-fails");
+        let path = dir.join("config.json");
+        write_plaintext_config(&path);
+
+        let mut config = Config::load_from(&path);
+        let secrets = fetch_secrets_from(&BrokenStore, config.pending_secrets());
+        assert!(secrets.failed);
+        assert!(!config.take_secrets(secrets));
+        assert!(config.plaintext_secrets);
+
+        // Any later save, e.g. after a settings change, keeps the keys
+        config.output.primary_selection = !config.output.primary_selection;
+        config.save_to(&path).unwrap();
+        let saved = saved_json(&path);
+        assert_eq!(saved["gemini_api_key"], "AIza-plain");
+        assert_eq!(saved["refinement"]["openai_api_key"], "sk-plain");
+        assert_eq!(saved["transcription"]["api_key"], "");
+    }
+
+    #[test]
+    fn applied_secrets_keep_keys_entered_meanwhile() {
+        let mut config = Config::default();
+        config.refinement.openai_api_key = "typed".into();
+        config.apply_secrets(Secrets {
+            keys: vec![
+                (crate::secrets::GEMINI_API_KEY, "gemini".into()),
+                (crate::secrets::OPENAI_API_KEY, "stored".into()),
+            ],
+            ..Secrets::default()
+        });
+        assert_eq!(config.gemini_api_key, "gemini");
+        assert_eq!(config.refinement.openai_api_key, "typed");
+        assert!(config.transcription.api_key.is_empty());
+    }
+}
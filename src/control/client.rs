@@ -0,0 +1,58 @@
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+
+use serde_json::json;
+
+use super::{socket_path, Command};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+const USAGE: &str =
+    "usage: voice-prompt ctl <toggle|start|stop|cancel|status|last|history|subscribe>";
+
+/// `voice-prompt ctl <command>`: send one command to the running instance
+/// and print its JSON reply; `subscribe` keeps printing events until the
+/// app exits. Returns the exit code.
+pub fn run_client(args: &[String]) -> i32 {
+    run_client_at(&socket_path(), args, &mut std::io::stdout())
+}
+
+/// `run_client` against the socket at `path`, printing to `out`.
+pub(super) fn run_client_at(path: &Path, args: &[String], out: &mut dyn Write) -> i32 {
+    let [name] = args else {
+        eprintln!("{USAGE}");
+        return 2;
+    };
+    let Ok(command) = serde_json::from_value::<Command>(json!(name)) else {
+        eprintln!("unknown command: {name}\n{USAGE}");
+        return 2;
+    };
+    match send(path, command, out) {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            eprintln!("voice-prompt: {e}");
+            1
+        }
+    }
+}
+
+/// Print the reply, and then any events. Returns whether the command
+/// succeeded.
+fn send(path: &Path, command: Command, out: &mut dyn Write) -> Result<bool, Error> {
+    let mut stream = UnixStream::connect(path)
+        .map_err(|e| format!("Voice Prompt is not running ({}: {e})", path.display()))?;
+    writeln!(stream, "{}", json!({ "cmd": command }))?;
+
+    let mut lines = BufReader::new(stream).lines();
+    let reply = lines.next().ok_or("no reply")??;
+    writeln!(out, "{reply}")?;
+    let ok = serde_json::from_str::<serde_json::Value>(&reply)?["ok"] == true;
+    if ok && command == Command::Subscribe {
+        for line in lines {
+            writeln!(out, "{}", line?)?;
+        }
+    }
+    Ok(ok)
+}
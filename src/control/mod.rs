@@ -0,0 +1,151 @@
+mod client;
+mod server;
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+pub use client::run_client;
+pub use server::{start_server, Events, Reply};
+
+/// A request from a control client, sent as one JSON object per line:
+/// `{"cmd": "toggle"}`, optionally with an `"id"` echoed in the reply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    /// Same as the hotkey
+    Toggle,
+    /// Start recording if idle
+    Start,
+    /// Stop recording and process it
+    Stop,
+    /// Stop recording and throw the audio away
+    Cancel,
+    Status,
+    /// The most recent prompt in the history
+    Last,
+    /// Every prompt in the history, oldest first
+    History,
+    /// Keep the connection open and receive events
+    Subscribe,
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    cmd: Command,
+    #[serde(default)]
+    id: Option<serde_json::Value>,
+}
+
+/// `$XDG_RUNTIME_DIR/voice-prompt.sock`, or without one, a socket in
+/// `voice-prompt-<uid>` in the temp dir. Either directory is closed to
+/// other users, so the socket is never reachable by them, whatever its mode.
+pub fn socket_path() -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(|| std::env::temp_dir().join(format!("voice-prompt-{}", uid())))
+        .join("voice-prompt.sock")
+}
+
+fn uid() -> u32 {
+    // SAFETY: getuid has no preconditions and always succeeds
+    unsafe { libc::getuid() }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::app::BackendEvent;
+    use crate::temp_dir::temp_dir;
+
+    /// Serve every client on `listener` as the server thread does, with a
+    /// main loop that answers `status` and refuses everything else.
+    fn serve(listener: UnixListener) {
+        let (backend, events) = async_channel::unbounded();
+        std::thread::spawn(move || {
+            while let Ok(event) = events.recv_blocking() {
+                let BackendEvent::Control(command, reply) = event else {
+                    continue;
+                };
+                let answer = match command {
+                    Command::Status => json!({ "ok": true, "status": "idle", "text": "Idle" }),
+                    _ => json!({ "ok": false, "error": "not recording" }),
+                };
+                let _ = reply.send_blocking(answer);
+            }
+        });
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let (stream, backend) = (stream.unwrap(), backend.clone());
+                std::thread::spawn(move || {
+                    server::serve_client(stream, backend, Default::default())
+                });
+            }
+        });
+    }
+
+    /// Run the `ctl` client against `path`, returning its exit code and the
+    /// JSON it printed.
+    fn ctl(path: &Path, command: &str) -> (i32, Vec<Value>) {
+        let mut out = Vec::new();
+        let code = client::run_client_at(path, &[command.to_string()], &mut out);
+        let printed = String::from_utf8(out).unwrap();
+        let lines = printed.lines().map(|l| serde_json::from_str(l).unwrap());
+        (code, lines.collect())
+    }
+
+    #[test]
+    fn client_gets_the_reply() {
+        let dir = temp_dir("control-client");
+        let path = dir.join("control.sock");
+        serve(UnixListener::bind(&path).unwrap());
+
+        assert_eq!(
+            ctl(&path, "status"),
+            (
+                0,
+                vec![json!({ "ok": true, "status": "idle", "text": "Idle" })]
+            )
+        );
+        assert_eq!(
+            ctl(&path, "stop"),
+            (1, vec![json!({ "ok": false, "error": "not recording" })])
+        );
+        // Never reaches the socket
+        assert_eq!(ctl(&path, "dance"), (2, vec![]));
+        assert_eq!(ctl(&dir.join("missing.sock"), "status"), (1, vec![]));
+    }
+
+    #[test]
+    fn one_reply_per_request_line() {
+        let dir = temp_dir("control-lines");
+        let path = dir.join("control.sock");
+        serve(UnixListener::bind(&path).unwrap());
+
+        let mut stream = UnixStream::connect(&path).unwrap();
+        stream
+            .write_all(b"{\"cmd\": \"status\", \"id\": 7}\nnot json\n\n{\"cmd\": \"stop\"}\n")
+            .unwrap();
+        let mut lines = BufReader::new(stream).lines();
+        let mut reply =
+            || -> Value { serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap() };
+
+        assert_eq!(
+            reply(),
+            json!({ "ok": true, "status": "idle", "text": "Idle", "id": 7 })
+        );
+        let bad = reply();
+        assert_eq!(bad["ok"], false);
+        assert!(
+            bad["error"].as_str().unwrap().starts_with("bad request: "),
+            "{bad}"
+        );
+        // The blank line is skipped, and the connection still works
+        assert_eq!(reply(), json!({ "ok": false, "error": "not recording" }));
+    }
+}
@@ -0,0 +1,171 @@
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::json;
+
+use super::{socket_path, uid, Command, Request};
+use crate::app::BackendEvent;
+
+/// Where the main loop sends its answer to a control command.
+pub type Reply = async_channel::Sender<serde_json::Value>;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+type Subscribers = Arc<Mutex<Vec<Arc<Mutex<UnixStream>>>>>;
+
+/// A subscriber that stops reading is dropped after this long.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Sends events to every subscribed client.
+#[derive(Clone)]
+pub struct Events {
+    sender: async_channel::Sender<serde_json::Value>,
+}
+
+impl Events {
+    pub fn emit(&self, event: serde_json::Value) {
+        let _ = self.sender.try_send(event);
+    }
+}
+
+/// Create `dir` with mode 0700, or check that the existing one is ours and
+/// closed to others; someone else could have made it first to listen in.
+fn ensure_private_dir(dir: &std::path::Path) -> Result<(), Error> {
+    match std::fs::DirBuilder::new().mode(0o700).create(dir) {
+        Ok(()) => return Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(format!("Failed to create {}: {e}", dir.display()).into()),
+    }
+    let meta = std::fs::symlink_metadata(dir)?;
+    if !meta.is_dir() || meta.uid() != uid() || meta.mode() & 0o077 != 0 {
+        return Err(format!(
+            "{} is not a directory only this user can access",
+            dir.display()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Listen on the control socket. Commands are forwarded to the main loop as
+/// `BackendEvent::Control`; events passed to the returned `Events` go out to
+/// subscribers.
+pub fn start_server(backend: async_channel::Sender<BackendEvent>) -> Result<Events, Error> {
+    let path = socket_path();
+    if dirs::runtime_dir().is_none() {
+        if let Some(dir) = path.parent() {
+            ensure_private_dir(dir)?;
+        }
+    }
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            return Err(format!("{} is in use by another instance", path.display()).into());
+        }
+        // Left over from a crash
+        std::fs::remove_file(&path)?;
+    }
+    let listener =
+        UnixListener::bind(&path).map_err(|e| format!("Failed to bind {}: {e}", path.display()))?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    log::info!("Control socket listening on {}", path.display());
+
+    let subscribers: Subscribers = Arc::default();
+    let (sender, receiver) = async_channel::unbounded::<serde_json::Value>();
+
+    {
+        let subscribers = subscribers.clone();
+        std::thread::Builder::new()
+            .name("control-events".into())
+            .spawn(move || {
+                while let Ok(event) = receiver.recv_blocking() {
+                    let line = format!("{event}\n");
+                    subscribers.lock().unwrap().retain(|client| {
+                        let mut stream = client.lock().unwrap();
+                        stream.write_all(line.as_bytes()).is_ok()
+                    });
+                }
+            })?;
+    }
+
+    std::thread::Builder::new()
+        .name("control-server".into())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        log::warn!("Control socket accept failed: {e}");
+                        continue;
+                    }
+                };
+                let backend = backend.clone();
+                let subscribers = subscribers.clone();
+                let spawned = std::thread::Builder::new()
+                    .name("control-client".into())
+                    .spawn(move || {
+                        if let Err(e) = serve_client(stream, backend, subscribers) {
+                            log::debug!("Control client disconnected: {e}");
+                        }
+                    });
+                if let Err(e) = spawned {
+                    log::warn!("Failed to spawn control client thread: {e}");
+                }
+            }
+        })?;
+
+    Ok(Events { sender })
+}
+
+/// Answer one client's requests, a JSON object per line each way, until it
+/// hangs up.
+pub(super) fn serve_client(
+    stream: UnixStream,
+    backend: async_channel::Sender<BackendEvent>,
+    subscribers: Subscribers,
+) -> Result<(), Error> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let reader = BufReader::new(stream.try_clone()?);
+    // Shared with the event thread once subscribed, so lines don't interleave
+    let writer = Arc::new(Mutex::new(stream));
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let mut reply = match request.cmd {
+                    Command::Subscribe => {
+                        subscribers.lock().unwrap().push(writer.clone());
+                        json!({ "ok": true })
+                    }
+                    command => ask(&backend, command),
+                };
+                if let Some(id) = request.id {
+                    reply["id"] = id;
+                }
+                reply
+            }
+            Err(e) => json!({ "ok": false, "error": format!("bad request: {e}") }),
+        };
+        writeln!(writer.lock().unwrap(), "{reply}")?;
+    }
+    Ok(())
+}
+
+/// Hand a command to the main loop and wait for its answer.
+fn ask(backend: &async_channel::Sender<BackendEvent>, command: Command) -> serde_json::Value {
+    let (reply, answer) = async_channel::bounded(1);
+    if backend
+        .send_blocking(BackendEvent::Control(command, reply))
+        .is_err()
+    {
+        return json!({ "ok": false, "error": "shutting down" });
+    }
+    answer
+        .recv_blocking()
+        .unwrap_or_else(|_| json!({ "ok": false, "error": "no reply" }))
+}
@@ -0,0 +1,247 @@
+use gio::prelude::*;
+
+use crate::app::BackendEvent;
+use crate::control::Command;
+
+/// Interface exported next to GApplication's own on the app's object path.
+/// The bus name is the application ID, `com.github.tr4m0ryp.voice-prompt`,
+/// but interface names may not contain "-", so the interface is spelt in
+/// CamelCase as D-Bus convention has it.
+pub const INTERFACE: &str = "com.github.tr4m0ryp.VoicePrompt";
+const ERROR_NAME: &str = "com.github.tr4m0ryp.VoicePrompt.Error.Failed";
+
+const INTROSPECTION: &str = r#"
+<node>
+  <interface name="com.github.tr4m0ryp.VoicePrompt">
+    <method name="Toggle"/>
+    <method name="Start"/>
+    <method name="Stop"/>
+    <method name="Cancel"/>
+    <method name="GetStatus">
+      <arg name="status" type="s" direction="out"/>
+      <arg name="text" type="s" direction="out"/>
+    </method>
+    <method name="GetLastPrompt">
+      <arg name="text" type="s" direction="out"/>
+    </method>
+    <signal name="StatusChanged">
+      <arg name="status" type="s"/>
+      <arg name="text" type="s"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+/// The exported interface, for emitting signals.
+pub struct DbusService {
+    connection: gio::DBusConnection,
+    path: String,
+}
+
+impl DbusService {
+    pub fn status_changed(&self, status: &str, text: &str) {
+        let result = self.connection.emit_signal(
+            None,
+            &self.path,
+            INTERFACE,
+            "StatusChanged",
+            Some(&(status, text).to_variant()),
+        );
+        if let Err(e) = result {
+            log::warn!("Failed to emit StatusChanged: {e}");
+        }
+    }
+}
+
+/// Export the interface on the application's session bus connection. Calls
+/// are answered by the main loop through `BackendEvent::Control`, like the
+/// control socket's.
+pub fn register(
+    app: &impl IsA<gio::Application>,
+    backend: async_channel::Sender<BackendEvent>,
+) -> Result<DbusService, Box<dyn std::error::Error + Send + Sync>> {
+    let connection = app.dbus_connection().ok_or("not on the session bus")?;
+    let path = app
+        .dbus_object_path()
+        .ok_or("no D-Bus object path")?
+        .to_string();
+    let node = gio::DBusNodeInfo::for_xml(INTROSPECTION)?;
+    let interface = node
+        .lookup_interface(INTERFACE)
+        .ok_or("interface missing from introspection data")?;
+
+    connection
+        .register_object(&path, &interface)
+        .method_call(move |_, _, _, _, method, _, invocation| {
+            let command = match method {
+                "Toggle" => Command::Toggle,
+                "Start" => Command::Start,
+                "Stop" => Command::Stop,
+                "Cancel" => Command::Cancel,
+                "GetStatus" => Command::Status,
+                "GetLastPrompt" => Command::Last,
+                _ => {
+                    invocation.return_dbus_error(ERROR_NAME, &format!("unknown method {method}"));
+                    return;
+                }
+            };
+            let (reply, answer) = async_channel::bounded(1);
+            if backend
+                .try_send(BackendEvent::Control(command, reply))
+                .is_err()
+            {
+                invocation.return_dbus_error(ERROR_NAME, "shutting down");
+                return;
+            }
+            glib::spawn_future_local(async move {
+                match answer.recv().await {
+                    Ok(answer) => respond(invocation, command, &answer),
+                    Err(_) => invocation.return_dbus_error(ERROR_NAME, "no reply"),
+                }
+            });
+        })
+        .build()?;
+
+    log::info!("D-Bus interface {INTERFACE} exported at {path}");
+    Ok(DbusService { connection, path })
+}
+
+/// Turn a control answer into the method's return value.
+fn respond(invocation: gio::DBusMethodInvocation, command: Command, answer: &serde_json::Value) {
+    if answer["ok"] != true {
+        let error = answer["error"].as_str().unwrap_or("failed");
+        invocation.return_dbus_error(ERROR_NAME, error);
+        return;
+    }
+    let text = |value: &serde_json::Value| value.as_str().unwrap_or_default().to_string();
+    match command {
+        Command::Status => {
+            let value = (text(&answer["status"]), text(&answer["text"])).to_variant();
+            invocation.return_value(Some(&value));
+        }
+        Command::Last => {
+            let value = (text(&answer["prompt"]["text"]),).to_variant();
+            invocation.return_value(Some(&value));
+        }
+        _ => invocation.return_value(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    const APP_ID: &str = "com.github.tr4m0ryp.VoicePromptTest";
+
+    /// Answer control commands as the main loop would, announcing the
+    /// recording that Toggle starts.
+    async fn answer_commands(
+        receiver: async_channel::Receiver<BackendEvent>,
+        service: DbusService,
+    ) {
+        while let Ok(event) = receiver.recv().await {
+            let BackendEvent::Control(command, reply) = event else {
+                continue;
+            };
+            let answer = match command {
+                Command::Toggle => {
+                    service.status_changed("recording", "Recording...");
+                    serde_json::json!({ "ok": true })
+                }
+                Command::Status => {
+                    serde_json::json!({ "ok": true, "status": "recording", "text": "Recording..." })
+                }
+                Command::Last => {
+                    serde_json::json!({ "ok": true, "prompt": { "text": "Fix the bug" } })
+                }
+                _ => serde_json::json!({ "ok": false, "error": "not recording" }),
+            };
+            let _ = reply.send(answer).await;
+        }
+    }
+
+    #[test]
+    fn methods_and_status_signal() {
+        // TestDBus aborts the process when it can't start the daemon
+        if !crate::keyboard::on_path("dbus-daemon") {
+            eprintln!("skipping: dbus-daemon is not installed");
+            return;
+        }
+        let bus = gio::TestDBus::new(gio::TestDBusFlags::NONE);
+        bus.up();
+        let address = bus.bus_address().expect("test bus has an address");
+
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                context.block_on(async {
+                    let app = gio::Application::new(Some(APP_ID), gio::ApplicationFlags::empty());
+                    app.register(None::<&gio::Cancellable>)
+                        .expect("register the application");
+                    let (backend, receiver) = async_channel::unbounded();
+                    let service = register(&app, backend).expect("export the interface");
+                    let path = service.path.clone();
+                    glib::spawn_future_local(answer_commands(receiver, service));
+
+                    let client = gio::DBusConnection::for_address_sync(
+                        &address,
+                        gio::DBusConnectionFlags::AUTHENTICATION_CLIENT
+                            | gio::DBusConnectionFlags::MESSAGE_BUS_CONNECTION,
+                        None,
+                        None::<&gio::Cancellable>,
+                    )
+                    .expect("connect to the test bus");
+                    let proxy = gio::DBusProxy::new_sync(
+                        &client,
+                        gio::DBusProxyFlags::DO_NOT_LOAD_PROPERTIES,
+                        None,
+                        Some(APP_ID),
+                        &path,
+                        INTERFACE,
+                        None::<&gio::Cancellable>,
+                    )
+                    .expect("create a proxy");
+                    let (signals, received) = async_channel::unbounded();
+                    proxy.connect_local("g-signal", false, move |values| {
+                        let name: String = values[2].get().unwrap();
+                        let args: glib::Variant = values[3].get().unwrap();
+                        let _ = signals.try_send((name, args.get::<(String, String)>()));
+                        None
+                    });
+                    let call = |method: &str| {
+                        proxy.call_future(method, None, gio::DBusCallFlags::NONE, 5000)
+                    };
+
+                    call("Toggle").await.expect("Toggle");
+                    let status = call("GetStatus").await.expect("GetStatus");
+                    assert_eq!(
+                        status.get::<(String, String)>(),
+                        Some(("recording".into(), "Recording...".into()))
+                    );
+                    let last = call("GetLastPrompt").await.expect("GetLastPrompt");
+                    assert_eq!(last.get::<(String,)>(), Some(("Fix the bug".into(),)));
+                    let error = call("Stop")
+                        .await
+                        .expect_err("Stop fails when not recording");
+                    assert!(error.message().contains("not recording"), "{error}");
+
+                    let signal = glib::future_with_timeout(Duration::from_secs(5), received.recv())
+                        .await
+                        .expect("StatusChanged within 5 s")
+                        .unwrap();
+                    assert_eq!(
+                        signal,
+                        (
+                            "StatusChanged".to_string(),
+                            Some(("recording".to_string(), "Recording...".to_string()))
+                        )
+                    );
+                })
+            })
+            .unwrap();
+
+        bus.down();
+    }
+}
@@ -0,0 +1,194 @@
+/// One run of a word-level diff.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Same(String),
+    Removed(String),
+    Added(String),
+}
+
+/// Above this many word pairs the diff degrades to "all removed, all added"
+/// rather than allocating a huge table.
+const MAX_CELLS: usize = 4_000_000;
+
+/// Word-level diff from `old` to `new`. Line breaks are kept as `"\n"`
+/// tokens so structure added by refinement shows up. Adjacent words of the
+/// same kind are merged into one run.
+pub fn diff_words(old: &str, new: &str) -> Vec<Change> {
+    let a = tokenize(old);
+    let b = tokenize(new);
+    let (n, m) = (a.len(), b.len());
+
+    if n.saturating_mul(m) > MAX_CELLS {
+        let mut changes = Vec::new();
+        push(&mut changes, Change::Removed(join(&a)));
+        push(&mut changes, Change::Added(join(&b)));
+        return changes;
+    }
+
+    // lcs[i][j] = length of the longest common subsequence of a[i..] and b[j..]
+    let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+    let idx = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[idx(i, j)] = if same(a[i], b[j]) {
+                lcs[idx(i + 1, j + 1)] + 1
+            } else {
+                lcs[idx(i + 1, j)].max(lcs[idx(i, j + 1)])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && same(a[i], b[j]) {
+            push(&mut changes, Change::Same(b[j].to_string()));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[idx(i + 1, j)] >= lcs[idx(i, j + 1)]) {
+            push(&mut changes, Change::Removed(a[i].to_string()));
+            i += 1;
+        } else {
+            push(&mut changes, Change::Added(b[j].to_string()));
+            j += 1;
+        }
+    }
+    changes
+}
+
+/// Words and line breaks.
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    for (k, line) in text.lines().enumerate() {
+        if k > 0 {
+            tokens.push("\n");
+        }
+        tokens.extend(line.split_whitespace());
+    }
+    tokens
+}
+
+/// Refinement mostly fixes case and punctuation; don't flag those as edits.
+fn same(a: &str, b: &str) -> bool {
+    let core = |w: &str| {
+        w.trim_matches(|c: char| !c.is_alphanumeric())
+            .to_lowercase()
+    };
+    a == b || (!core(a).is_empty() && core(a) == core(b))
+}
+
+fn join(tokens: &[&str]) -> String {
+    let mut out = String::new();
+    for token in tokens {
+        if !out.is_empty() && *token != "\n" && !out.ends_with('\n') {
+            out.push(' ');
+        }
+        out.push_str(token);
+    }
+    out
+}
+
+/// Append a token, merging it into the previous run of the same kind.
+fn push(changes: &mut Vec<Change>, change: Change) {
+    let (Change::Same(text) | Change::Removed(text) | Change::Added(text)) = &change;
+    if text.is_empty() {
+        return;
+    }
+    match (changes.last_mut(), &change) {
+        (Some(Change::Same(prev)), Change::Same(text))
+        | (Some(Change::Removed(prev)), Change::Removed(text))
+        | (Some(Change::Added(prev)), Change::Added(text)) => {
+            if *text != "\n" && !prev.ends_with('\n') {
+                prev.push(' ');
+            }
+            prev.push_str(text);
+        }
+        _ => changes.push(change),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Change::*;
+
+    fn s(text: &str) -> String {
+        text.to_string()
+    }
+
+    #[test]
+    fn identical_text_is_one_run() {
+        assert_eq!(
+            diff_words("fix the bug", "fix the bug"),
+            [Same(s("fix the bug"))]
+        );
+    }
+
+    #[test]
+    fn case_and_punctuation_are_not_edits() {
+        assert_eq!(
+            diff_words("fix the bug", "Fix the bug."),
+            [Same(s("Fix the bug."))]
+        );
+    }
+
+    #[test]
+    fn pure_insert() {
+        assert_eq!(
+            diff_words("fix bug", "fix the login bug"),
+            [Same(s("fix")), Added(s("the login")), Same(s("bug"))]
+        );
+    }
+
+    #[test]
+    fn pure_delete() {
+        assert_eq!(
+            diff_words("um fix the uh bug", "fix the bug"),
+            [
+                Removed(s("um")),
+                Same(s("fix the")),
+                Removed(s("uh")),
+                Same(s("bug"))
+            ]
+        );
+    }
+
+    #[test]
+    fn replace() {
+        assert_eq!(
+            diff_words("call the fetch user function", "call the getUser function"),
+            [
+                Same(s("call the")),
+                Removed(s("fetch user")),
+                Added(s("getUser")),
+                Same(s("function"))
+            ]
+        );
+    }
+
+    #[test]
+    fn line_breaks_are_tokens() {
+        assert_eq!(
+            diff_words("milk eggs", "milk\neggs"),
+            [Same(s("milk")), Added(s("\n")), Same(s("eggs"))]
+        );
+    }
+
+    #[test]
+    fn empty_inputs() {
+        assert_eq!(diff_words("", ""), []);
+        assert_eq!(diff_words("", "new text"), [Added(s("new text"))]);
+        assert_eq!(diff_words("old text", ""), [Removed(s("old text"))]);
+        assert_eq!(diff_words(" ", "word"), [Added(s("word"))]);
+    }
+
+    #[test]
+    fn huge_inputs_degrade_to_remove_and_add() {
+        let old = "a ".repeat(2_001);
+        let new = "b ".repeat(2_001);
+        let changes = diff_words(&old, &new);
+        assert_eq!(changes.len(), 2);
+        assert!(matches!(&changes[0], Removed(text) if text.len() == 4_001));
+        assert!(matches!(&changes[1], Added(text) if text.len() == 4_001));
+    }
+}
@@ -3,13 +3,15 @@ use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+use super::{HotkeyAction, HotkeyBindings};
+#[cfg(feature = "ui")]
 use crate::config::HotkeyConfig;
 
 /// Start the hotkey listener on a dedicated OS thread.
-/// Sends `()` through the async channel each time the hotkey is triggered.
+/// Sends the matched action through the async channel each time a hotkey is triggered.
 pub fn start_listener(
-    sender: async_channel::Sender<()>,
-    shared_hotkey: Arc<Mutex<HotkeyConfig>>,
+    sender: async_channel::Sender<HotkeyAction>,
+    shared_hotkey: Arc<Mutex<HotkeyBindings>>,
 ) {
     std::thread::Builder::new()
         .name("hotkey-listener".into())
@@ -22,8 +24,8 @@ pub fn start_listener(
 }
 
 fn listener_loop(
-    sender: async_channel::Sender<()>,
-    shared_hotkey: Arc<Mutex<HotkeyConfig>>,
+    sender: async_channel::Sender<HotkeyAction>,
+    shared_hotkey: Arc<Mutex<HotkeyBindings>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut devices = open_keyboard_devices();
     if devices.is_empty() {
@@ -64,16 +66,20 @@ fn listener_loop(
         }
 
         // Check hotkey match
-        let hotkey = shared_hotkey.lock().unwrap().clone();
-        let mods_held = hotkey.modifiers.iter().all(|m| held_keys.contains(m));
-        let trigger_held = held_keys.contains(&hotkey.trigger);
-
-        if mods_held && trigger_held && last_trigger.elapsed() > debounce {
-            last_trigger = Instant::now();
-            log::info!("Hotkey triggered: {}", hotkey.display_name);
-            if sender.try_send(()).is_err() {
-                log::info!("GTK channel closed, exiting hotkey listener");
-                return Ok(());
+        let matched = shared_hotkey
+            .lock()
+            .unwrap()
+            .matching(&held_keys)
+            .map(|(action, hk)| (action, hk.display_name.clone()));
+
+        if let Some((action, display_name)) = matched {
+            if last_trigger.elapsed() > debounce {
+                last_trigger = Instant::now();
+                log::info!("Hotkey triggered: {display_name}");
+                if sender.try_send(action).is_err() {
+                    log::info!("Main loop channel closed, exiting hotkey listener");
+                    return Ok(());
+                }
             }
         }
 
@@ -119,6 +125,7 @@ fn open_keyboard_devices() -> Vec<Device> {
 /// Capture a single key combination from evdev devices.
 /// Returns when a non-modifier key is pressed while modifiers are held.
 /// Used by the hotkey dialog to detect the user's desired combo.
+#[cfg(feature = "ui")]
 pub fn capture_hotkey_combo() -> Option<HotkeyConfig> {
     let mut devices = open_keyboard_devices();
     if devices.is_empty() {
@@ -175,6 +182,7 @@ pub fn capture_hotkey_combo() -> Option<HotkeyConfig> {
     }
 }
 
+#[cfg(feature = "ui")]
 fn is_modifier(code: u16) -> bool {
     matches!(
         code,
@@ -183,6 +191,7 @@ fn is_modifier(code: u16) -> bool {
     )
 }
 
+#[cfg(feature = "ui")]
 fn key_name(code: u16) -> &'static str {
     match code {
         29 => "Ctrl",
@@ -197,6 +206,7 @@ fn key_name(code: u16) -> &'static str {
     }
 }
 
+#[cfg(feature = "ui")]
 fn trigger_name(code: u16) -> String {
     match code {
         1 => "Esc".into(),
@@ -214,6 +224,7 @@ fn trigger_name(code: u16) -> String {
     }
 }
 
+#[cfg(feature = "ui")]
 fn build_display_name(modifiers: &[u16], trigger: u16) -> String {
     let mut parts: Vec<String> = Vec::new();
     let mut seen = HashSet::new();
@@ -4,13 +4,14 @@ use std::time::{Duration, Instant};
 
 use rdev::{listen, Event, EventType, Key};
 
+use super::{HotkeyAction, HotkeyBindings};
 use crate::config::HotkeyConfig;
 
 /// Start the hotkey listener on a dedicated OS thread.
-/// Sends `()` through the async channel each time the hotkey is triggered.
+/// Sends the matched action through the async channel each time a hotkey is triggered.
 pub fn start_listener(
-    sender: async_channel::Sender<()>,
-    shared_hotkey: Arc<Mutex<HotkeyConfig>>,
+    sender: async_channel::Sender<HotkeyAction>,
+    shared_hotkey: Arc<Mutex<HotkeyBindings>>,
 ) {
     std::thread::Builder::new()
         .name("hotkey-listener".into())
@@ -33,16 +34,19 @@ pub fn start_listener(
                         let mut held = keys.lock().unwrap();
                         held.insert(code);
 
-                        let hk = hotkey.lock().unwrap().clone();
-                        let mods_held =
-                            hk.modifiers.iter().all(|m| held.contains(m));
-                        let trigger_held = held.contains(&hk.trigger);
+                        let matched = hotkey
+                            .lock()
+                            .unwrap()
+                            .matching(&held)
+                            .map(|(action, hk)| (action, hk.display_name.clone()));
 
                         let mut last = trigger.lock().unwrap();
-                        if mods_held && trigger_held && last.elapsed() > debounce {
-                            *last = Instant::now();
-                            log::info!("Hotkey triggered: {}", hk.display_name);
-                            let _ = tx.try_send(());
+                        if let Some((action, display_name)) = matched {
+                            if last.elapsed() > debounce {
+                                *last = Instant::now();
+                                log::info!("Hotkey triggered: {display_name}");
+                                let _ = tx.try_send(action);
+                            }
                         }
                     }
                     EventType::KeyRelease(key) => {
@@ -63,6 +67,7 @@ pub fn start_listener(
 /// Capture a single key combination.
 /// Returns when a non-modifier key is pressed while modifiers are held.
 /// Used by the hotkey dialog to detect the user's desired combo.
+#[cfg(feature = "ui")]
 pub fn capture_hotkey_combo() -> Option<HotkeyConfig> {
     let result: Arc<Mutex<Option<HotkeyConfig>>> = Arc::new(Mutex::new(None));
     let done: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
@@ -209,6 +214,7 @@ fn rdev_key_to_code(key: Key) -> u16 {
     }
 }
 
+#[cfg(feature = "ui")]
 fn is_modifier(code: u16) -> bool {
     matches!(
         code,
@@ -217,6 +223,7 @@ fn is_modifier(code: u16) -> bool {
     )
 }
 
+#[cfg(feature = "ui")]
 fn key_name(code: u16) -> &'static str {
     match code {
         29 | 97 => "Ctrl",
@@ -227,6 +234,7 @@ fn key_name(code: u16) -> &'static str {
     }
 }
 
+#[cfg(feature = "ui")]
 fn trigger_name(code: u16) -> String {
     match code {
         1 => "Esc".into(),
@@ -264,6 +272,7 @@ fn trigger_name(code: u16) -> String {
     }
 }
 
+#[cfg(feature = "ui")]
 fn build_display_name(modifiers: &[u16], trigger: u16) -> String {
     let mut parts: Vec<String> = Vec::new();
     let mut seen = HashSet::new();
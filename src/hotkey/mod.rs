@@ -7,3 +7,58 @@ pub use linux::*;
 mod macos;
 #[cfg(target_os = "macos")]
 pub use macos::*;
+
+use std::collections::HashSet;
+
+use crate::config::{Config, HotkeyConfig};
+
+/// What a matched hotkey asks the app to do.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HotkeyAction {
+    /// Start or stop recording with the active profile.
+    Toggle,
+    /// Switch to the named profile, then start or stop recording.
+    Profile(String),
+    /// Start or stop a take that is added to the draft.
+    Append,
+}
+
+/// All key combinations the listener watches, shared with the UI thread
+/// so rebinding takes effect without restarting the listener.
+#[derive(Debug, Clone, Default)]
+pub struct HotkeyBindings {
+    pub toggle: HotkeyConfig,
+    pub profiles: Vec<(String, HotkeyConfig)>,
+    pub append: Option<HotkeyConfig>,
+}
+
+impl HotkeyBindings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            toggle: config.hotkey.clone(),
+            profiles: config
+                .profiles
+                .iter()
+                .filter_map(|p| p.hotkey.clone().map(|hk| (p.name.clone(), hk)))
+                .collect(),
+            append: config.append.hotkey.clone(),
+        }
+    }
+
+    /// Find the binding satisfied by `held` keys. When several match
+    /// (Ctrl+Space vs Ctrl+Shift+Space) the one with most modifiers wins.
+    pub fn matching(&self, held: &HashSet<u16>) -> Option<(HotkeyAction, &HotkeyConfig)> {
+        let candidates = std::iter::once((HotkeyAction::Toggle, &self.toggle))
+            .chain(
+                self.profiles
+                    .iter()
+                    .map(|(name, hk)| (HotkeyAction::Profile(name.clone()), hk)),
+            )
+            .chain(self.append.iter().map(|hk| (HotkeyAction::Append, hk)));
+        candidates
+            .filter(|(_, hk)| {
+                held.contains(&hk.trigger) && hk.modifiers.iter().all(|m| held.contains(m))
+            })
+            .max_by_key(|(_, hk)| hk.modifiers.len())
+    }
+}
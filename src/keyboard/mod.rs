@@ -0,0 +1,662 @@
+#[cfg(target_os = "linux")]
+mod uinput;
+
+use std::process::Command;
+use std::sync::Mutex;
+
+use crate::config::KeyboardBackend;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// A modifier key in a shortcut.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    Ctrl,
+    Shift,
+    Alt,
+    /// Super on Linux, Command on macOS
+    Super,
+}
+
+impl Modifier {
+    /// evdev key code of the left-hand key.
+    fn code(self) -> u16 {
+        match self {
+            Self::Ctrl => 29,
+            Self::Shift => 42,
+            Self::Alt => 56,
+            Self::Super => 125,
+        }
+    }
+
+    /// Name understood by `wtype -M` and `xdotool key`.
+    fn tool_name(self) -> &'static str {
+        match self {
+            Self::Ctrl => "ctrl",
+            Self::Shift => "shift",
+            Self::Alt => "alt",
+            Self::Super => "super",
+        }
+    }
+}
+
+/// A keyboard shortcut such as "ctrl+shift+v" or "shift+insert".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chord {
+    pub modifiers: Vec<Modifier>,
+    /// Lowercase key name: a letter, a digit or a named key
+    pub key: String,
+}
+
+impl Chord {
+    /// Parse "+"-separated key names, case-insensitively. The last part is
+    /// the key; the others must be modifiers.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let parts: Vec<String> = text.split('+').map(|p| p.trim().to_lowercase()).collect();
+        let (key, modifiers) = parts
+            .split_last()
+            .filter(|(key, _)| !key.is_empty())
+            .ok_or_else(|| format!("\"{text}\" has no key"))?;
+        let modifiers = modifiers
+            .iter()
+            .map(|m| match m.as_str() {
+                "ctrl" | "control" => Ok(Modifier::Ctrl),
+                "shift" => Ok(Modifier::Shift),
+                "alt" => Ok(Modifier::Alt),
+                "super" | "meta" | "logo" | "cmd" | "command" => Ok(Modifier::Super),
+                _ => Err(format!("\"{m}\" is not a modifier")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if key_code(key).is_none() {
+            return Err(format!("unknown key \"{key}\""));
+        }
+        Ok(Self {
+            modifiers,
+            key: key.clone(),
+        })
+    }
+}
+
+/// evdev key code for a key name, by its position on a US layout.
+fn key_code(name: &str) -> Option<u16> {
+    // Letter rows, padded so a letter's index + 16 is its key code
+    const LETTERS: &[u8] = b"qwertyuiop\0\0\0\0asdfghjkl\0\0\0\0\0zxcvbnm";
+    let code = match name {
+        "esc" | "escape" => 1,
+        "backspace" => 14,
+        "tab" => 15,
+        "enter" | "return" => 28,
+        "space" => 57,
+        "insert" => 110,
+        "delete" => 111,
+        _ => {
+            let mut chars = name.chars();
+            let (Some(c), None) = (chars.next(), chars.next()) else {
+                return None;
+            };
+            match c {
+                '1'..='9' => c as u16 - '1' as u16 + 2,
+                '0' => 11,
+                'a'..='z' => {
+                    let index = LETTERS.iter().position(|&l| l == c as u8)?;
+                    16 + index as u16
+                }
+                _ => return None,
+            }
+        }
+    };
+    Some(code)
+}
+
+/// X keysym name for a key, as `wtype -k` and `xdotool key` expect.
+fn keysym(name: &str) -> &str {
+    match name {
+        "esc" | "escape" => "Escape",
+        "backspace" => "BackSpace",
+        "tab" => "Tab",
+        "enter" | "return" => "Return",
+        "insert" => "Insert",
+        "delete" => "Delete",
+        other => other,
+    }
+}
+
+/// evdev key code and whether Shift is needed to type `c` on a US layout.
+fn us_key(c: char) -> Option<(u16, bool)> {
+    // (plain, shifted, key code)
+    const SYMBOLS: &[(char, char, u16)] = &[
+        ('1', '!', 2),
+        ('2', '@', 3),
+        ('3', '#', 4),
+        ('4', '$', 5),
+        ('5', '%', 6),
+        ('6', '^', 7),
+        ('7', '&', 8),
+        ('8', '*', 9),
+        ('9', '(', 10),
+        ('0', ')', 11),
+        ('-', '_', 12),
+        ('=', '+', 13),
+        ('[', '{', 26),
+        (']', '}', 27),
+        (';', ':', 39),
+        ('\'', '"', 40),
+        ('`', '~', 41),
+        ('\\', '|', 43),
+        (',', '<', 51),
+        ('.', '>', 52),
+        ('/', '?', 53),
+    ];
+    match c {
+        'a'..='z' => key_code(c.encode_utf8(&mut [0; 4])).map(|code| (code, false)),
+        'A'..='Z' => {
+            key_code(c.to_ascii_lowercase().encode_utf8(&mut [0; 4])).map(|code| (code, true))
+        }
+        ' ' => Some((57, false)),
+        '\t' => Some((15, false)),
+        _ => SYMBOLS.iter().find_map(|&(plain, shifted, code)| {
+            if c == plain {
+                Some((code, false))
+            } else if c == shifted {
+                Some((code, true))
+            } else {
+                None
+            }
+        }),
+    }
+}
+
+/// The first character of `text` (line breaks aside) that uinput and
+/// ydotool can't type.
+#[cfg(not(target_os = "macos"))]
+fn untypeable(text: &str) -> Option<char> {
+    text.chars().find(|&c| c != '\n' && us_key(c).is_none())
+}
+
+/// Whether `backend` presses keys by their position on a US layout rather
+/// than sending the characters themselves.
+#[cfg(not(target_os = "macos"))]
+fn is_positional(backend: KeyboardBackend) -> bool {
+    matches!(backend, KeyboardBackend::Uinput | KeyboardBackend::Ydotool)
+}
+
+/// How one backend's attempt went wrong.
+#[cfg(not(target_os = "macos"))]
+#[derive(Debug)]
+enum Attempt {
+    /// Nothing reached the window, so another backend may start over
+    Skipped(Error),
+    /// Part of the text was typed; starting over would repeat it
+    Interrupted(Error),
+}
+
+/// Run `attempt` with each backend in turn until one succeeds.
+#[cfg(not(target_os = "macos"))]
+fn first_that_works(
+    backends: &[KeyboardBackend],
+    mut attempt: impl FnMut(KeyboardBackend) -> Result<(), Attempt>,
+) -> Result<(), Error> {
+    let mut failures = Vec::new();
+    for &backend in backends {
+        match attempt(backend) {
+            Ok(()) => return Ok(()),
+            Err(Attempt::Skipped(e)) => {
+                log::warn!("{} could not send keys: {e}", tool_name(backend));
+                failures.push(format!("{}: {e}", tool_name(backend)));
+            }
+            Err(Attempt::Interrupted(e)) => return Err(e),
+        }
+    }
+    Err(failures.join("; ").into())
+}
+
+/// Press `chord` in the focused window. With `Auto`, a backend that fails
+/// (e.g. wtype on a compositor without the virtual keyboard protocol)
+/// falls through to the next.
+pub fn send_chord(backend: KeyboardBackend, chord: &Chord) -> Result<(), Error> {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = backend;
+        send_chord_macos(chord)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        first_that_works(&candidates(backend)?, |backend| {
+            press(backend, chord).map_err(Attempt::Skipped)
+        })
+    }
+}
+
+/// Type `text` into the focused window, sending `newline` for each line
+/// break. Only one text is typed at a time. With `Auto`, backends that
+/// fail before typing anything, or can't type some character, fall
+/// through to the next.
+pub fn type_text(backend: KeyboardBackend, text: &str, newline: &Chord) -> Result<(), Error> {
+    static TYPING: Mutex<()> = Mutex::new(());
+    let _guard = TYPING.lock().unwrap_or_else(|e| e.into_inner());
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+
+    #[cfg(target_os = "macos")]
+    {
+        let _ = backend;
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                send_chord_macos(newline)?;
+            }
+            if !line.is_empty() {
+                let escaped = line.replace('\\', "\\\\").replace('"', "\\\"");
+                let script =
+                    format!("tell application \"System Events\" to keystroke \"{escaped}\"");
+                run("osascript", &["-e", script.as_str()])?;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        first_that_works(&candidates(backend)?, |backend| {
+            type_with(backend, &text, newline)
+        })
+    }
+}
+
+/// Type `text` with one concrete backend.
+#[cfg(not(target_os = "macos"))]
+fn type_with(backend: KeyboardBackend, text: &str, newline: &Chord) -> Result<(), Attempt> {
+    // These send key positions, not characters; check everything up front
+    // so nothing is typed half-way
+    if is_positional(backend) {
+        if let Some(c) = untypeable(text) {
+            return Err(Attempt::Skipped(
+                format!("can't type \"{c}\" (US layout, ASCII only)").into(),
+            ));
+        }
+    }
+    let mut typed = false;
+    let fail = |typed: bool, e: Error| {
+        if typed {
+            Attempt::Interrupted(e)
+        } else {
+            Attempt::Skipped(e)
+        }
+    };
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            press(backend, newline).map_err(|e| fail(typed, e))?;
+            typed = true;
+        }
+        if !line.is_empty() {
+            type_line(backend, line).map_err(|e| fail(typed, e))?;
+            typed = true;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn press(backend: KeyboardBackend, chord: &Chord) -> Result<(), Error> {
+    match backend {
+        #[cfg(target_os = "linux")]
+        KeyboardBackend::Uinput => {
+            let mut codes: Vec<u16> = chord.modifiers.iter().map(|m| m.code()).collect();
+            codes.extend(key_code(&chord.key));
+            let mut events: Vec<(u16, i32)> = codes.iter().map(|&c| (c, 1)).collect();
+            events.extend(codes.iter().rev().map(|&c| (c, 0)));
+            uinput::send_keys(&events)
+        }
+        KeyboardBackend::Wtype => {
+            let mut args = Vec::new();
+            for m in &chord.modifiers {
+                args.extend(["-M", m.tool_name()]);
+            }
+            args.extend(["-k", keysym(&chord.key)]);
+            for m in chord.modifiers.iter().rev() {
+                args.extend(["-m", m.tool_name()]);
+            }
+            run("wtype", &args)
+        }
+        KeyboardBackend::Ydotool => {
+            let mut codes: Vec<u16> = chord.modifiers.iter().map(|m| m.code()).collect();
+            codes.extend(key_code(&chord.key));
+            let mut args = vec!["key".to_string()];
+            args.extend(codes.iter().map(|c| format!("{c}:1")));
+            args.extend(codes.iter().rev().map(|c| format!("{c}:0")));
+            run("ydotool", &args)
+        }
+        KeyboardBackend::Xdotool => {
+            let mut combo: Vec<&str> = chord.modifiers.iter().map(|m| m.tool_name()).collect();
+            combo.push(keysym(&chord.key));
+            run(
+                "xdotool",
+                &["key", "--clearmodifiers", combo.join("+").as_str()],
+            )
+        }
+        other => Err(format!("{other:?} is not available on this platform").into()),
+    }
+}
+
+/// Type one line (no line breaks) with a concrete backend.
+#[cfg(not(target_os = "macos"))]
+fn type_line(backend: KeyboardBackend, line: &str) -> Result<(), Error> {
+    match backend {
+        #[cfg(target_os = "linux")]
+        KeyboardBackend::Uinput => {
+            const SHIFT: u16 = 42;
+            let mut events = Vec::new();
+            for (code, shift) in line.chars().filter_map(us_key) {
+                if shift {
+                    events.push((SHIFT, 1));
+                }
+                events.extend([(code, 1), (code, 0)]);
+                if shift {
+                    events.push((SHIFT, 0));
+                }
+            }
+            uinput::send_keys(&events)
+        }
+        KeyboardBackend::Wtype => run("wtype", &["--", line]),
+        KeyboardBackend::Ydotool => run("ydotool", &["type", "--", line]),
+        KeyboardBackend::Xdotool => run(
+            "xdotool",
+            &["type", "--clearmodifiers", "--delay", "5", "--", line],
+        ),
+        other => Err(format!("{other:?} is not available on this platform").into()),
+    }
+}
+
+/// The concrete backends to try for `backend`, best first. For `Auto` the
+/// session's tool (wtype or xdotool) goes first: it sends keysyms, so text
+/// and shortcuts come out right in any keyboard layout. uinput and ydotool
+/// press keys by their position on a US layout, so "ctrl+v" may paste
+/// nothing on e.g. AZERTY.
+#[cfg(not(target_os = "macos"))]
+fn candidates(backend: KeyboardBackend) -> Result<Vec<KeyboardBackend>, Error> {
+    if backend != KeyboardBackend::Auto {
+        return Ok(vec![backend]);
+    }
+    let tool = session_tool();
+    let found: Vec<KeyboardBackend> = [tool, KeyboardBackend::Uinput, KeyboardBackend::Ydotool]
+        .into_iter()
+        .filter(|&b| is_available(b))
+        .collect();
+    if found.is_empty() {
+        return Err(format!(
+            "no way to send key presses: /dev/uinput is not writable and neither {} nor ydotool is installed",
+            tool_name(tool)
+        )
+        .into());
+    }
+    Ok(found)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn session_tool() -> KeyboardBackend {
+    if std::env::var("XDG_SESSION_TYPE").is_ok_and(|s| s == "wayland") {
+        KeyboardBackend::Wtype
+    } else {
+        KeyboardBackend::Xdotool
+    }
+}
+
+/// How typing and auto-paste will send keys, for the dashboard.
+pub fn describe(backend: KeyboardBackend) -> String {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = backend;
+        "System Events".into()
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let backends = match candidates(backend) {
+            Ok(backends) => backends,
+            Err(e) => return e.to_string(),
+        };
+        let names: Vec<String> = backends
+            .iter()
+            .map(|&b| {
+                if is_positional(b) {
+                    format!("{} (US layout, ASCII only)", tool_name(b))
+                } else {
+                    tool_name(b).to_string()
+                }
+            })
+            .collect();
+        let mut detail = names.join(", falling back to ");
+        if backends.iter().all(|&b| is_positional(b)) {
+            let tool = tool_name(session_tool());
+            detail.push_str(&format!(
+                " \u{2014} install {tool} to type in other layouts; text it can't type is copied instead"
+            ));
+        }
+        detail
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn is_available(backend: KeyboardBackend) -> bool {
+    match backend {
+        #[cfg(target_os = "linux")]
+        KeyboardBackend::Uinput => uinput::is_available(),
+        KeyboardBackend::Wtype | KeyboardBackend::Ydotool | KeyboardBackend::Xdotool => {
+            on_path(tool_name(backend))
+        }
+        _ => false,
+    }
+}
+
+fn tool_name(backend: KeyboardBackend) -> &'static str {
+    match backend {
+        KeyboardBackend::Auto => "auto",
+        KeyboardBackend::Uinput => "uinput",
+        KeyboardBackend::Wtype => "wtype",
+        KeyboardBackend::Ydotool => "ydotool",
+        KeyboardBackend::Xdotool => "xdotool",
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn send_chord_macos(chord: &Chord) -> Result<(), Error> {
+    let using: Vec<&str> = chord
+        .modifiers
+        .iter()
+        .map(|m| match m {
+            Modifier::Ctrl => "control down",
+            Modifier::Shift => "shift down",
+            Modifier::Alt => "option down",
+            Modifier::Super => "command down",
+        })
+        .collect();
+    // Named keys go by macOS virtual key code
+    let key = match chord.key.as_str() {
+        "enter" | "return" => "key code 36".to_string(),
+        "tab" => "key code 48".to_string(),
+        "space" => "key code 49".to_string(),
+        "backspace" => "key code 51".to_string(),
+        "esc" | "escape" => "key code 53".to_string(),
+        k if k.chars().count() == 1 => format!("keystroke \"{k}\""),
+        k => return Err(format!("\"{k}\" can't be pressed on macOS").into()),
+    };
+    let mut script = format!("tell application \"System Events\" to {key}");
+    if !using.is_empty() {
+        script.push_str(&format!(" using {{{}}}", using.join(", ")));
+    }
+    run("osascript", &["-e", script.as_str()])
+}
+
+/// Whether `cmd` is an executable on `PATH`.
+pub fn on_path(cmd: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(cmd).is_file()))
+        .unwrap_or(false)
+}
+
+fn run<S: AsRef<std::ffi::OsStr>>(cmd: &str, args: &[S]) -> Result<(), Error> {
+    let output = Command::new(cmd)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {cmd}: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{cmd} failed ({}): {}", output.status, stderr.trim()).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_chords() {
+        assert_eq!(
+            Chord::parse("ctrl+shift+v"),
+            Ok(Chord {
+                modifiers: vec![Modifier::Ctrl, Modifier::Shift],
+                key: "v".into(),
+            })
+        );
+        assert_eq!(
+            Chord::parse(" Control + INSERT "),
+            Ok(Chord {
+                modifiers: vec![Modifier::Ctrl],
+                key: "insert".into(),
+            })
+        );
+        assert_eq!(
+            Chord::parse("cmd+alt+return"),
+            Ok(Chord {
+                modifiers: vec![Modifier::Super, Modifier::Alt],
+                key: "return".into(),
+            })
+        );
+        assert_eq!(
+            Chord::parse("enter"),
+            Ok(Chord {
+                modifiers: vec![],
+                key: "enter".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Chord::parse(""), Err("\"\" has no key".into()));
+        assert_eq!(Chord::parse("ctrl+"), Err("\"ctrl+\" has no key".into()));
+        assert_eq!(
+            Chord::parse("hyper+v"),
+            Err("\"hyper\" is not a modifier".into())
+        );
+        assert_eq!(Chord::parse("ctrl+f5"), Err("unknown key \"f5\"".into()));
+        assert_eq!(Chord::parse("ctrl+é"), Err("unknown key \"é\"".into()));
+    }
+
+    #[test]
+    fn key_codes_follow_the_us_layout() {
+        let cases = [
+            ("q", 16),
+            ("p", 25),
+            ("a", 30),
+            ("l", 38),
+            ("z", 44),
+            ("m", 50),
+            ("1", 2),
+            ("9", 10),
+            ("0", 11),
+            ("esc", 1),
+            ("backspace", 14),
+            ("tab", 15),
+            ("return", 28),
+            ("space", 57),
+            ("insert", 110),
+            ("delete", 111),
+        ];
+        for (name, code) in cases {
+            assert_eq!(key_code(name), Some(code), "{name}");
+        }
+        for name in ["", "A", "f1", "é", ";", "ctrl"] {
+            assert_eq!(key_code(name), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn us_keys() {
+        let cases = [
+            ('a', Some((30, false))),
+            ('A', Some((30, true))),
+            ('m', Some((50, false))),
+            ('1', Some((2, false))),
+            ('!', Some((2, true))),
+            ('0', Some((11, false))),
+            (')', Some((11, true))),
+            ('"', Some((40, true))),
+            ('\\', Some((43, false))),
+            ('?', Some((53, true))),
+            (' ', Some((57, false))),
+            ('\t', Some((15, false))),
+            ('é', None),
+            ('€', None),
+            ('\n', None),
+        ];
+        for (c, expected) in cases {
+            assert_eq!(us_key(c), expected, "{c:?}");
+        }
+    }
+
+    #[test]
+    #[cfg(not(target_os = "macos"))]
+    fn untypeable_skips_line_breaks() {
+        assert_eq!(untypeable("Fix the bug\nin main.rs"), None);
+        assert_eq!(untypeable("Café au lait"), Some('é'));
+    }
+
+    #[test]
+    #[cfg(not(target_os = "macos"))]
+    fn falls_back_until_a_backend_works() {
+        let mut tried = Vec::new();
+        let result = first_that_works(
+            &[KeyboardBackend::Wtype, KeyboardBackend::Uinput],
+            |backend| {
+                tried.push(backend);
+                match backend {
+                    KeyboardBackend::Wtype => Err(Attempt::Skipped(
+                        "Compositor does not support the virtual keyboard protocol".into(),
+                    )),
+                    _ => Ok(()),
+                }
+            },
+        );
+        assert!(result.is_ok());
+        assert_eq!(tried, [KeyboardBackend::Wtype, KeyboardBackend::Uinput]);
+    }
+
+    #[test]
+    #[cfg(not(target_os = "macos"))]
+    fn reports_every_failure() {
+        let result = first_that_works(
+            &[KeyboardBackend::Wtype, KeyboardBackend::Ydotool],
+            |backend| Err(Attempt::Skipped(format!("{backend:?} broke").into())),
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "wtype: Wtype broke; ydotool: Ydotool broke"
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_os = "macos"))]
+    fn stops_after_typing_half_way() {
+        let mut tried = Vec::new();
+        let result = first_that_works(
+            &[KeyboardBackend::Xdotool, KeyboardBackend::Uinput],
+            |backend| {
+                tried.push(backend);
+                Err(Attempt::Interrupted("xdotool was killed".into()))
+            },
+        );
+        assert_eq!(result.unwrap_err().to_string(), "xdotool was killed");
+        assert_eq!(tried, [KeyboardBackend::Xdotool]);
+    }
+}
@@ -0,0 +1,58 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use evdev::uinput::VirtualDevice;
+use evdev::{AttributeSet, EventType, InputEvent, KeyCode};
+
+/// Time for the compositor to pick up a newly created device; key presses
+/// sent before that are lost.
+const SETTLE: Duration = Duration::from_millis(200);
+/// Gap between key events, so slow clients see every one.
+const KEY_DELAY: Duration = Duration::from_millis(8);
+
+/// Created on first use and kept, so later presses don't pay `SETTLE`.
+static DEVICE: Mutex<Option<VirtualDevice>> = Mutex::new(None);
+
+/// Whether we may create a virtual keyboard. Usually needs a udev rule
+/// giving the `input` group write access to `/dev/uinput`.
+pub fn is_available() -> bool {
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open("/dev/uinput")
+        .is_ok()
+}
+
+/// Send key events (code, 1 = press / 0 = release) in order.
+pub fn send_keys(events: &[(u16, i32)]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut guard = DEVICE.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.is_none() {
+        *guard = Some(create()?);
+        thread::sleep(SETTLE);
+    }
+    let device = guard.as_mut().expect("device was just created");
+    for &(code, value) in events {
+        device.emit(&[key(code, value)])?;
+        thread::sleep(KEY_DELAY);
+    }
+    Ok(())
+}
+
+fn create() -> Result<VirtualDevice, Box<dyn std::error::Error + Send + Sync>> {
+    // Every key, so the device looks like an ordinary keyboard
+    let mut keys = AttributeSet::<KeyCode>::new();
+    for code in 1..=248 {
+        keys.insert(KeyCode(code));
+    }
+    let device = VirtualDevice::builder()
+        .map_err(|e| format!("Failed to open /dev/uinput: {e}"))?
+        .name("Voice Prompt virtual keyboard")
+        .with_keys(&keys)?
+        .build()?;
+    log::info!("Created uinput virtual keyboard");
+    Ok(device)
+}
+
+fn key(code: u16, value: i32) -> InputEvent {
+    InputEvent::new(EventType::KEY.0, code, value)
+}
@@ -1,68 +1,204 @@
 mod app;
 mod audio_feedback;
+mod cli;
 mod clipboard;
+mod commands;
 mod config;
+mod control;
+mod dbus;
+#[cfg(feature = "ui")]
+mod diff;
 mod hotkey;
+mod keyboard;
+mod output;
 mod recorder;
+mod redact;
 mod refiner;
+mod secrets;
 mod stats;
+#[cfg(test)]
+mod temp_dir;
+#[cfg(test)]
+mod test_server;
 mod transcriber;
+#[cfg(feature = "ui")]
 mod ui;
 
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use gio::prelude::*;
+#[cfg(feature = "ui")]
 use gtk4::prelude::*;
+#[cfg(feature = "ui")]
 use libadwaita::prelude::*;
 
 use app::{AppState, BackendEvent};
 
+/// libadwaita's application with the UI, plain GIO's in headless builds.
+#[cfg(feature = "ui")]
+type Application = libadwaita::Application;
+#[cfg(not(feature = "ui"))]
+type Application = gio::Application;
+
 fn main() {
-    env_logger::init();
-    log::info!("Voice Prompt starting");
+    // Without windows the log is where status shows up
+    let default_level = if cfg!(feature = "ui") {
+        "error"
+    } else {
+        "info"
+    };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_level))
+        .init();
+
+    // `voice-prompt ctl <command>` talks to the running instance
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).is_some_and(|arg| arg == "ctl") {
+        std::process::exit(control::run_client(&args[2..]));
+    }
+    let invocation = match cli::parse(&args[1..]) {
+        Ok(invocation) => invocation,
+        Err(e) => {
+            eprintln!("voice-prompt: {e}\n{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
 
-    let application = libadwaita::Application::builder()
+    let application = Application::builder()
         .application_id("com.github.tr4m0ryp.voice-prompt")
-        .flags(gtk4::gio::ApplicationFlags::default())
+        .flags(gio::ApplicationFlags::HANDLES_COMMAND_LINE)
         .build();
 
+    // Commands are run by the primary instance; don't become it just for one
+    if let cli::Invocation::Forward { .. } = invocation {
+        if let Err(e) = application.register(None::<&gio::Cancellable>) {
+            eprintln!("voice-prompt: {e}");
+            std::process::exit(1);
+        }
+        if !application.is_remote() {
+            eprintln!("voice-prompt: Voice Prompt is not running");
+            std::process::exit(1);
+        }
+    } else {
+        log::info!("Voice Prompt starting");
+    }
+
+    // Backend → UI channel, shared by activation and forwarded commands
+    let (backend_tx, backend_rx) = async_channel::unbounded::<BackendEvent>();
+
+    #[cfg(feature = "ui")]
     application.connect_startup(on_startup);
-    application.connect_activate(on_activate);
+    {
+        let (backend_tx, backend_rx) = (backend_tx.clone(), backend_rx.clone());
+        application.connect_activate(move |app| on_activate(app, &backend_tx, &backend_rx, true));
+    }
+    application.connect_command_line(move |app, cmdline| {
+        on_command_line(app, cmdline, &backend_tx, &backend_rx)
+    });
     application.run();
 }
 
-fn on_startup(app: &libadwaita::Application) {
+/// Runs in the primary instance for its own and every forwarded command line.
+fn on_command_line(
+    app: &Application,
+    cmdline: &gio::ApplicationCommandLine,
+    backend_tx: &async_channel::Sender<BackendEvent>,
+    backend_rx: &async_channel::Receiver<BackendEvent>,
+) -> glib::ExitCode {
+    let args: Vec<String> = cmdline
+        .arguments()
+        .iter()
+        .skip(1)
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect();
+    let (command, json) = match cli::parse(&args) {
+        Ok(cli::Invocation::Launch { hidden }) => {
+            on_activate(app, backend_tx, backend_rx, !hidden);
+            return glib::ExitCode::SUCCESS;
+        }
+        Ok(cli::Invocation::Forward { command, json }) => (command, json),
+        Err(e) => {
+            cmdline.printerr_literal(&format!("voice-prompt: {e}\n{}\n", cli::USAGE));
+            return glib::ExitCode::FAILURE;
+        }
+    };
+
+    // The caller waits until `cmdline` is dropped, and exits with its status
+    let (reply, answer) = async_channel::bounded(1);
+    if backend_tx
+        .try_send(BackendEvent::Control(command, reply))
+        .is_err()
+    {
+        return glib::ExitCode::FAILURE;
+    }
+    let cmdline = cmdline.clone();
+    glib::spawn_future_local(async move {
+        let Ok(answer) = answer.recv().await else {
+            cmdline.set_exit_status(1);
+            return;
+        };
+        let (out, err, status) = cli::render(command, &answer, json);
+        cmdline.print_literal(&out);
+        cmdline.printerr_literal(&err);
+        cmdline.set_exit_status(status);
+    });
+    glib::ExitCode::SUCCESS
+}
+
+#[cfg(feature = "ui")]
+fn on_startup(app: &Application) {
     // Set up application actions (only once)
     setup_actions(app);
 }
 
-fn on_activate(app: &libadwaita::Application) {
+/// Build the app on first activation and show the dashboard if `present`.
+#[cfg(feature = "ui")]
+fn on_activate(
+    app: &Application,
+    backend_tx: &async_channel::Sender<BackendEvent>,
+    backend_rx: &async_channel::Receiver<BackendEvent>,
+    present: bool,
+) {
     // If there's already a window, show and present it
     if let Some(window) = app.active_window() {
-        window.set_visible(true);
-        window.present();
+        if present {
+            window.set_visible(true);
+            window.present();
+        }
         return;
     }
 
     // First activation - create everything
-    // Create async channels for backend → UI communication
-    let (backend_tx, backend_rx) = async_channel::unbounded::<BackendEvent>();
-    let (hotkey_tx, hotkey_rx) = async_channel::unbounded::<()>();
-
-    // Build app state
     let overlay_tx = backend_tx.clone();
-    let state = Rc::new(RefCell::new(AppState::new(backend_tx)));
+    let state = Rc::new(RefCell::new(AppState::new(backend_tx.clone())));
 
     // Build UI
+    let clipboard_detail = clipboard::describe();
+    log::info!("Clipboard: {clipboard_detail}");
+    let keyboard_detail = keyboard::describe(state.borrow().config.paste.backend);
+    log::info!("Keyboard: {keyboard_detail}");
+    let (profile_names, active_index) = profile_choices(&state.borrow().config);
     let dashboard = ui::dashboard::build_dashboard(
         app,
         "Starting...",
         state.borrow().stats.total_words,
         state.borrow().stats.total_prompts,
         &state.borrow().config.hotkey.display_name,
+        &append_hotkey_display(&state.borrow().config),
+        &clipboard_detail,
+        &keyboard_detail,
+        state.borrow().config.output.primary_selection,
+        &profile_names,
+        active_index,
+        state.borrow().config.cleanup.pre_pass,
+        state.borrow().config.commands.enabled,
         &state.borrow().config.gemini_api_key,
     );
-    let overlay = ui::overlay::build_overlay(app, overlay_tx);
+    let overlay = ui::overlay::build_overlay(
+        app,
+        overlay_tx,
+        &state.borrow().config.active_profile().name,
+    );
 
     // Wire up the "Change Hotkey" button
     {
@@ -74,12 +210,12 @@ fn on_activate(app: &libadwaita::Application) {
                 if let Some(new_hotkey) = result {
                     log::info!("New hotkey: {}", new_hotkey.display_name);
                     let mut s = state_inner.borrow_mut();
-                    *s.shared_hotkey.lock().unwrap() = new_hotkey.clone();
                     s.config.hotkey = new_hotkey.clone();
+                    s.sync_hotkey_bindings();
                     if let Err(e) = s.config.save() {
                         log::warn!("Failed to save config: {e}");
                     }
-                    if let Some(ref dash) = s.dashboard {
+                    if let Some(ref dash) = s.view.dashboard {
                         dash.hotkey_label.set_text(&new_hotkey.display_name);
                     }
                 }
@@ -87,50 +223,239 @@ fn on_activate(app: &libadwaita::Application) {
         });
     }
 
-    // Wire up API key changes
+    // Wire up the append hotkey's "Change" button
+    {
+        let state_clone = state.clone();
+        let dash_window = dashboard.window.clone();
+        dashboard.change_append_hotkey_button.connect_clicked(move |_| {
+            let state_inner = state_clone.clone();
+            ui::hotkey_dialog::show_hotkey_dialog(&dash_window, move |result| {
+                if let Some(new_hotkey) = result {
+                    log::info!("New append hotkey: {}", new_hotkey.display_name);
+                    let mut s = state_inner.borrow_mut();
+                    s.config.append.hotkey = Some(new_hotkey.clone());
+                    s.sync_hotkey_bindings();
+                    if let Err(e) = s.config.save() {
+                        log::warn!("Failed to save config: {e}");
+                    }
+                    if let Some(ref dash) = s.view.dashboard {
+                        dash.append_hotkey_label.set_text(&new_hotkey.display_name);
+                    }
+                }
+            });
+        });
+    }
+
+    // Wire up active profile selection
+    {
+        let sender = state.borrow().backend_sender.clone();
+        dashboard.profile_row.connect_selected_notify(move |row| {
+            let name = row
+                .selected_item()
+                .and_downcast::<gtk4::StringObject>()
+                .map(|obj| obj.string().to_string());
+            if let Some(name) = name {
+                let _ = sender.try_send(BackendEvent::SelectProfile(name));
+            }
+        });
+    }
+
+    // Wire up the profile editor
+    {
+        let state_clone = state.clone();
+        let dash_window = dashboard.window.clone();
+        dashboard.edit_profiles_button.connect_clicked(move |_| {
+            let profiles = state_clone.borrow().config.profiles.clone();
+            let state_inner = state_clone.clone();
+            ui::profiles::show_profiles_window(&dash_window, &profiles, move |edited| {
+                let active = {
+                    let mut s = state_inner.borrow_mut();
+                    s.config.profiles = edited;
+                    s.sync_hotkey_bindings();
+                    if let Err(e) = s.config.save() {
+                        log::warn!("Failed to save config: {e}");
+                    }
+                    if let Some(ref dash) = s.view.dashboard {
+                        let (names, index) = profile_choices(&s.config);
+                        ui::dashboard::set_profile_names(&dash.profile_row, &names, index);
+                    }
+                    s.config.active_profile().name
+                };
+                app::select_profile(&state_inner, &active);
+            });
+        });
+    }
+
+    // Wire up the primary selection toggle
+    {
+        let state_clone = state.clone();
+        dashboard.primary_row.connect_active_notify(move |row| {
+            let mut s = state_clone.borrow_mut();
+            s.config.output.primary_selection = row.is_active();
+            if let Err(e) = s.config.save() {
+                log::warn!("Failed to save config: {e}");
+            }
+        });
+    }
+
+    // Wire up the rule-based pre-pass toggle
+    {
+        let state_clone = state.clone();
+        dashboard.cleanup_row.connect_active_notify(move |row| {
+            let mut s = state_clone.borrow_mut();
+            s.config.cleanup.pre_pass = row.is_active();
+            if let Err(e) = s.config.save() {
+                log::warn!("Failed to save config: {e}");
+            }
+        });
+    }
+
+    // Wire up the spoken commands toggle
+    {
+        let state_clone = state.clone();
+        dashboard.commands_row.connect_active_notify(move |row| {
+            let mut s = state_clone.borrow_mut();
+            s.config.commands.enabled = row.is_active();
+            if let Err(e) = s.config.save() {
+                log::warn!("Failed to save config: {e}");
+            }
+        });
+    }
+
+    // Wire up API key changes (stored when the row's apply button is pressed)
     {
         let state_clone = state.clone();
         dashboard
             .api_key_row
-            .connect_changed(move |row: &libadwaita::PasswordEntryRow| {
-                let key = row.text().to_string();
-                let mut s = state_clone.borrow_mut();
-                s.config.gemini_api_key = key;
-                if let Err(e) = s.config.save() {
-                    log::warn!("Failed to save config: {e}");
-                }
+            .connect_apply(move |row: &libadwaita::PasswordEntryRow| {
+                let key = row.text().trim().to_string();
+                app::dispatch_secret_store(&state_clone, secrets::GEMINI_API_KEY, key);
             });
     }
 
+    // Wire up the connection test (uses the key as typed, even before applying)
+    {
+        let state_clone = state.clone();
+        let key_row = dashboard.api_key_row.clone();
+        dashboard.test_connection_button.connect_clicked(move |_| {
+            if let Some(ref dash) = state_clone.borrow().view.dashboard {
+                ui::dashboard::set_connection_testing(dash);
+            }
+            app::dispatch_connection_test(&state_clone, key_row.text().trim().to_string());
+        });
+    }
+
     // Wire up prompts row to open history
     {
         let state_clone = state.clone();
         let dash_window = dashboard.window.clone();
         dashboard.prompts_row.connect_activated(move |_| {
             let history = state_clone.borrow().stats.history.clone();
-            ui::history::show_history_window(&dash_window, &history);
+            let (profile_names, active_profile) = profile_choices(&state_clone.borrow().config);
+
+            let state_for_refine = state_clone.clone();
+            let on_refine: ui::refine_again::RefineRequest =
+                Rc::new(
+                    move |transcript: &str,
+                          profile: &str,
+                          provider: Option<config::RefinementProvider>,
+                          model: &str,
+                          on_done: ui::refine_again::RefineDone| {
+                        app::refine_again(
+                            &state_for_refine,
+                            transcript,
+                            profile,
+                            provider,
+                            model,
+                            on_done,
+                        );
+                    },
+                );
+
+            let state_for_save = state_clone.clone();
+            let on_save: ui::refine_again::SaveRequest =
+                Rc::new(move |text: &str, details: stats::PromptDetails| {
+                    let mut s = state_for_save.borrow_mut();
+                    s.stats.record_prompt(text, details);
+                    if let Err(e) = s.stats.save() {
+                        log::warn!("Failed to save stats: {e}");
+                    }
+                    s.view.show_stats(&s.stats);
+                });
+
+            let actions = ui::history::RefineActions {
+                profile_names,
+                active_profile,
+                on_refine,
+                on_save,
+            };
+            ui::history::show_history_window(&dash_window, &history, Rc::new(actions));
         });
     }
 
     // Store UI handles in state
     {
         let mut s = state.borrow_mut();
-        s.dashboard = Some(dashboard);
-        s.overlay = Some(overlay);
+        s.view.dashboard = Some(dashboard);
+        s.view.overlay = Some(overlay);
     }
 
     // Show the dashboard and make sure it's visible
-    let window = state.borrow().dashboard.as_ref().unwrap().window.clone();
+    let window = state.borrow().view.dashboard.as_ref().unwrap().window.clone();
 
     // Handle close request - hide instead of destroy so app stays running
     window.connect_close_request(move |win| {
         win.set_visible(false);
-        gtk4::glib::Propagation::Stop // Prevent actual close
+        glib::Propagation::Stop // Prevent actual close
     });
 
-    window.present();
+    if present {
+        window.present();
+    }
+
+    start_backend(app, &state, backend_rx);
+}
+
+/// Start the pipeline on first activation. There is no window to present;
+/// the application runs until it is killed.
+#[cfg(not(feature = "ui"))]
+fn on_activate(
+    app: &Application,
+    backend_tx: &async_channel::Sender<BackendEvent>,
+    backend_rx: &async_channel::Receiver<BackendEvent>,
+    _present: bool,
+) {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    static STARTED: AtomicBool = AtomicBool::new(false);
+    if STARTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    log::info!("Running headless");
+    log::info!("Clipboard: {}", clipboard::describe());
+
+    let state = Rc::new(RefCell::new(AppState::new(backend_tx.clone())));
+    log::info!(
+        "Keyboard: {}",
+        keyboard::describe(state.borrow().config.paste.backend)
+    );
+
+    // Nothing else holds the application open without windows
+    std::mem::forget(app.hold());
 
+    state.borrow_mut().view.app = Some(app.clone());
+    start_backend(app, &state, backend_rx);
+}
+
+/// Start the hotkey listener, the event loop, the control socket and D-Bus
+/// interface, and the transcriber.
+fn start_backend(
+    app: &Application,
+    state: &Rc<RefCell<AppState>>,
+    backend_rx: &async_channel::Receiver<BackendEvent>,
+) {
     // Start hotkey listener
+    let (hotkey_tx, hotkey_rx) = async_channel::unbounded::<hotkey::HotkeyAction>();
     {
         let shared_hotkey = state.borrow().shared_hotkey.clone();
         hotkey::start_listener(hotkey_tx, shared_hotkey);
@@ -139,9 +464,17 @@ fn on_activate(app: &libadwaita::Application) {
     // Forward hotkey triggers to backend event channel
     {
         let sender = state.borrow().backend_sender.clone();
-        gtk4::glib::spawn_future_local(async move {
-            while hotkey_rx.recv().await.is_ok() {
-                let _ = sender.send(BackendEvent::HotkeyTriggered).await;
+        glib::spawn_future_local(async move {
+            while let Ok(action) = hotkey_rx.recv().await {
+                let event = match action {
+                    hotkey::HotkeyAction::Toggle => BackendEvent::HotkeyTriggered,
+                    hotkey::HotkeyAction::Profile(name) => {
+                        let _ = sender.send(BackendEvent::SelectProfile(name)).await;
+                        BackendEvent::HotkeyTriggered
+                    }
+                    hotkey::HotkeyAction::Append => BackendEvent::AppendTriggered,
+                };
+                let _ = sender.send(event).await;
             }
         });
     }
@@ -149,20 +482,51 @@ fn on_activate(app: &libadwaita::Application) {
     // Attach backend event handler
     {
         let state_clone = state.clone();
-        gtk4::glib::spawn_future_local(async move {
+        let backend_rx = backend_rx.clone();
+        glib::spawn_future_local(async move {
             while let Ok(event) = backend_rx.recv().await {
                 app::handle_backend_event(&state_clone, event);
             }
         });
     }
 
-    // Start whisper model download/load
-    app::ensure_whisper_model(&state);
+    // Listen for editor plugins and scripts
+    match control::start_server(state.borrow().backend_sender.clone()) {
+        Ok(events) => state.borrow_mut().control_events = Some(events),
+        Err(e) => log::warn!("Control socket unavailable: {e}"),
+    }
+    match dbus::register(app, state.borrow().backend_sender.clone()) {
+        Ok(service) => state.borrow_mut().dbus = Some(service),
+        Err(e) => log::warn!("D-Bus interface unavailable: {e}"),
+    }
+
+    // Start transcription backend (whisper model download/load or remote)
+    app::init_transcriber(state);
+}
+
+/// Profile names for the dashboard combo row and the index of the active one.
+#[cfg(feature = "ui")]
+fn profile_choices(config: &config::Config) -> (Vec<String>, u32) {
+    let names: Vec<String> = config.profiles.iter().map(|p| p.name.clone()).collect();
+    let active = config.active_profile().name;
+    let index = names.iter().position(|n| *n == active).unwrap_or(0);
+    (names, index as u32)
+}
+
+#[cfg(feature = "ui")]
+fn append_hotkey_display(config: &config::Config) -> String {
+    config
+        .append
+        .hotkey
+        .as_ref()
+        .map(|hk| hk.display_name.clone())
+        .unwrap_or_else(|| "None".into())
 }
 
-fn setup_actions(app: &libadwaita::Application) {
+#[cfg(feature = "ui")]
+fn setup_actions(app: &Application) {
     // Quit action
-    let quit_action = gtk4::gio::SimpleAction::new("quit", None);
+    let quit_action = gio::SimpleAction::new("quit", None);
     let app_clone = app.clone();
     quit_action.connect_activate(move |_, _| {
         app_clone.quit();
@@ -171,7 +535,7 @@ fn setup_actions(app: &libadwaita::Application) {
     app.set_accels_for_action("app.quit", &["<Control>q"]);
 
     // About action
-    let about_action = gtk4::gio::SimpleAction::new("about", None);
+    let about_action = gio::SimpleAction::new("about", None);
     let app_clone = app.clone();
     about_action.connect_activate(move |_, _| {
         show_about_dialog(&app_clone);
@@ -179,7 +543,7 @@ fn setup_actions(app: &libadwaita::Application) {
     app.add_action(&about_action);
 
     // Show window action
-    let show_action = gtk4::gio::SimpleAction::new("show-window", None);
+    let show_action = gio::SimpleAction::new("show-window", None);
     let app_clone = app.clone();
     show_action.connect_activate(move |_, _| {
         if let Some(window) = app_clone.active_window() {
@@ -191,7 +555,7 @@ fn setup_actions(app: &libadwaita::Application) {
     app.set_accels_for_action("app.show-window", &["<Control><Shift>v"]);
 
     // Hide window action
-    let hide_action = gtk4::gio::SimpleAction::new("hide-window", None);
+    let hide_action = gio::SimpleAction::new("hide-window", None);
     let app_clone = app.clone();
     hide_action.connect_activate(move |_, _| {
         if let Some(window) = app_clone.active_window() {
@@ -202,7 +566,8 @@ fn setup_actions(app: &libadwaita::Application) {
     app.set_accels_for_action("app.hide-window", &["<Control>h"]);
 }
 
-fn show_about_dialog(app: &libadwaita::Application) {
+#[cfg(feature = "ui")]
+fn show_about_dialog(app: &Application) {
     let window = app.active_window();
     let about = libadwaita::AboutDialog::builder()
         .application_name("Voice Prompt")
@@ -0,0 +1,373 @@
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::config::OutputSink;
+use crate::stats::PromptDetails;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// A command sink still running after this long is killed.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Short name for a sink in logs and error messages.
+pub fn describe(sink: &OutputSink) -> String {
+    match sink {
+        OutputSink::Clipboard => "clipboard".into(),
+        OutputSink::Type => "typing".into(),
+        OutputSink::File(path) => format!("file {path}"),
+        OutputSink::Fifo(path) => format!("FIFO {path}"),
+        OutputSink::Stdout => "stdout".into(),
+        OutputSink::Command(command) => format!("command `{command}`"),
+    }
+}
+
+/// Hand `text` to one of the sinks that don't need the main thread. Blocks
+/// until it is done, so run it off the main loop.
+pub fn deliver(sink: &OutputSink, text: &str, details: &PromptDetails) -> Result<(), Error> {
+    match sink {
+        OutputSink::File(path) => append_to_file(&expand_home(path), text),
+        OutputSink::Fifo(path) => write_to_fifo(&expand_home(path), text),
+        OutputSink::Stdout => print_to_stdout(text),
+        OutputSink::Command(command) => run_command(command, text, details),
+        OutputSink::Clipboard | OutputSink::Type => {
+            Err(format!("{} is handled on the main thread", describe(sink)).into())
+        }
+    }
+}
+
+/// Append the prompt followed by a blank line, creating the file if needed.
+fn append_to_file(path: &Path, text: &str) -> Result<(), Error> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    // One write, so prompts from overlapping runs don't interleave
+    file.write_all(format!("{}\n\n", text.trim_end()).as_bytes())?;
+    Ok(())
+}
+
+/// Write the prompt and a newline to a named pipe. Fails rather than waits
+/// if nothing has the pipe open for reading.
+fn write_to_fifo(path: &Path, text: &str) -> Result<(), Error> {
+    use std::os::fd::AsRawFd;
+    use std::os::unix::fs::{FileTypeExt, OpenOptionsExt};
+
+    let meta =
+        std::fs::metadata(path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    if !meta.file_type().is_fifo() {
+        return Err(format!("{} is not a FIFO (create it with mkfifo)", path.display()).into());
+    }
+    // Non-blocking, the open fails with ENXIO instead of waiting for a reader
+    let mut fifo = OpenOptions::new()
+        .write(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(path)
+        .map_err(|e| match e.raw_os_error() {
+            Some(libc::ENXIO) => format!("nothing is reading {}", path.display()),
+            _ => format!("Failed to open {}: {e}", path.display()),
+        })?;
+    // Once open, a prompt bigger than the pipe buffer has to wait for a slow
+    // reader rather than fail with EAGAIN
+    let fd = fifo.as_raw_fd();
+    // SAFETY: `fd` is open for as long as `fifo` lives; F_GETFL and F_SETFL
+    // only read and change its status flags
+    let cleared = unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFL);
+        flags >= 0 && libc::fcntl(fd, libc::F_SETFL, flags & !libc::O_NONBLOCK) == 0
+    };
+    if !cleared {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    fifo.write_all(format!("{}\n", text.trim_end()).as_bytes())?;
+    Ok(())
+}
+
+fn print_to_stdout(text: &str) -> Result<(), Error> {
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{}", text.trim_end())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Run `command` through `sh -c` with the prompt on stdin and what is known
+/// about it in `VOICE_PROMPT_*` variables.
+fn run_command(command: &str, text: &str, details: &PromptDetails) -> Result<(), Error> {
+    run_command_within(command, text, details, COMMAND_TIMEOUT)
+}
+
+/// `run_command`, killing the command and everything it started if it is
+/// still running after `timeout`.
+fn run_command_within(
+    command: &str,
+    text: &str,
+    details: &PromptDetails,
+    timeout: Duration,
+) -> Result<(), Error> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("VOICE_PROMPT_PROFILE", &details.profile)
+        .env(
+            "VOICE_PROMPT_REFINED",
+            if details.refined { "1" } else { "0" },
+        )
+        .env("VOICE_PROMPT_PROVIDER", &details.provider)
+        .env("VOICE_PROMPT_MODEL", &details.model)
+        .env("VOICE_PROMPT_LANGUAGE", &details.language)
+        .env("VOICE_PROMPT_RAW", &details.raw)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        // Its own process group, so a timeout also kills what it started
+        .process_group(0)
+        .spawn()
+        .map_err(|e| format!("Failed to run sh: {e}"))?;
+
+    // Both pipes are served from their own threads: a command that writes
+    // a lot to stderr before reading its input would otherwise block on us
+    // while we block on it. Neither is joined, as something the command
+    // left running in the background may hold them open.
+    if let Some(mut stdin) = child.stdin.take() {
+        let text = text.to_string();
+        std::thread::spawn(move || {
+            // A command that ignores its input closes the pipe early; that's fine
+            if let Err(e) = stdin.write_all(text.as_bytes()) {
+                if e.kind() != std::io::ErrorKind::BrokenPipe {
+                    log::warn!("Failed to write the prompt to the command: {e}");
+                }
+            }
+        });
+    }
+    let (stderr_sender, stderr_receiver) = mpsc::channel();
+    if let Some(mut stderr) = child.stderr.take() {
+        std::thread::spawn(move || {
+            let mut output = Vec::new();
+            let _ = stderr.read_to_end(&mut output);
+            let _ = stderr_sender.send(output);
+        });
+    }
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            // SAFETY: kill has no preconditions; the group is the child's own
+            unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+            let _ = child.wait();
+            return Err(format!("still running after {timeout:?}, killed").into());
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    if !status.success() {
+        let stderr = stderr_receiver
+            .recv_timeout(Duration::from_millis(100))
+            .unwrap_or_default();
+        let stderr = String::from_utf8_lossy(&stderr);
+        return Err(format!("{status}: {}", stderr.trim()).into());
+    }
+    Ok(())
+}
+
+/// Expand a leading `~/` to the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::temp_dir;
+    use std::io::Read;
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::time::Duration;
+
+    /// Open `path` for reading without waiting for a writer.
+    fn open_reader(path: &Path) -> std::fs::File {
+        OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)
+            .unwrap()
+    }
+
+    fn mkfifo(path: &Path) {
+        let status = Command::new("mkfifo").arg(path).status().unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn file_is_created_then_appended_to() {
+        let dir = temp_dir("file");
+        let path = dir.join("prompts.md");
+        append_to_file(&path, "first prompt\n").unwrap();
+        append_to_file(&path, "second prompt").unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(written, "first prompt\n\nsecond prompt\n\n");
+    }
+
+    #[test]
+    fn fifo_without_reader_fails() {
+        let dir = temp_dir("fifo-no-reader");
+        let path = dir.join("prompts");
+        mkfifo(&path);
+        let err = write_to_fifo(&path, "hello").unwrap_err().to_string();
+        assert_eq!(err, format!("nothing is reading {}", path.display()));
+    }
+
+    #[test]
+    fn fifo_reaches_reader() {
+        let dir = temp_dir("fifo-reader");
+        let path = dir.join("prompts");
+        mkfifo(&path);
+        let mut reader = open_reader(&path);
+        write_to_fifo(&path, "hello\n").unwrap();
+        let mut received = String::new();
+        reader.read_to_string(&mut received).unwrap();
+        assert_eq!(received, "hello\n");
+    }
+
+    #[test]
+    fn fifo_waits_for_a_slow_reader() {
+        let dir = temp_dir("fifo-slow-reader");
+        let path = dir.join("prompts");
+        mkfifo(&path);
+        // Several times the 64 KiB pipe buffer
+        let prompt = "word ".repeat(60_000);
+        let expected = prompt.trim_end().len() + 1;
+        let mut reader = open_reader(&path);
+        let reading = std::thread::spawn(move || {
+            let mut received = Vec::new();
+            let mut buf = [0; 4096];
+            while received.len() < expected {
+                match reader.read(&mut buf) {
+                    Ok(n) if n > 0 => received.extend_from_slice(&buf[..n]),
+                    // Nothing written yet, or the writer has not opened it
+                    _ => std::thread::sleep(Duration::from_millis(5)),
+                }
+            }
+            received.len()
+        });
+        write_to_fifo(&path, &prompt).unwrap();
+        assert_eq!(reading.join().unwrap(), expected);
+    }
+
+    #[test]
+    fn fifo_must_be_a_fifo() {
+        let dir = temp_dir("fifo-file");
+        let path = dir.join("prompts");
+        std::fs::write(&path, "").unwrap();
+        let err = write_to_fifo(&path, "hello").unwrap_err().to_string();
+        assert!(err.contains("is not a FIFO"), "{err}");
+        assert!(write_to_fifo(&path.with_extension("missing"), "hello").is_err());
+    }
+
+    #[test]
+    fn command_gets_prompt_and_details() {
+        let dir = temp_dir("command");
+        let out = dir.join("out");
+        let command = format!(
+            "cat > '{0}'; echo >> '{0}'; env | grep ^VOICE_PROMPT_ | sort >> '{0}'",
+            out.display()
+        );
+        let details = PromptDetails {
+            raw: "um fix the bug".into(),
+            refined: true,
+            profile: "Commit message".into(),
+            provider: "ollama".into(),
+            model: "llama3.2".into(),
+            language: "en".into(),
+            ..PromptDetails::default()
+        };
+        run_command(&command, "Fix the bug", &details).unwrap();
+        let written = std::fs::read_to_string(&out).unwrap();
+        assert_eq!(
+            written,
+            "Fix the bug\n\
+             VOICE_PROMPT_LANGUAGE=en\n\
+             VOICE_PROMPT_MODEL=llama3.2\n\
+             VOICE_PROMPT_PROFILE=Commit message\n\
+             VOICE_PROMPT_PROVIDER=ollama\n\
+             VOICE_PROMPT_RAW=um fix the bug\n\
+             VOICE_PROMPT_REFINED=1\n"
+        );
+    }
+
+    #[test]
+    fn command_failure_reports_stderr() {
+        let details = PromptDetails::default();
+        let err = run_command("cat > /dev/null; echo broken >&2; exit 3", "text", &details)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains('3') && err.ends_with(": broken"), "{err}");
+    }
+
+    #[test]
+    fn command_is_killed_after_the_timeout() {
+        let dir = temp_dir("command-timeout");
+        let out = dir.join("out");
+        let command = format!(
+            "echo started > '{0}'; sleep 5; echo finished >> '{0}'",
+            out.display()
+        );
+        let started = Instant::now();
+        let err = run_command_within(
+            &command,
+            "text",
+            &PromptDetails::default(),
+            Duration::from_millis(300),
+        )
+        .unwrap_err()
+        .to_string();
+        assert_eq!(err, "still running after 300ms, killed");
+        assert!(started.elapsed() < Duration::from_secs(3));
+        // The sleep went down with the shell
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "started\n");
+    }
+
+    #[test]
+    fn command_may_write_stderr_before_reading() {
+        let dir = temp_dir("command-stderr");
+        let out = dir.join("out");
+        // More than a pipe holds, each way
+        let command = format!("head -c 300000 /dev/zero >&2; cat > '{}'", out.display());
+        let text = "x".repeat(300_000);
+        run_command_within(
+            &command,
+            &text,
+            &PromptDetails::default(),
+            Duration::from_secs(10),
+        )
+        .unwrap();
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), text);
+    }
+
+    #[test]
+    fn home_is_expanded_only_at_the_start() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(
+            expand_home("~/notes/prompts.md"),
+            home.join("notes/prompts.md")
+        );
+        assert_eq!(
+            expand_home("/tmp/prompts.md"),
+            PathBuf::from("/tmp/prompts.md")
+        );
+        assert_eq!(
+            expand_home("~other/prompts.md"),
+            PathBuf::from("~other/prompts.md")
+        );
+        assert_eq!(expand_home("notes/~/x"), PathBuf::from("notes/~/x"));
+    }
+}
@@ -62,7 +62,6 @@ pub fn start_capture(
 }
 
 /// Convert f32 samples to WAV bytes (mono 16-bit PCM).
-#[allow(dead_code)]
 pub fn samples_to_wav(
     samples: &[f32],
     sample_rate: u32,
@@ -0,0 +1,106 @@
+use std::fmt;
+
+/// Why a refinement request failed, classified so the UI can tell the user
+/// what to fix (a bad key vs. an exhausted quota vs. a flaky network).
+#[derive(Debug, Clone, PartialEq)]
+pub enum RefineError {
+    /// 401/403: the API key is missing, wrong or lacks permission.
+    Auth(String),
+    /// 429: rate limit or quota exhausted (after retries).
+    Quota(String),
+    /// 5xx from the provider (after retries).
+    Server(u16, String),
+    /// Other 4xx: the provider rejected the request (unknown model, bad payload).
+    Request(u16, String),
+    /// Could not connect, or the connection dropped.
+    Network(String),
+    /// No response within the configured timeout.
+    Timeout,
+    /// The provider answered with something we could not use.
+    InvalidResponse(String),
+}
+
+impl RefineError {
+    /// Classify an HTTP error status and its response body.
+    pub fn from_status(provider: &str, status: u16, body: &str) -> Self {
+        let msg = format!(
+            "{provider}: {}",
+            crate::redact::scrub(&summarize_body(body))
+        );
+        match status {
+            401 | 403 => Self::Auth(msg),
+            429 => Self::Quota(msg),
+            500..=599 => Self::Server(status, msg),
+            _ => Self::Request(status, msg),
+        }
+    }
+
+    /// Short label for status lines ("auth", "quota", ...).
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Auth(_) => "auth",
+            Self::Quota(_) => "quota",
+            Self::Server(..) => "server",
+            Self::Request(..) => "request",
+            Self::Network(_) => "network",
+            Self::Timeout => "timeout",
+            Self::InvalidResponse(_) => "invalid response",
+        }
+    }
+}
+
+impl fmt::Display for RefineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Auth(msg) => write!(f, "Authentication failed ({msg})"),
+            Self::Quota(msg) => write!(f, "Rate limit or quota exceeded ({msg})"),
+            Self::Server(status, msg) => write!(f, "Provider error {status} ({msg})"),
+            Self::Request(status, msg) => write!(f, "Request rejected {status} ({msg})"),
+            Self::Network(msg) => write!(f, "Network error: {msg}"),
+            Self::Timeout => write!(f, "Request timed out"),
+            Self::InvalidResponse(msg) => write!(f, "Invalid response: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for RefineError {}
+
+impl From<reqwest::Error> for RefineError {
+    fn from(e: reqwest::Error) -> Self {
+        let (timeout, decode) = (e.is_timeout(), e.is_decode());
+        // reqwest echoes the request URL, which may carry credentials
+        let msg = crate::redact::scrub(&e.without_url().to_string());
+        if timeout {
+            Self::Timeout
+        } else if decode {
+            Self::InvalidResponse(msg)
+        } else {
+            Self::Network(msg)
+        }
+    }
+}
+
+impl From<serde_json::Error> for RefineError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::InvalidResponse(e.to_string())
+    }
+}
+
+/// Providers return JSON error envelopes; keep the message, drop the noise.
+fn summarize_body(body: &str) -> String {
+    let message = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| {
+            v.pointer("/error/message")
+                .or_else(|| v.get("error"))
+                .and_then(|m| m.as_str().map(str::to_string))
+        })
+        .unwrap_or_else(|| body.trim().to_string());
+    const MAX: usize = 200;
+    if message.chars().count() > MAX {
+        let truncated: String = message.chars().take(MAX).collect();
+        format!("{truncated}\u{2026}")
+    } else {
+        message
+    }
+}
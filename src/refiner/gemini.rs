@@ -0,0 +1,360 @@
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use super::http::{self, RetryPolicy};
+use super::{OnDelta, RefineError, RefineParams, Refiner};
+
+/// Gemini request types
+#[derive(Serialize)]
+struct GeminiRequest {
+    system_instruction: SystemInstruction,
+    contents: Vec<Content>,
+    generation_config: GenerationConfig,
+}
+
+#[derive(Serialize)]
+struct SystemInstruction {
+    parts: Vec<Part>,
+}
+
+#[derive(Serialize)]
+struct Content {
+    parts: Vec<Part>,
+}
+
+#[derive(Serialize)]
+struct Part {
+    text: String,
+}
+
+#[derive(Serialize)]
+struct GenerationConfig {
+    temperature: f32,
+    max_output_tokens: u32,
+}
+
+/// Gemini response types
+#[derive(Deserialize)]
+struct GeminiResponse {
+    candidates: Option<Vec<Candidate>>,
+}
+
+#[derive(Deserialize)]
+struct Candidate {
+    // Streamed chunks (e.g. the final one carrying `finishReason`) may omit content
+    #[serde(default)]
+    content: CandidateContent,
+}
+
+#[derive(Deserialize, Default)]
+struct CandidateContent {
+    #[serde(default)]
+    parts: Vec<CandidatePart>,
+}
+
+impl GeminiResponse {
+    /// Concatenated text of the first candidate, if any.
+    fn text(self) -> Option<String> {
+        self.candidates.and_then(|c| c.into_iter().next()).map(|c| {
+            c.content
+                .parts
+                .into_iter()
+                .map(|p| p.text)
+                .collect::<Vec<_>>()
+                .join("")
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct CandidatePart {
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct ModelList {
+    #[serde(default)]
+    models: Vec<ModelInfo>,
+}
+
+#[derive(Deserialize)]
+struct ModelInfo {
+    /// Resource name, e.g. `models/gemini-2.5-flash`
+    name: String,
+}
+
+/// Refinement via the Gemini `generateContent` / `streamGenerateContent` API.
+pub struct GeminiRefiner {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+    /// Model resource URL, e.g. `.../v1beta/models/gemini-2.5-flash`
+    model_url: String,
+    api_key: String,
+    params: RefineParams,
+    policy: RetryPolicy,
+}
+
+impl GeminiRefiner {
+    /// `base_url` is the API root, e.g. `https://generativelanguage.googleapis.com/v1beta`.
+    pub fn new(
+        base_url: &str,
+        model: &str,
+        api_key: &str,
+        params: RefineParams,
+        policy: RetryPolicy,
+    ) -> Result<Self, RefineError> {
+        let base_url = base_url.trim_end_matches('/').to_string();
+        Ok(Self {
+            client: http::client()?,
+            model_url: format!("{base_url}/models/{model}"),
+            base_url,
+            model: model.to_string(),
+            api_key: api_key.to_string(),
+            params,
+            policy,
+        })
+    }
+
+    fn request_body(&self, transcript: &str) -> GeminiRequest {
+        GeminiRequest {
+            system_instruction: SystemInstruction {
+                parts: vec![Part {
+                    text: self.params.system_prompt.clone(),
+                }],
+            },
+            contents: vec![Content {
+                parts: vec![Part {
+                    text: transcript.to_string(),
+                }],
+            }],
+            generation_config: GenerationConfig {
+                temperature: self.params.temperature,
+                max_output_tokens: self.params.max_tokens,
+            },
+        }
+    }
+}
+
+/// The key goes in a header so it never appears in URLs, proxy logs or errors.
+fn post(
+    client: &reqwest::Client,
+    url: &str,
+    api_key: &str,
+    body: &GeminiRequest,
+) -> reqwest::RequestBuilder {
+    client
+        .post(url)
+        .header("x-goog-api-key", api_key)
+        .json(body)
+}
+
+impl Refiner for GeminiRefiner {
+    fn name(&self) -> &'static str {
+        "gemini"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn refine(&self, transcript: &str) -> BoxFuture<'static, Result<String, RefineError>> {
+        let client = self.client.clone();
+        let url = format!("{}:generateContent", self.model_url);
+        let api_key = self.api_key.clone();
+        let body = self.request_body(transcript);
+        let policy = self.policy;
+
+        Box::pin(async move {
+            let resp =
+                http::send("Gemini", policy, || post(&client, &url, &api_key, &body)).await?;
+            let gemini_resp: GeminiResponse = resp.json().await?;
+            // A blocked prompt comes back without any text
+            let text = gemini_resp
+                .text()
+                .filter(|text| !text.trim().is_empty())
+                .ok_or_else(|| RefineError::InvalidResponse("Gemini returned no text".into()))?;
+            Ok(text.trim().to_string())
+        })
+    }
+
+    fn refine_streaming(
+        &self,
+        transcript: &str,
+        on_delta: OnDelta,
+    ) -> BoxFuture<'static, Result<String, RefineError>> {
+        let client = self.client.clone();
+        let url = format!("{}:streamGenerateContent?alt=sse", self.model_url);
+        let api_key = self.api_key.clone();
+        let body = self.request_body(transcript);
+        let policy = self.policy;
+
+        Box::pin(async move {
+            let resp =
+                http::send("Gemini", policy, || post(&client, &url, &api_key, &body)).await?;
+            let mut text = String::new();
+            super::sse::for_each_event(resp, |data| {
+                let chunk: GeminiResponse = serde_json::from_str(data)?;
+                if let Some(delta) = chunk.text().filter(|d| !d.is_empty()) {
+                    on_delta(&delta);
+                    text.push_str(&delta);
+                }
+                Ok(true)
+            })
+            .await?;
+            if text.trim().is_empty() {
+                return Err(RefineError::InvalidResponse(
+                    "Gemini stream ended without any text".into(),
+                ));
+            }
+            Ok(text.trim().to_string())
+        })
+    }
+
+    fn list_models(&self) -> BoxFuture<'static, Result<Vec<String>, RefineError>> {
+        let client = self.client.clone();
+        let url = format!("{}/models?pageSize=1000", self.base_url);
+        let api_key = self.api_key.clone();
+        let policy = self.policy.once();
+
+        Box::pin(async move {
+            let resp = http::send("Gemini", policy, || {
+                client.get(&url).header("x-goog-api-key", &api_key)
+            })
+            .await?;
+            let list: ModelList = resp.json().await?;
+            Ok(list
+                .models
+                .into_iter()
+                .map(|m| m.name.trim_start_matches("models/").to_string())
+                .collect())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{Reply, Server};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    fn refiner(url: &str) -> GeminiRefiner {
+        let params = RefineParams {
+            system_prompt: "Clean it up".into(),
+            temperature: 0.2,
+            max_tokens: 256,
+        };
+        let policy = RetryPolicy {
+            timeout: Duration::from_secs(5),
+            max_retries: 0,
+        };
+        GeminiRefiner::new(url, "gemini-test", "AIzaTestKey", params, policy).unwrap()
+    }
+
+    #[tokio::test]
+    async fn refines_with_the_key_in_a_header() {
+        let server = Server::start(vec![Reply::json(serde_json::json!({
+            "candidates": [{ "content": { "parts": [{ "text": "Fix the " }, { "text": "bug.\n" }] } }]
+        }))]);
+        let refined = refiner(&format!("{}/v1beta/", server.url))
+            .refine("um fix the bug")
+            .await
+            .unwrap();
+        assert_eq!(refined, "Fix the bug.");
+
+        let request = &server.requests()[0];
+        assert_eq!(
+            request.line,
+            "POST /v1beta/models/gemini-test:generateContent"
+        );
+        assert_eq!(request.header("x-goog-api-key"), Some("AIzaTestKey"));
+        let body = request.json();
+        assert_eq!(
+            body["system_instruction"]["parts"][0]["text"],
+            "Clean it up"
+        );
+        assert_eq!(body["contents"][0]["parts"][0]["text"], "um fix the bug");
+        assert_eq!(body["generation_config"]["max_output_tokens"], 256);
+    }
+
+    #[tokio::test]
+    async fn blocked_response_is_an_error() {
+        let server = Server::start(vec![Reply::json(serde_json::json!({
+            "candidates": [{ "finishReason": "SAFETY" }]
+        }))]);
+        let err = refiner(&server.url).refine("transcript").await.unwrap_err();
+        assert!(matches!(err, RefineError::InvalidResponse(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn auth_failure_is_classified() {
+        let server = Server::start(vec![Reply::new(
+            403,
+            r#"{"error": {"message": "API key not valid"}}"#,
+        )]);
+        let err = refiner(&server.url).refine("transcript").await.unwrap_err();
+        assert!(matches!(err, RefineError::Auth(_)), "{err:?}");
+    }
+
+    /// A delta callback and the deltas it has seen.
+    fn collect() -> (OnDelta, Arc<Mutex<Vec<String>>>) {
+        let deltas = Arc::new(Mutex::new(Vec::new()));
+        let seen = deltas.clone();
+        let on_delta: OnDelta = Arc::new(move |d: &str| seen.lock().unwrap().push(d.to_string()));
+        (on_delta, deltas)
+    }
+
+    #[tokio::test]
+    async fn streaming_joins_the_deltas() {
+        let server = Server::start(vec![Reply::streamed(
+            200,
+            &[
+                "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Fix \"}]}}]}\n\n",
+                "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"the bug.\"}]}}]}\n\n",
+                "data: {\"candidates\": [{\"finishReason\": \"STOP\"}]}\n\n",
+            ],
+        )
+        .header("content-type", "text/event-stream")]);
+        let (on_delta, deltas) = collect();
+        let refined = refiner(&format!("{}/v1beta", server.url))
+            .refine_streaming("um fix the bug", on_delta)
+            .await
+            .unwrap();
+        assert_eq!(refined, "Fix the bug.");
+        assert_eq!(*deltas.lock().unwrap(), ["Fix ", "the bug."]);
+
+        let request = &server.requests()[0];
+        assert_eq!(
+            request.line,
+            "POST /v1beta/models/gemini-test:streamGenerateContent?alt=sse"
+        );
+        assert_eq!(request.header("x-goog-api-key"), Some("AIzaTestKey"));
+    }
+
+    #[tokio::test]
+    async fn stream_without_text_is_an_error() {
+        let server = Server::start(vec![Reply::new(
+            200,
+            "data: {\"candidates\": [{\"finishReason\": \"SAFETY\"}]}\n\n",
+        )]);
+        let (on_delta, deltas) = collect();
+        let err = refiner(&server.url)
+            .refine_streaming("transcript", on_delta)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RefineError::InvalidResponse(_)), "{err:?}");
+        assert!(deltas.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn stream_status_error_is_classified() {
+        let server = Server::start(vec![Reply::new(429, "quota exhausted")]);
+        let (on_delta, _) = collect();
+        let err = refiner(&server.url)
+            .refine_streaming("transcript", on_delta)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RefineError::Quota(_)), "{err:?}");
+    }
+}
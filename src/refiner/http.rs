@@ -0,0 +1,226 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use super::error::RefineError;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(8);
+
+/// One HTTP client for all refiners, so connections are pooled and every
+/// request gets a connect timeout.
+pub fn client() -> Result<reqwest::Client, RefineError> {
+    static CLIENT: OnceLock<Result<reqwest::Client, String>> = OnceLock::new();
+    CLIENT
+        .get_or_init(|| {
+            reqwest::Client::builder()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .build()
+                .map_err(|e| e.to_string())
+        })
+        .clone()
+        .map_err(|e| RefineError::Network(format!("could not set up the HTTP client: {e}")))
+}
+
+/// Per-request timeout and retry behaviour.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Overall timeout for one attempt, including reading the response
+    pub timeout: Duration,
+    /// Extra attempts after the first on 429, 5xx and network errors
+    pub max_retries: u32,
+}
+
+impl RetryPolicy {
+    /// Same timeout, no retries — for interactive checks.
+    pub fn once(self) -> Self {
+        Self {
+            max_retries: 0,
+            ..self
+        }
+    }
+
+    /// Delay before retry number `attempt` (0-based): 0.5s, 1s, 2s, ... capped.
+    fn backoff(&self, attempt: u32) -> Duration {
+        BASE_DELAY
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(MAX_DELAY)
+    }
+}
+
+/// Send the request built by `build`, retrying transient failures with
+/// exponential backoff. `Retry-After` from the server takes precedence
+/// over the computed delay. Returns the first successful response, or the
+/// classified error of the last attempt.
+pub async fn send<F>(
+    provider: &str,
+    policy: RetryPolicy,
+    build: F,
+) -> Result<reqwest::Response, RefineError>
+where
+    F: Fn() -> reqwest::RequestBuilder,
+{
+    let mut attempt = 0;
+    loop {
+        let (err, retry_after) = match build().timeout(policy.timeout).send().await {
+            Ok(resp) if resp.status().is_success() => return Ok(resp),
+            Ok(resp) => {
+                let status = resp.status();
+                let retry_after = retry_after(&resp);
+                let body = resp.text().await.unwrap_or_default();
+                let err = RefineError::from_status(provider, status.as_u16(), &body);
+                if !is_retryable_status(status) {
+                    return Err(err);
+                }
+                (err, retry_after)
+            }
+            Err(e) => (RefineError::from(e), None),
+        };
+
+        if attempt >= policy.max_retries {
+            return Err(err);
+        }
+        let delay = retry_after.unwrap_or_else(|| policy.backoff(attempt));
+        // A server asking us to wait minutes is a quota problem, not a blip
+        if delay > MAX_DELAY * 4 {
+            return Err(err);
+        }
+        log::info!(
+            "{provider} request failed ({}), retrying in {:.1}s",
+            err.kind(),
+            delay.as_secs_f32()
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn retry_after(resp: &reqwest::Response) -> Option<Duration> {
+    let value = resp.headers().get(reqwest::header::RETRY_AFTER)?;
+    parse_retry_after(value.to_str().ok()?, chrono::Utc::now())
+}
+
+/// Parse `Retry-After` as delta-seconds or an HTTP date.
+fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = at.signed_duration_since(now);
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{Reply, Server};
+
+    fn policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            timeout: Duration::from_secs(5),
+            max_retries,
+        }
+    }
+
+    /// A failure that asks to be retried straight away, so tests don't wait.
+    fn failure(status: u16) -> Reply {
+        Reply::new(status, "try again").header("retry-after", "0")
+    }
+
+    async fn post(server: &Server, policy: RetryPolicy) -> Result<String, RefineError> {
+        let client = client().unwrap();
+        let url = format!("{}/v1/chat", server.url);
+        let resp = send("Test", policy, || client.post(&url).body("hi")).await?;
+        Ok(resp.text().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn rate_limits_and_server_errors_are_retried() {
+        let server = Server::start(vec![failure(429), failure(503), Reply::new(200, "done")]);
+        assert_eq!(post(&server, policy(2)).await, Ok("done".into()));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests
+            .iter()
+            .all(|r| r.line == "POST /v1/chat" && r.text() == "hi"));
+    }
+
+    #[tokio::test]
+    async fn retries_stop_at_the_limit() {
+        let server = Server::start(vec![failure(500), failure(502), failure(503)]);
+        let err = post(&server, policy(1)).await.unwrap_err();
+        assert_eq!(err, RefineError::Server(502, "Test: try again".into()));
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn no_retries_means_one_attempt() {
+        let server = Server::start(vec![failure(429), Reply::new(200, "done")]);
+        let err = post(&server, policy(3).once()).await.unwrap_err();
+        assert_eq!(err.kind(), "quota");
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_retried() {
+        for status in [400, 401, 403, 404] {
+            let server = Server::start(vec![failure(status), Reply::new(200, "done")]);
+            let err = post(&server, policy(3)).await.unwrap_err();
+            assert_eq!(err, RefineError::from_status("Test", status, "try again"));
+            assert_eq!(server.requests().len(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn long_retry_after_gives_up() {
+        let server = Server::start(vec![
+            Reply::new(429, "slow down").header("retry-after", "3600"),
+            Reply::new(200, "done"),
+        ]);
+        let err = post(&server, policy(3)).await.unwrap_err();
+        assert_eq!(err.kind(), "quota");
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn retry_after_in_seconds() {
+        let now = chrono::Utc::now();
+        assert_eq!(parse_retry_after("0", now), Some(Duration::ZERO));
+        assert_eq!(
+            parse_retry_after(" 120 ", now),
+            Some(Duration::from_secs(120))
+        );
+    }
+
+    #[test]
+    fn retry_after_as_a_date() {
+        let now = chrono::DateTime::parse_from_rfc2822("Wed, 21 Oct 2026 07:28:00 GMT")
+            .unwrap()
+            .to_utc();
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2026 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        // A date already past means retry now
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2026 07:27:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+        assert_eq!(parse_retry_after("-5", now), None);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_a_cap() {
+        let delays: Vec<u64> = (0..7)
+            .map(|attempt| policy(0).backoff(attempt).as_millis() as u64)
+            .collect();
+        assert_eq!(delays, [500, 1000, 2000, 4000, 8000, 8000, 8000]);
+    }
+}
@@ -0,0 +1,196 @@
+mod error;
+mod gemini;
+mod http;
+mod ollama;
+mod openai;
+mod rules;
+mod sse;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, RefinementProfile, RefinementProvider};
+
+pub use error::RefineError;
+use gemini::GeminiRefiner;
+use http::RetryPolicy;
+use ollama::OllamaRefiner;
+use openai::OpenAiRefiner;
+use rules::RuleRefiner;
+
+pub const SYSTEM_PROMPT: &str = r#"You are a voice-to-text post-processor for a developer who dictates prompts for Claude Code (an AI coding assistant).
+
+Your task:
+1. Remove all filler words (um, uh, like, you know, basically, actually, so, well, etc.)
+2. Extract the coding/technical intent from the speech
+3. Preserve ALL technical terms, library names, function names, file paths, and code identifiers EXACTLY as spoken
+4. Fix obvious speech-to-text errors for technical terms (e.g., "react" should stay "React" if referring to the library)
+5. Structure the output as a clear, concise prompt that Claude Code can act on
+6. Keep line breaks, bullet lists and Markdown code (inline and fenced) that are already present in the input
+7. Output ONLY the cleaned prompt — no explanations, no preamble, no commentary
+
+If the input is already clean and well-structured, return it as-is."#;
+
+pub const COMMIT_PROMPT: &str = r#"You turn a developer's spoken description of a code change into a git commit message.
+
+Rules:
+1. First line: imperative mood summary, at most 72 characters, no trailing period
+2. If there is more detail, add a blank line followed by a short body wrapped at 72 characters
+3. Remove filler words and speech artifacts; keep identifiers and file paths exactly as spoken
+4. Output ONLY the commit message — no explanations, no code fences"#;
+
+pub const CHAT_PROMPT: &str = r#"You clean up a dictated chat message (Slack, Teams, email reply).
+
+Rules:
+1. Remove filler words, false starts and repetitions
+2. Keep the speaker's tone and meaning; do not add content
+3. Fix punctuation and capitalization; keep it concise and conversational
+4. Output ONLY the message text"#;
+
+/// Receives refined text incrementally as a provider streams it.
+pub type OnDelta = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// An LLM backend that turns a raw transcript into a clean prompt.
+///
+/// Implementations own their HTTP client and settings so the returned
+/// future is `'static` and can be spawned on the tokio runtime.
+pub trait Refiner: Send + Sync {
+    /// Short name used in logs and the dashboard.
+    fn name(&self) -> &'static str;
+
+    /// Model the provider is asked to use; empty for offline refiners.
+    fn model(&self) -> &str {
+        ""
+    }
+
+    /// Refine `transcript` into a prompt.
+    fn refine(
+        &self,
+        transcript: &str,
+    ) -> BoxFuture<'static, Result<String, RefineError>>;
+
+    /// Refine `transcript`, calling `on_delta` with each chunk of text as it
+    /// arrives. Resolves to the complete text. Providers without a streaming
+    /// API deliver the whole result as a single chunk.
+    fn refine_streaming(
+        &self,
+        transcript: &str,
+        on_delta: OnDelta,
+    ) -> BoxFuture<'static, Result<String, RefineError>> {
+        let fut = self.refine(transcript);
+        Box::pin(async move {
+            let text = fut.await?;
+            on_delta(&text);
+            Ok(text)
+        })
+    }
+
+    /// Cheap authenticated request used to test the connection: the models
+    /// the provider offers. Offline refiners report none.
+    // Only the dashboard tests connections; the providers' HTTP code is
+    // not worth gating method by method
+    #[cfg_attr(not(feature = "ui"), allow(dead_code))]
+    fn list_models(&self) -> BoxFuture<'static, Result<Vec<String>, RefineError>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+}
+
+/// Prompt and sampling settings shared by every provider.
+#[derive(Debug, Clone)]
+pub struct RefineParams {
+    pub system_prompt: String,
+    pub temperature: f32,
+    pub max_tokens: u32,
+}
+
+impl From<&RefinementProfile> for RefineParams {
+    fn from(profile: &RefinementProfile) -> Self {
+        Self {
+            system_prompt: profile.system_prompt.clone(),
+            temperature: profile.temperature,
+            max_tokens: profile.max_tokens,
+        }
+    }
+}
+
+/// Chat message used by the OpenAI-compatible and Ollama APIs.
+#[derive(Serialize, Deserialize)]
+struct ChatMessage {
+    role: String,
+    /// `null` in OpenAI replies that carry a refusal or tool call instead
+    content: Option<String>,
+}
+
+impl ChatMessage {
+    /// The reply's text, or an error naming `provider` if there is none.
+    fn into_text(self, provider: &str) -> Result<String, RefineError> {
+        self.content
+            .filter(|text| !text.trim().is_empty())
+            .map(|text| text.trim().to_string())
+            .ok_or_else(|| RefineError::InvalidResponse(format!("{provider} returned no text")))
+    }
+}
+
+fn chat_messages(params: &RefineParams, transcript: &str) -> Vec<ChatMessage> {
+    vec![
+        ChatMessage {
+            role: "system".into(),
+            content: Some(params.system_prompt.clone()),
+        },
+        ChatMessage {
+            role: "user".into(),
+            content: Some(transcript.to_string()),
+        },
+    ]
+}
+
+/// Apply the rule-based cleanup when it is configured as a pre-pass.
+pub fn prepare(config: &Config, transcript: &str) -> String {
+    if config.cleanup.pre_pass {
+        rules::clean(transcript, &config.cleanup.substitutions)
+    } else {
+        transcript.to_string()
+    }
+}
+
+/// Build the refiner for `profile`, using the endpoints and keys in `config`.
+/// Falls back to the offline rule-based refiner when Gemini has no API key,
+/// and fails only when the HTTP client cannot be set up.
+pub fn build(
+    config: &Config,
+    profile: &RefinementProfile,
+) -> Result<Box<dyn Refiner>, RefineError> {
+    let params = RefineParams::from(profile);
+    let policy = RetryPolicy {
+        timeout: Duration::from_secs(config.refinement.timeout_secs.max(1)),
+        max_retries: config.refinement.max_retries,
+    };
+    let base_url = config.refinement.base_url(profile.provider);
+    let model = profile.effective_model();
+    Ok(match profile.provider {
+        RefinementProvider::Gemini if config.gemini_api_key.is_empty() => {
+            log::info!("No Gemini API key — using rule-based cleanup");
+            Box::new(RuleRefiner::new(&config.cleanup.substitutions))
+        }
+        RefinementProvider::Gemini => Box::new(GeminiRefiner::new(
+            &base_url,
+            &model,
+            &config.gemini_api_key,
+            params,
+            policy,
+        )?),
+        RefinementProvider::OpenAi => Box::new(OpenAiRefiner::new(
+            &base_url,
+            &model,
+            &config.refinement.openai_api_key,
+            params,
+            policy,
+        )?),
+        RefinementProvider::Ollama => {
+            Box::new(OllamaRefiner::new(&base_url, &model, params, policy)?)
+        }
+    })
+}
@@ -0,0 +1,262 @@
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use super::http::{self, RetryPolicy};
+use super::{chat_messages, ChatMessage, OnDelta, RefineError, RefineParams, Refiner};
+
+#[derive(Serialize)]
+struct OllamaRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    stream: bool,
+    options: OllamaOptions,
+}
+
+#[derive(Serialize)]
+struct OllamaOptions {
+    temperature: f32,
+    num_predict: u32,
+}
+
+/// A full response, or one newline-delimited chunk when streaming.
+#[derive(Deserialize)]
+struct OllamaResponse {
+    message: ChatMessage,
+    #[serde(default)]
+    done: bool,
+}
+
+/// `/api/tags` response: the locally installed models.
+#[derive(Deserialize)]
+struct TagList {
+    #[serde(default)]
+    models: Vec<TagInfo>,
+}
+
+#[derive(Deserialize)]
+struct TagInfo {
+    name: String,
+}
+
+/// Refinement via a local or self-hosted Ollama `/api/chat` endpoint.
+pub struct OllamaRefiner {
+    client: reqwest::Client,
+    base_url: String,
+    url: String,
+    model: String,
+    params: RefineParams,
+    policy: RetryPolicy,
+}
+
+impl OllamaRefiner {
+    /// `base_url` is the server root, e.g. `http://127.0.0.1:11434`.
+    pub fn new(
+        base_url: &str,
+        model: &str,
+        params: RefineParams,
+        policy: RetryPolicy,
+    ) -> Result<Self, RefineError> {
+        let base_url = base_url.trim_end_matches('/').to_string();
+        Ok(Self {
+            client: http::client()?,
+            url: format!("{base_url}/api/chat"),
+            base_url,
+            model: model.to_string(),
+            params,
+            policy,
+        })
+    }
+
+    /// Send the chat request with retries; the returned future owns its data.
+    fn send(
+        &self,
+        transcript: &str,
+        stream: bool,
+    ) -> impl std::future::Future<Output = Result<reqwest::Response, RefineError>> + 'static {
+        let body = OllamaRequest {
+            model: self.model.clone(),
+            messages: chat_messages(&self.params, transcript),
+            stream,
+            options: OllamaOptions {
+                temperature: self.params.temperature,
+                num_predict: self.params.max_tokens,
+            },
+        };
+        let client = self.client.clone();
+        let url = self.url.clone();
+        let policy = self.policy;
+        async move { http::send("Ollama", policy, || client.post(&url).json(&body)).await }
+    }
+}
+
+impl Refiner for OllamaRefiner {
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn refine(&self, transcript: &str) -> BoxFuture<'static, Result<String, RefineError>> {
+        let send = self.send(transcript, false);
+
+        Box::pin(async move {
+            let resp = send.await?;
+            let ollama_resp: OllamaResponse = resp.json().await?;
+            ollama_resp.message.into_text("Ollama")
+        })
+    }
+
+    /// Ollama streams newline-delimited JSON objects rather than SSE.
+    fn refine_streaming(
+        &self,
+        transcript: &str,
+        on_delta: OnDelta,
+    ) -> BoxFuture<'static, Result<String, RefineError>> {
+        let send = self.send(transcript, true);
+
+        Box::pin(async move {
+            let resp = send.await?;
+            let mut text = String::new();
+            super::sse::for_each_line(resp, |line| {
+                if line.trim().is_empty() {
+                    return Ok(true);
+                }
+                let chunk: OllamaResponse = serde_json::from_str(line)?;
+                if let Some(delta) = chunk.message.content.filter(|d| !d.is_empty()) {
+                    on_delta(&delta);
+                    text.push_str(&delta);
+                }
+                Ok(!chunk.done)
+            })
+            .await?;
+            if text.trim().is_empty() {
+                return Err(RefineError::InvalidResponse(
+                    "Ollama stream ended without any text".into(),
+                ));
+            }
+            Ok(text.trim().to_string())
+        })
+    }
+
+    fn list_models(&self) -> BoxFuture<'static, Result<Vec<String>, RefineError>> {
+        let client = self.client.clone();
+        let url = format!("{}/api/tags", self.base_url);
+        let policy = self.policy.once();
+
+        Box::pin(async move {
+            let resp = http::send("Ollama", policy, || client.get(&url)).await?;
+            let list: TagList = resp.json().await?;
+            Ok(list.models.into_iter().map(|m| m.name).collect())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{Reply, Server};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    fn refiner(url: &str) -> OllamaRefiner {
+        let params = RefineParams {
+            system_prompt: "Clean it up".into(),
+            temperature: 0.2,
+            max_tokens: 256,
+        };
+        let policy = RetryPolicy {
+            timeout: Duration::from_secs(5),
+            max_retries: 0,
+        };
+        OllamaRefiner::new(url, "llama-test", params, policy).unwrap()
+    }
+
+    #[tokio::test]
+    async fn refines_with_api_chat() {
+        let server = Server::start(vec![Reply::json(serde_json::json!({
+            "message": { "role": "assistant", "content": "Fix the bug.\n" },
+            "done": true
+        }))]);
+        let refined = refiner(&server.url).refine("um fix the bug").await.unwrap();
+        assert_eq!(refined, "Fix the bug.");
+
+        let request = &server.requests()[0];
+        assert_eq!(request.line, "POST /api/chat");
+        let body = request.json();
+        assert_eq!(body["model"], "llama-test");
+        assert_eq!(body["stream"], false);
+        assert_eq!(body["options"]["num_predict"], 256);
+        assert_eq!(body["messages"][1]["content"], "um fix the bug");
+    }
+
+    #[tokio::test]
+    async fn empty_reply_is_an_error() {
+        let server = Server::start(vec![Reply::json(serde_json::json!({
+            "message": { "role": "assistant", "content": "" },
+            "done": true
+        }))]);
+        let err = refiner(&server.url).refine("transcript").await.unwrap_err();
+        assert!(matches!(err, RefineError::InvalidResponse(_)), "{err:?}");
+    }
+
+    /// A delta callback and the deltas it has seen.
+    fn collect() -> (OnDelta, Arc<Mutex<Vec<String>>>) {
+        let deltas = Arc::new(Mutex::new(Vec::new()));
+        let seen = deltas.clone();
+        let on_delta: OnDelta = Arc::new(move |d: &str| seen.lock().unwrap().push(d.to_string()));
+        (on_delta, deltas)
+    }
+
+    #[tokio::test]
+    async fn streaming_stops_when_done() {
+        let server = Server::start(vec![Reply::streamed(
+            200,
+            &[
+                "{\"message\": {\"role\": \"assistant\", \"content\": \"Fix \"}, \"done\": false}\n",
+                "{\"message\": {\"role\": \"assistant\", \"content\": \"the bug.\"}, \"done\": false}\n",
+                "{\"message\": {\"role\": \"assistant\", \"content\": \"\"}, \"done\": true}\n",
+                "{\"message\": {\"role\": \"assistant\", \"content\": \" late\"}, \"done\": false}\n",
+            ],
+        )
+        .header("content-type", "application/x-ndjson")]);
+        let (on_delta, deltas) = collect();
+        let refined = refiner(&server.url)
+            .refine_streaming("um fix the bug", on_delta)
+            .await
+            .unwrap();
+        assert_eq!(refined, "Fix the bug.");
+        assert_eq!(*deltas.lock().unwrap(), ["Fix ", "the bug."]);
+
+        let request = &server.requests()[0];
+        assert_eq!(request.line, "POST /api/chat");
+        assert_eq!(request.json()["stream"], true);
+    }
+
+    #[tokio::test]
+    async fn stream_without_text_is_an_error() {
+        let server = Server::start(vec![Reply::new(
+            200,
+            "{\"message\": {\"role\": \"assistant\", \"content\": \" \"}, \"done\": true}\n",
+        )]);
+        let (on_delta, _) = collect();
+        let err = refiner(&server.url)
+            .refine_streaming("transcript", on_delta)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RefineError::InvalidResponse(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn stream_status_error_is_classified() {
+        let server = Server::start(vec![Reply::new(404, r#"{"error": "model not found"}"#)]);
+        let (on_delta, deltas) = collect();
+        let err = refiner(&server.url)
+            .refine_streaming("transcript", on_delta)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RefineError::Request(404, _)), "{err:?}");
+        assert!(deltas.lock().unwrap().is_empty());
+    }
+}
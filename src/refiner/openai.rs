@@ -0,0 +1,317 @@
+use futures_util::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use super::http::{self, RetryPolicy};
+use super::{chat_messages, ChatMessage, OnDelta, RefineError, RefineParams, Refiner};
+
+#[derive(Serialize)]
+struct ChatRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    temperature: f32,
+    max_tokens: u32,
+    stream: bool,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: ChatMessage,
+}
+
+/// One `data:` event of a streamed chat completion.
+#[derive(Deserialize)]
+struct ChatChunk {
+    choices: Vec<ChunkChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChunkChoice {
+    #[serde(default)]
+    delta: ChunkDelta,
+}
+
+#[derive(Deserialize, Default)]
+struct ChunkDelta {
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ModelList {
+    #[serde(default)]
+    data: Vec<ModelInfo>,
+}
+
+#[derive(Deserialize)]
+struct ModelInfo {
+    id: String,
+}
+
+/// Refinement via an OpenAI-compatible `/chat/completions` endpoint
+/// (OpenAI, vLLM, llama.cpp server, LiteLLM, ...).
+pub struct OpenAiRefiner {
+    client: reqwest::Client,
+    base_url: String,
+    url: String,
+    model: String,
+    api_key: String,
+    params: RefineParams,
+    policy: RetryPolicy,
+}
+
+impl OpenAiRefiner {
+    /// `base_url` is the API root including the version, e.g. `https://api.openai.com/v1`.
+    pub fn new(
+        base_url: &str,
+        model: &str,
+        api_key: &str,
+        params: RefineParams,
+        policy: RetryPolicy,
+    ) -> Result<Self, RefineError> {
+        let base_url = base_url.trim_end_matches('/').to_string();
+        Ok(Self {
+            client: http::client()?,
+            url: format!("{base_url}/chat/completions"),
+            base_url,
+            model: model.to_string(),
+            api_key: api_key.to_string(),
+            params,
+            policy,
+        })
+    }
+
+    /// Send the chat request with retries; the returned future owns its data.
+    fn send(
+        &self,
+        transcript: &str,
+        stream: bool,
+    ) -> impl std::future::Future<Output = Result<reqwest::Response, RefineError>> + 'static {
+        let body = ChatRequest {
+            model: self.model.clone(),
+            messages: chat_messages(&self.params, transcript),
+            temperature: self.params.temperature,
+            max_tokens: self.params.max_tokens,
+            stream,
+        };
+        let client = self.client.clone();
+        let url = self.url.clone();
+        let api_key = self.api_key.clone();
+        let policy = self.policy;
+        async move {
+            http::send("OpenAI-compatible", policy, || {
+                let req = client.post(&url).json(&body);
+                // Self-hosted servers often run without authentication
+                if api_key.is_empty() {
+                    req
+                } else {
+                    req.bearer_auth(&api_key)
+                }
+            })
+            .await
+        }
+    }
+}
+
+impl Refiner for OpenAiRefiner {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn refine(&self, transcript: &str) -> BoxFuture<'static, Result<String, RefineError>> {
+        let send = self.send(transcript, false);
+
+        Box::pin(async move {
+            let resp = send.await?;
+            let chat_resp: ChatResponse = resp.json().await?;
+            chat_resp
+                .choices
+                .into_iter()
+                .next()
+                .ok_or_else(|| {
+                    RefineError::InvalidResponse("OpenAI-compatible API returned no choices".into())
+                })?
+                .message
+                .into_text("OpenAI-compatible API")
+        })
+    }
+
+    fn refine_streaming(
+        &self,
+        transcript: &str,
+        on_delta: OnDelta,
+    ) -> BoxFuture<'static, Result<String, RefineError>> {
+        let send = self.send(transcript, true);
+
+        Box::pin(async move {
+            let resp = send.await?;
+            let mut text = String::new();
+            super::sse::for_each_event(resp, |data| {
+                if data.trim() == "[DONE]" {
+                    return Ok(false);
+                }
+                let chunk: ChatChunk = serde_json::from_str(data)?;
+                let delta = chunk
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|c| c.delta.content);
+                if let Some(delta) = delta.filter(|d| !d.is_empty()) {
+                    on_delta(&delta);
+                    text.push_str(&delta);
+                }
+                Ok(true)
+            })
+            .await?;
+            if text.trim().is_empty() {
+                return Err(RefineError::InvalidResponse(
+                    "OpenAI-compatible stream ended without any text".into(),
+                ));
+            }
+            Ok(text.trim().to_string())
+        })
+    }
+
+    fn list_models(&self) -> BoxFuture<'static, Result<Vec<String>, RefineError>> {
+        let client = self.client.clone();
+        let url = format!("{}/models", self.base_url);
+        let api_key = self.api_key.clone();
+        let policy = self.policy.once();
+
+        Box::pin(async move {
+            let resp = http::send("OpenAI-compatible", policy, || {
+                let req = client.get(&url);
+                if api_key.is_empty() {
+                    req
+                } else {
+                    req.bearer_auth(&api_key)
+                }
+            })
+            .await?;
+            let list: ModelList = resp.json().await?;
+            Ok(list.data.into_iter().map(|m| m.id).collect())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{Reply, Server};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    fn refiner(url: &str, api_key: &str) -> OpenAiRefiner {
+        let params = RefineParams {
+            system_prompt: "Clean it up".into(),
+            temperature: 0.2,
+            max_tokens: 256,
+        };
+        let policy = RetryPolicy {
+            timeout: Duration::from_secs(5),
+            max_retries: 0,
+        };
+        OpenAiRefiner::new(url, "gpt-test", api_key, params, policy).unwrap()
+    }
+
+    #[tokio::test]
+    async fn refines_with_a_chat_completion() {
+        let server = Server::start(vec![Reply::json(serde_json::json!({
+            "choices": [{ "message": { "role": "assistant", "content": " Fix the bug. " } }]
+        }))]);
+        let refined = refiner(&format!("{}/v1", server.url), "sk-test")
+            .refine("um fix the bug")
+            .await
+            .unwrap();
+        assert_eq!(refined, "Fix the bug.");
+
+        let request = &server.requests()[0];
+        assert_eq!(request.line, "POST /v1/chat/completions");
+        assert_eq!(request.header("authorization"), Some("Bearer sk-test"));
+        let body = request.json();
+        assert_eq!(body["model"], "gpt-test");
+        assert_eq!(body["stream"], false);
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["messages"][0]["content"], "Clean it up");
+        assert_eq!(body["messages"][1]["content"], "um fix the bug");
+    }
+
+    #[tokio::test]
+    async fn null_content_is_an_error() {
+        let server = Server::start(vec![Reply::json(serde_json::json!({
+            "choices": [{ "message": { "role": "assistant", "content": null, "refusal": "No." } }]
+        }))]);
+        let err = refiner(&server.url, "")
+            .refine("transcript")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RefineError::InvalidResponse(_)), "{err:?}");
+        assert_eq!(server.requests()[0].header("authorization"), None);
+    }
+
+    /// A delta callback and the deltas it has seen.
+    fn collect() -> (OnDelta, Arc<Mutex<Vec<String>>>) {
+        let deltas = Arc::new(Mutex::new(Vec::new()));
+        let seen = deltas.clone();
+        let on_delta: OnDelta = Arc::new(move |d: &str| seen.lock().unwrap().push(d.to_string()));
+        (on_delta, deltas)
+    }
+
+    #[tokio::test]
+    async fn streaming_stops_at_done() {
+        let server = Server::start(vec![Reply::streamed(
+            200,
+            &[
+                "data: {\"choices\": [{\"delta\": {\"role\": \"assistant\"}}]}\n\n",
+                "data: {\"choices\": [{\"delta\": {\"content\": \"Fix \"}}]}\n\n",
+                "data: {\"choices\": [{\"delta\": {\"content\": \"the bug.\"}}]}\n\n",
+                "data: [DONE]\n\ndata: {\"choices\": [{\"delta\": {\"content\": \" late\"}}]}\n\n",
+            ],
+        )
+        .header("content-type", "text/event-stream")]);
+        let (on_delta, deltas) = collect();
+        let refined = refiner(&format!("{}/v1", server.url), "sk-test")
+            .refine_streaming("um fix the bug", on_delta)
+            .await
+            .unwrap();
+        assert_eq!(refined, "Fix the bug.");
+        assert_eq!(*deltas.lock().unwrap(), ["Fix ", "the bug."]);
+
+        let request = &server.requests()[0];
+        assert_eq!(request.line, "POST /v1/chat/completions");
+        assert_eq!(request.json()["stream"], true);
+    }
+
+    #[tokio::test]
+    async fn stream_without_text_is_an_error() {
+        let server = Server::start(vec![Reply::new(
+            200,
+            "data: {\"choices\": [{\"delta\": {\"content\": null}}]}\n\ndata: [DONE]\n\n",
+        )]);
+        let (on_delta, _) = collect();
+        let err = refiner(&server.url, "")
+            .refine_streaming("transcript", on_delta)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RefineError::InvalidResponse(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn stream_status_error_is_classified() {
+        let server = Server::start(vec![Reply::new(401, "invalid api key")]);
+        let (on_delta, deltas) = collect();
+        let err = refiner(&server.url, "sk-wrong")
+            .refine_streaming("transcript", on_delta)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RefineError::Auth(_)), "{err:?}");
+        assert!(deltas.lock().unwrap().is_empty());
+    }
+}
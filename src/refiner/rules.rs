@@ -0,0 +1,618 @@
+use futures_util::future::BoxFuture;
+
+use super::{RefineError, Refiner};
+use crate::config::Substitution;
+
+/// Fillers removed wherever they appear.
+const FILLERS: &[&str] = &[
+    "um", "umm", "uh", "uhh", "uhm", "erm", "ah", "hmm", "mhm",
+];
+
+/// Words that are fillers when set off by commas on both sides ("it's, like, ...").
+/// Otherwise they carry meaning: "turn right, then", "things I like, such as",
+/// "5 mm", "the ER".
+const COMMA_FILLERS: &[&str] = &[
+    "like", "basically", "actually", "so", "well", "anyway", "literally", "right", "okay", "er",
+    "mm",
+];
+
+/// Of those, the ones that are also fillers opening a sentence ("So, ...").
+/// "Okay, ship it" and "Right, ..." answer something, so they stay.
+const OPENING_FILLERS: &[&str] = &[
+    "like", "basically", "actually", "so", "well", "anyway", "literally", "er", "mm",
+];
+
+/// Two-word fillers, removed when the second word carries a comma or ends the sentence.
+const PHRASE_FILLERS: &[(&str, &str)] = &[("you", "know"), ("i", "mean")];
+
+/// Prefixes that form real hyphenated words ("re-read", "co-owner"), so one
+/// on its own in front of a word is not taken for a stutter.
+const WORD_PREFIXES: &[&str] = &["bi", "co", "de", "e", "ex", "re", "un"];
+
+/// Words that are legitimately doubled in English ("I know that that works").
+const ALLOWED_REPEATS: &[&str] = &["that", "had", "is"];
+
+/// Deterministic, offline transcript cleanup: removes fillers, collapses
+/// stutters and repeated words, applies the user's substitutions and fixes
+/// capitalization and punctuation. Used when no LLM is available.
+pub struct RuleRefiner {
+    substitutions: Vec<Substitution>,
+}
+
+impl RuleRefiner {
+    pub fn new(substitutions: &[Substitution]) -> Self {
+        Self {
+            substitutions: substitutions.to_vec(),
+        }
+    }
+}
+
+impl Refiner for RuleRefiner {
+    fn name(&self) -> &'static str {
+        "rules"
+    }
+
+    fn refine(
+        &self,
+        transcript: &str,
+    ) -> BoxFuture<'static, Result<String, RefineError>> {
+        let cleaned = clean(transcript, &self.substitutions);
+        Box::pin(async move { Ok(cleaned) })
+    }
+}
+
+/// A word split into leading punctuation, the word itself and trailing punctuation.
+#[derive(Debug, Clone)]
+struct Token {
+    lead: String,
+    core: String,
+    trail: String,
+}
+
+impl Token {
+    fn parse(word: &str) -> Self {
+        let is_edge = |c: char| !c.is_alphanumeric() && !matches!(c, '-' | '\'' | '_' | '/');
+        let core_start = word.find(|c: char| !is_edge(c)).unwrap_or(word.len());
+        let core_end = word
+            .rfind(|c: char| !is_edge(c))
+            .map(|i| i + word[i..].chars().next().map_or(1, char::len_utf8))
+            .unwrap_or(core_start);
+        Self {
+            lead: word[..core_start].to_string(),
+            core: word[core_start..core_end].to_string(),
+            trail: word[core_end..].to_string(),
+        }
+    }
+
+    fn lower(&self) -> String {
+        self.core.to_lowercase()
+    }
+
+    fn ends_sentence(&self) -> bool {
+        self.trail.contains(['.', '?', '!'])
+    }
+
+    fn has_comma(&self) -> bool {
+        self.trail.starts_with(',')
+    }
+
+    fn render(&self) -> String {
+        format!("{}{}{}", self.lead, self.core, self.trail)
+    }
+}
+
+/// Run the full rule-based cleanup over `text`.
+/// Works line by line so structure from spoken commands survives: list
+/// markers are kept, and fenced code blocks are left untouched.
+pub fn clean(text: &str, substitutions: &[Substitution]) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut in_fence = false;
+    let mut out = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            out.push(line.to_string());
+            continue;
+        }
+        if in_fence || line.trim().is_empty() {
+            out.push(line.to_string());
+            continue;
+        }
+        let (marker, rest) = split_list_marker(line);
+        // List items, and lines introducing a list, read better without a forced period
+        let introduces_list = lines
+            .get(i + 1)
+            .is_some_and(|next| !split_list_marker(next).0.is_empty());
+        let terminate = marker.is_empty() && !introduces_list;
+        out.push(format!("{marker}{}", clean_line(rest, substitutions, terminate)));
+    }
+    out.join("\n")
+}
+
+fn clean_line(text: &str, substitutions: &[Substitution], terminate: bool) -> String {
+    let tokens = tokenize(text);
+    let tokens = apply_substitutions(tokens, substitutions);
+    let tokens = remove_fillers(tokens);
+    let tokens = collapse_stutters(tokens);
+    let tokens = collapse_repeats(tokens);
+    let tokens = fix_case(tokens);
+    finish(tokens, terminate)
+}
+
+/// Split "- item", "* item" or "1. item" into the marker and the item text.
+fn split_list_marker(line: &str) -> (&str, &str) {
+    let body = line.trim_start();
+    let indent = line.len() - body.len();
+    let marker_len = if body.starts_with("- ") || body.starts_with("* ") {
+        2
+    } else {
+        let digits = body.chars().take_while(char::is_ascii_digit).count();
+        if digits > 0 && body[digits..].starts_with(". ") {
+            digits + 2
+        } else {
+            0
+        }
+    };
+    line.split_at(indent + marker_len)
+}
+
+/// Split on whitespace; bare punctuation ("," or "...") is attached to the previous word.
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    for word in text.split_whitespace() {
+        let token = Token::parse(word);
+        if token.core.is_empty() {
+            if let Some(prev) = tokens.last_mut() {
+                prev.trail.push_str(&token.lead);
+                continue;
+            }
+            // Leading punctuation with nothing before it is noise
+            continue;
+        }
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// Replace whole-word phrases, case-insensitively, keeping surrounding punctuation.
+fn apply_substitutions(tokens: Vec<Token>, substitutions: &[Substitution]) -> Vec<Token> {
+    let mut tokens = tokens;
+    for sub in substitutions {
+        let pattern: Vec<String> = sub.from.split_whitespace().map(str::to_lowercase).collect();
+        if pattern.is_empty() {
+            continue;
+        }
+        let mut out: Vec<Token> = Vec::with_capacity(tokens.len());
+        let mut i = 0;
+        while i < tokens.len() {
+            let window = tokens.get(i..i + pattern.len());
+            let matches = window.is_some_and(|w| {
+                w.iter().zip(&pattern).enumerate().all(|(j, (t, p))| {
+                    // Only the last word of the phrase may carry trailing punctuation
+                    t.lower() == *p && (j + 1 == pattern.len() || t.trail.is_empty())
+                })
+            });
+            if matches {
+                let first = &tokens[i];
+                let last = &tokens[i + pattern.len() - 1];
+                let lead = first.lead.clone();
+                let trail = last.trail.clone();
+                let replacement: Vec<&str> = sub.to.split_whitespace().collect();
+                if replacement.is_empty() {
+                    // Deleting a phrase: keep sentence-ending punctuation on the previous word
+                    if let Some(prev) = out.last_mut() {
+                        if prev.trail.is_empty() && !trail.starts_with(',') {
+                            prev.trail = trail;
+                        }
+                    }
+                } else {
+                    let n = replacement.len();
+                    for (j, word) in replacement.into_iter().enumerate() {
+                        out.push(Token {
+                            lead: if j == 0 { lead.clone() } else { String::new() },
+                            core: word.to_string(),
+                            trail: if j + 1 == n { trail.clone() } else { String::new() },
+                        });
+                    }
+                }
+                i += pattern.len();
+            } else {
+                out.push(tokens[i].clone());
+                i += 1;
+            }
+        }
+        tokens = out;
+    }
+    tokens
+}
+
+/// Drop a filler token, handing any sentence-ending punctuation to the previous word.
+/// Whisper brackets fillers with commas ("to, uh, fix"), so a comma pair collapses too.
+fn drop_token(out: &mut [Token], removed: &Token) {
+    let Some(prev) = out.last_mut() else {
+        return;
+    };
+    if removed.ends_sentence() {
+        prev.trail = removed.trail.trim_start_matches(',').to_string();
+    } else if removed.has_comma() && prev.has_comma() {
+        prev.trail = prev.trail[1..].to_string();
+    }
+}
+
+fn remove_fillers(tokens: Vec<Token>) -> Vec<Token> {
+    let mut out: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        let lower = token.lower();
+
+        if FILLERS.contains(&lower.as_str()) {
+            drop_token(&mut out, token);
+            i += 1;
+            continue;
+        }
+
+        let between_commas = out.last().is_some_and(Token::has_comma);
+        let opens_sentence = out.last().is_none_or(Token::ends_sentence);
+        let is_comma_filler = (between_commas && COMMA_FILLERS.contains(&lower.as_str()))
+            || (opens_sentence && OPENING_FILLERS.contains(&lower.as_str()));
+        if is_comma_filler && token.has_comma() {
+            drop_token(&mut out, token);
+            i += 1;
+            continue;
+        }
+
+        if let Some(next) = tokens.get(i + 1) {
+            let is_phrase = PHRASE_FILLERS
+                .iter()
+                .any(|(a, b)| lower == *a && next.lower() == *b);
+            // "you know, ..." or "..., you know." but not "do you know?"
+            let prev_comma = out.last().is_some_and(Token::has_comma);
+            let at_end = next.trail.starts_with('.')
+                || (i + 2 == tokens.len() && next.trail.is_empty());
+            let set_off = next.has_comma() || (prev_comma && at_end);
+            if is_phrase && token.trail.is_empty() && set_off {
+                drop_token(&mut out, next);
+                i += 2;
+                continue;
+            }
+        }
+
+        out.push(token.clone());
+        i += 1;
+    }
+    out
+}
+
+/// "fu- function" → "function", "th-the" → "the".
+fn collapse_stutters(tokens: Vec<Token>) -> Vec<Token> {
+    let mut out: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        let mut token = tokens[i].clone();
+
+        // Fragment cut off with a trailing dash, restarted in the next word
+        if token.trail == "-" || token.core.ends_with('-') {
+            let fragment = token.core.trim_end_matches('-').to_lowercase();
+            if let Some(next) = tokens.get(i + 1) {
+                if !fragment.is_empty() && next.lower().starts_with(&fragment) {
+                    i += 1;
+                    continue;
+                }
+            }
+        }
+
+        // Stutter inside a single hyphenated word
+        if token.core.contains('-') {
+            let parts: Vec<&str> = token.core.split('-').collect();
+            if let Some((last, rest)) = parts.split_last() {
+                if is_stutter(rest, last) {
+                    token.core = last.to_string();
+                }
+            }
+        }
+
+        out.push(token);
+        i += 1;
+    }
+    out
+}
+
+/// Whether the hyphen-separated `fragments` are false starts of `word`:
+/// each one or two letters of its beginning, and either repeated
+/// ("th-th-the") or not a word-forming prefix ("th-the", but not "re-read").
+fn is_stutter(fragments: &[&str], word: &str) -> bool {
+    let word = word.to_lowercase();
+    let word_len = word.chars().count();
+    let all_starts = fragments.iter().all(|f| {
+        let len = f.chars().count();
+        len > 0 && len <= 2 && len < word_len && word.starts_with(&f.to_lowercase())
+    });
+    all_starts
+        && (fragments.len() > 1 || !WORD_PREFIXES.contains(&fragments[0].to_lowercase().as_str()))
+}
+
+/// "I I want the the thing" → "I want the thing".
+fn collapse_repeats(tokens: Vec<Token>) -> Vec<Token> {
+    let mut out: Vec<Token> = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        if let Some(prev) = out.last() {
+            let same = prev.lower() == token.lower();
+            let separable = prev.trail.is_empty() || prev.trail == ",";
+            if same && separable && !ALLOWED_REPEATS.contains(&token.lower().as_str()) {
+                let lead = out.pop().map(|p| p.lead).unwrap_or_default();
+                out.push(Token { lead, ..token });
+                continue;
+            }
+        }
+        out.push(token);
+    }
+    out
+}
+
+/// Capitalize sentence starts and the pronoun "I".
+fn fix_case(tokens: Vec<Token>) -> Vec<Token> {
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut sentence_start = true;
+    for mut token in tokens {
+        let lower = token.lower();
+        let is_pronoun = lower == "i" || lower.starts_with("i'");
+        if (sentence_start && !is_code_like(&token)) || is_pronoun {
+            token.core = capitalize_first(&token.core);
+        }
+        sentence_start = token.ends_sentence();
+        out.push(token);
+    }
+    out
+}
+
+/// Identifiers and inline code keep their case: `user_id`, getUser.
+fn is_code_like(token: &Token) -> bool {
+    token.lead.contains('`')
+        || token.core.contains('_')
+        || token.core.chars().skip(1).any(char::is_uppercase)
+}
+
+fn capitalize_first(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Tidy trailing punctuation, join and (if `terminate`) make sure the text ends a sentence.
+fn finish(mut tokens: Vec<Token>, terminate: bool) -> String {
+    for token in &mut tokens {
+        token.trail = tidy_trail(&token.trail);
+    }
+    if let Some(last) = tokens.last_mut() {
+        let trimmed = last.trail.trim_end_matches([',', ';', ':']).to_string();
+        last.trail = trimmed;
+        let ends_with_word = last.core.ends_with(char::is_alphanumeric)
+            && last.trail.chars().all(|c| matches!(c, '"' | '\'' | ')'));
+        if terminate && !last.ends_sentence() && ends_with_word {
+            // Inside closing quotes, but after a closing parenthesis
+            if last.trail.ends_with(')') {
+                last.trail.push('.');
+            } else {
+                last.trail.insert(0, '.');
+            }
+        }
+    }
+    tokens.iter().map(Token::render).collect::<Vec<_>>().join(" ")
+}
+
+/// ",," → ",", ",." → ".", ". ," → "."
+fn tidy_trail(trail: &str) -> String {
+    let mut out = String::with_capacity(trail.len());
+    for c in trail.chars() {
+        match (out.chars().last(), c) {
+            (Some(','), ',') => {}
+            (Some(','), '.' | '?' | '!') => {
+                out.pop();
+                out.push(c);
+            }
+            (Some('.' | '?' | '!'), ',') => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapses_hyphenated_stutters() {
+        assert_eq!(clean("th-the file is open", &[]), "The file is open.");
+        assert_eq!(clean("call the fu-function", &[]), "Call the function.");
+        assert_eq!(clean("b-b-but why", &[]), "But why.");
+        assert_eq!(clean("re-re-read it", &[]), "Read it.");
+    }
+
+    #[test]
+    fn keeps_hyphenated_words() {
+        assert_eq!(clean("re-read the docs", &[]), "Re-read the docs.");
+        assert_eq!(
+            clean("use the pre-prepared list", &[]),
+            "Use the pre-prepared list."
+        );
+        assert_eq!(clean("ask the co-cofounder", &[]), "Ask the co-cofounder.");
+        assert_eq!(clean("a co-owner", &[]), "A co-owner.");
+        assert_eq!(clean("a well-known bug", &[]), "A well-known bug.");
+    }
+
+    fn sub(from: &str, to: &str) -> Substitution {
+        Substitution {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    #[test]
+    fn removes_fillers() {
+        assert_eq!(
+            clean("um I think uh we should go", &[]),
+            "I think we should go."
+        );
+        assert_eq!(clean("we need to, uh, fix it", &[]), "We need to fix it.");
+        assert_eq!(clean("it works, erm.", &[]), "It works.");
+        assert_eq!(clean("Hmm, not sure", &[]), "Not sure.");
+        assert_eq!(clean("umm", &[]), "");
+    }
+
+    #[test]
+    fn keeps_units_and_abbreviations() {
+        assert_eq!(clean("the margin is 5 mm", &[]), "The margin is 5 mm.");
+        assert_eq!(clean("set it to 10 mm wide", &[]), "Set it to 10 mm wide.");
+        assert_eq!(clean("send him to the er", &[]), "Send him to the er.");
+        assert_eq!(clean("it's, mm, fine", &[]), "It's fine.");
+        assert_eq!(clean("er, I think so", &[]), "I think so.");
+        assert_eq!(clean("Mm, not sure", &[]), "Not sure.");
+    }
+
+    #[test]
+    fn removes_comma_fillers_between_commas() {
+        assert_eq!(clean("it's, like, broken", &[]), "It's broken.");
+        assert_eq!(
+            clean("the build, basically, never finishes", &[]),
+            "The build never finishes."
+        );
+        assert_eq!(
+            clean("it works, right, but slowly", &[]),
+            "It works but slowly."
+        );
+    }
+
+    #[test]
+    fn removes_comma_fillers_opening_a_sentence() {
+        assert_eq!(clean("so, what now", &[]), "What now.");
+        assert_eq!(
+            clean("It failed. Well, try again", &[]),
+            "It failed. Try again."
+        );
+        assert_eq!(
+            clean("Actually, use the other one", &[]),
+            "Use the other one."
+        );
+    }
+
+    #[test]
+    fn keeps_comma_fillers_that_carry_meaning() {
+        assert_eq!(
+            clean("turn right, then left", &[]),
+            "Turn right, then left."
+        );
+        assert_eq!(clean("Okay, ship it", &[]), "Okay, ship it.");
+        assert_eq!(
+            clean("Right, that makes sense", &[]),
+            "Right, that makes sense."
+        );
+        assert_eq!(
+            clean("things I like, such as tests", &[]),
+            "Things I like, such as tests."
+        );
+        assert_eq!(
+            clean("I did so, and it worked", &[]),
+            "I did so, and it worked."
+        );
+        assert_eq!(clean("it works well", &[]), "It works well.");
+        assert_eq!(
+            clean("make it look like this", &[]),
+            "Make it look like this."
+        );
+    }
+
+    #[test]
+    fn removes_phrase_fillers() {
+        assert_eq!(
+            clean("you know, the cache is stale", &[]),
+            "The cache is stale."
+        );
+        assert_eq!(clean("it's slow, you know.", &[]), "It's slow.");
+        assert_eq!(
+            clean("I mean, it could be faster", &[]),
+            "It could be faster."
+        );
+        assert_eq!(
+            clean("do you know the answer", &[]),
+            "Do you know the answer."
+        );
+        assert_eq!(clean("what do you know?", &[]), "What do you know?");
+    }
+
+    #[test]
+    fn collapses_repeated_words() {
+        assert_eq!(clean("I I want the the thing", &[]), "I want the thing.");
+        assert_eq!(clean("open the, the file", &[]), "Open the file.");
+        assert_eq!(
+            clean("I know that that works", &[]),
+            "I know that that works."
+        );
+        assert_eq!(clean("it is done. Done.", &[]), "It is done. Done.");
+    }
+
+    #[test]
+    fn fixes_capitalisation() {
+        assert_eq!(
+            clean("fix it. then test it? yes! ship", &[]),
+            "Fix it. Then test it? Yes! Ship."
+        );
+        assert_eq!(clean("i think i'm done", &[]), "I think I'm done.");
+        assert_eq!(clean("user_id is wrong", &[]), "user_id is wrong.");
+        assert_eq!(clean("getUser returns null", &[]), "getUser returns null.");
+        assert_eq!(clean("`ls` the folder", &[]), "`ls` the folder.");
+    }
+
+    #[test]
+    fn tidies_punctuation() {
+        assert_eq!(clean("wait ,, what", &[]), "Wait, what.");
+        assert_eq!(clean("done ,.", &[]), "Done.");
+        assert_eq!(clean("it ends here,", &[]), "It ends here.");
+        assert_eq!(clean("is it done?", &[]), "Is it done?");
+        assert_eq!(clean("say \"hello\"", &[]), "Say \"hello.\"");
+        assert_eq!(clean("run it (twice)", &[]), "Run it (twice).");
+    }
+
+    #[test]
+    fn keeps_structure() {
+        assert_eq!(
+            clean("the steps are\n- um build it\n- test it", &[]),
+            "The steps are\n- Build it\n- Test it"
+        );
+        assert_eq!(
+            clean("run this\n```\num so\n```\nthen stop", &[]),
+            "Run this.\n```\num so\n```\nThen stop."
+        );
+        assert_eq!(clean("1. first thing", &[]), "1. First thing");
+    }
+
+    #[test]
+    fn applies_substitutions() {
+        let subs = [sub("post gress", "Postgres"), sub("jason", "JSON")];
+        assert_eq!(
+            clean("store the jason in post gress.", &subs),
+            "Store the JSON in Postgres."
+        );
+        assert_eq!(clean("Post Gress, please", &subs), "Postgres, please.");
+        // The phrase must not be split by punctuation
+        assert_eq!(clean("post, gress", &subs), "Post, gress.");
+    }
+
+    #[test]
+    fn substitutions_can_delete_phrases() {
+        let subs = [sub("over and out", "")];
+        assert_eq!(clean("that's all over and out.", &subs), "That's all.");
+        assert_eq!(clean("that's all, over and out", &subs), "That's all.");
+    }
+
+    #[test]
+    fn collapses_cut_off_words() {
+        assert_eq!(
+            clean("the func- function works", &[]),
+            "The function works."
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use futures_util::StreamExt;
+
+use super::error::RefineError;
+
+/// Read a streaming response line by line, calling `on_line` for each
+/// complete line (without the line terminator). Stops early when `on_line`
+/// returns `false`.
+pub async fn for_each_line<F>(resp: reqwest::Response, mut on_line: F) -> Result<(), RefineError>
+where
+    F: FnMut(&str) -> Result<bool, RefineError>,
+{
+    let mut stream = resp.bytes_stream();
+    let mut buf: Vec<u8> = Vec::new();
+
+    while let Some(chunk) = stream.next().await {
+        buf.extend_from_slice(&chunk?);
+        while let Some(pos) = buf.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buf.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            if !on_line(line.trim_end_matches(['\r', '\n']))? {
+                return Ok(());
+            }
+        }
+    }
+
+    if !buf.is_empty() {
+        let line = String::from_utf8_lossy(&buf);
+        on_line(line.trim_end_matches(['\r', '\n']))?;
+    }
+    Ok(())
+}
+
+/// Read a Server-Sent Events stream, calling `on_data` with the `data`
+/// payload of each event. Other fields (`event:`, `id:`, comments) are
+/// ignored. Stops early when `on_data` returns `false`.
+pub async fn for_each_event<F>(resp: reqwest::Response, mut on_data: F) -> Result<(), RefineError>
+where
+    F: FnMut(&str) -> Result<bool, RefineError>,
+{
+    let mut data = String::new();
+    let mut stopped = false;
+
+    for_each_line(resp, |line| {
+        if line.is_empty() {
+            // Blank line dispatches the buffered event
+            if data.is_empty() {
+                return Ok(true);
+            }
+            let keep_going = on_data(&data)?;
+            data.clear();
+            stopped = !keep_going;
+            Ok(keep_going)
+        } else if let Some(rest) = line.strip_prefix("data:") {
+            if !data.is_empty() {
+                data.push('\n');
+            }
+            data.push_str(rest.strip_prefix(' ').unwrap_or(rest));
+            Ok(true)
+        } else {
+            Ok(true)
+        }
+    })
+    .await?;
+
+    // Servers may close the stream without a final blank line
+    if !stopped && !data.is_empty() {
+        on_data(&data)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{Reply, Server};
+
+    async fn get(reply: Reply) -> reqwest::Response {
+        let server = Server::start(vec![reply]);
+        reqwest::get(&server.url).await.unwrap()
+    }
+
+    /// Every `data` payload, up to a `[DONE]` sentinel.
+    async fn events(pieces: &[&str]) -> Vec<String> {
+        let mut events = Vec::new();
+        for_each_event(get(Reply::streamed(200, pieces)).await, |data| {
+            if data == "[DONE]" {
+                return Ok(false);
+            }
+            events.push(data.to_string());
+            Ok(true)
+        })
+        .await
+        .unwrap();
+        events
+    }
+
+    #[tokio::test]
+    async fn events_split_across_chunks() {
+        let events = events(&["data: {\"a\":", "1}\n", "\ndata: two\n\n"]).await;
+        assert_eq!(events, ["{\"a\":1}", "two"]);
+    }
+
+    #[tokio::test]
+    async fn multi_line_data_is_joined() {
+        let events = events(&["data: first\ndata: second\ndata:third\n\n"]).await;
+        assert_eq!(events, ["first\nsecond\nthird"]);
+    }
+
+    #[tokio::test]
+    async fn other_fields_and_comments_are_skipped() {
+        let events = events(&[": keep-alive\n\nevent: delta\nid: 7\ndata: x\n\n"]).await;
+        assert_eq!(events, ["x"]);
+    }
+
+    #[tokio::test]
+    async fn done_stops_the_stream() {
+        let events = events(&["data: a\n\ndata: [DONE]\n\n", "data: late\n\n"]).await;
+        assert_eq!(events, ["a"]);
+    }
+
+    #[tokio::test]
+    async fn crlf_line_endings() {
+        let events = events(&["data: a\r\n\r", "\ndata: b\r\n\r\n"]).await;
+        assert_eq!(events, ["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn stream_ending_without_a_blank_line() {
+        assert_eq!(events(&["data: a\n\ndata: b\n"]).await, ["a", "b"]);
+        assert_eq!(events(&["data: a\n\ndata: b"]).await, ["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn callback_errors_are_returned() {
+        let resp = get(Reply::new(200, "data: a\n\n")).await;
+        let result =
+            for_each_event(resp, |_| Err(RefineError::InvalidResponse("bad".into()))).await;
+        assert_eq!(result, Err(RefineError::InvalidResponse("bad".into())));
+    }
+
+    #[tokio::test]
+    async fn lines_split_across_chunks() {
+        let resp = get(Reply::streamed(
+            200,
+            &["{\"n\":", "1}\r\n{\"n\":2}\n", "{\"n\":3}"],
+        ))
+        .await;
+        let mut lines = Vec::new();
+        for_each_line(resp, |line| {
+            lines.push(line.to_string());
+            Ok(true)
+        })
+        .await
+        .unwrap();
+        assert_eq!(lines, ["{\"n\":1}", "{\"n\":2}", "{\"n\":3}"]);
+    }
+
+    #[tokio::test]
+    async fn lines_stop_when_asked() {
+        let resp = get(Reply::new(200, "one\ntwo\nthree\n")).await;
+        let mut lines = Vec::new();
+        for_each_line(resp, |line| {
+            lines.push(line.to_string());
+            Ok(line != "two")
+        })
+        .await
+        .unwrap();
+        assert_eq!(lines, ["one", "two"]);
+    }
+}
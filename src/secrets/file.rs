@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+use super::SecretStore;
+
+const NONCE_LEN: usize = 12;
+
+/// Fallback store for systems without a Secret Service: a ChaCha20-Poly1305
+/// encrypted JSON map in `secrets.bin`, keyed by a random `secrets.key`.
+/// Both files are created with mode 0600. This keeps keys out of
+/// `config.json` (and dotfile repos), but anyone who can read both files
+/// can decrypt them.
+pub struct EncryptedFileStore {
+    dir: PathBuf,
+    // Serializes read-modify-write cycles
+    lock: Mutex<()>,
+}
+
+impl EncryptedFileStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Directory: ~/.local/share/voice-prompt/
+    pub fn default_dir() -> PathBuf {
+        let mut p = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+        p.push("voice-prompt");
+        p
+    }
+
+    fn cipher(&self) -> Result<ChaCha20Poly1305, Box<dyn std::error::Error + Send + Sync>> {
+        let path = self.dir.join("secrets.key");
+        let key = match fs::read(&path) {
+            Ok(bytes) if bytes.len() == 32 => *Key::from_slice(&bytes),
+            Ok(_) => return Err(format!("{} is corrupt", path.display()).into()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+                write_private(&path, &key)?;
+                key
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(ChaCha20Poly1305::new(&key))
+    }
+
+    fn read_all(
+        &self,
+        cipher: &ChaCha20Poly1305,
+    ) -> Result<BTreeMap<String, String>, Box<dyn std::error::Error + Send + Sync>> {
+        let data = match fs::read(self.dir.join("secrets.bin")) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(e.into()),
+        };
+        if data.len() < NONCE_LEN {
+            return Err("secrets.bin is truncated".into());
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "secrets.bin could not be decrypted")?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    fn write_all(
+        &self,
+        cipher: &ChaCha20Poly1305,
+        secrets: &BTreeMap<String, String>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let plaintext = serde_json::to_vec(secrets)?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| "failed to encrypt secrets")?;
+        let mut data = nonce.to_vec();
+        data.extend_from_slice(&ciphertext);
+        write_private(&self.dir.join("secrets.bin"), &data)
+    }
+}
+
+impl SecretStore for EncryptedFileStore {
+    fn name(&self) -> &'static str {
+        "encrypted file"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let cipher = self.cipher()?;
+        Ok(self.read_all(&cipher)?.remove(key))
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let cipher = self.cipher()?;
+        let mut secrets = self.read_all(&cipher)?;
+        if value.is_empty() {
+            if secrets.remove(key).is_none() {
+                return Ok(());
+            }
+        } else {
+            secrets.insert(key.to_string(), value.to_string());
+        }
+        self.write_all(&cipher, &secrets)
+    }
+}
+
+/// Write `data` to `path`, readable only by the current user. Goes through a
+/// temporary file so a crash never leaves a half-written secret file.
+fn write_private(path: &Path, data: &[u8]) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(&tmp)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A store in a directory of its own, removed when dropped.
+    struct TempStore {
+        store: EncryptedFileStore,
+        dir: PathBuf,
+    }
+
+    impl TempStore {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "voice-prompt-secrets-{}-{name}",
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            Self {
+                store: EncryptedFileStore::new(dir.clone()),
+                dir,
+            }
+        }
+    }
+
+    impl Drop for TempStore {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[test]
+    fn keys_roundtrip() {
+        let t = TempStore::new("roundtrip");
+        assert_eq!(t.store.get("gemini_api_key").unwrap(), None);
+
+        t.store.set("gemini_api_key", "AIza-secret").unwrap();
+        t.store.set("openai_api_key", "sk-secret").unwrap();
+        // A fresh store reads what an earlier one wrote
+        let reopened = EncryptedFileStore::new(t.dir.clone());
+        assert_eq!(
+            reopened.get("gemini_api_key").unwrap().as_deref(),
+            Some("AIza-secret")
+        );
+
+        reopened.set("gemini_api_key", "").unwrap();
+        assert_eq!(t.store.get("gemini_api_key").unwrap(), None);
+        assert_eq!(
+            t.store.get("openai_api_key").unwrap().as_deref(),
+            Some("sk-secret")
+        );
+    }
+
+    #[test]
+    fn keys_are_not_stored_in_the_clear() {
+        let t = TempStore::new("ciphertext");
+        t.store.set("gemini_api_key", "AIza-secret").unwrap();
+        let data = fs::read(t.dir.join("secrets.bin")).unwrap();
+        assert!(!String::from_utf8_lossy(&data).contains("AIza-secret"));
+    }
+
+    #[test]
+    fn another_key_cannot_decrypt() {
+        let t = TempStore::new("wrong-key");
+        t.store.set("gemini_api_key", "AIza-secret").unwrap();
+        let other = ChaCha20Poly1305::generate_key(&mut OsRng);
+        write_private(&t.dir.join("secrets.key"), &other).unwrap();
+
+        let err = t.store.get("gemini_api_key").unwrap_err();
+        assert_eq!(err.to_string(), "secrets.bin could not be decrypted");
+        // Nor is the file overwritten, losing the other keys
+        assert!(t.store.set("openai_api_key", "sk-secret").is_err());
+    }
+
+    #[test]
+    fn corrupt_files_are_errors() {
+        let t = TempStore::new("corrupt");
+        t.store.set("gemini_api_key", "AIza-secret").unwrap();
+        let path = t.dir.join("secrets.bin");
+
+        let mut data = fs::read(&path).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        fs::write(&path, &data).unwrap();
+        assert!(t.store.get("gemini_api_key").is_err());
+
+        fs::write(&path, [0; 4]).unwrap();
+        let err = t.store.get("gemini_api_key").unwrap_err();
+        assert_eq!(err.to_string(), "secrets.bin is truncated");
+
+        fs::write(t.dir.join("secrets.key"), [0; 7]).unwrap();
+        let err = t.store.get("gemini_api_key").unwrap_err();
+        assert!(err.to_string().ends_with("secrets.key is corrupt"), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn files_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let t = TempStore::new("permissions");
+        t.store.set("gemini_api_key", "AIza-secret").unwrap();
+        for name in ["secrets.key", "secrets.bin"] {
+            let mode = fs::metadata(t.dir.join(name)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600, "{name}");
+        }
+        assert!(!t.dir.join("secrets.tmp").exists());
+    }
+}
@@ -0,0 +1,47 @@
+mod file;
+#[cfg(target_os = "linux")]
+mod service;
+
+use std::sync::OnceLock;
+
+pub use file::EncryptedFileStore;
+#[cfg(target_os = "linux")]
+pub use service::SecretServiceStore;
+
+/// Secret names, also used as the `key` attribute of Secret Service items.
+pub const GEMINI_API_KEY: &str = "gemini_api_key";
+pub const OPENAI_API_KEY: &str = "openai_api_key";
+pub const TRANSCRIPTION_API_KEY: &str = "transcription_api_key";
+
+/// Somewhere to keep API keys outside of `config.json`.
+pub trait SecretStore: Send + Sync {
+    /// Short name used in logs and the dashboard.
+    fn name(&self) -> &'static str;
+
+    /// The stored value, or `None` if nothing is stored under `key`.
+    fn get(&self, key: &str) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>>;
+
+    /// Store `value` under `key`; an empty value deletes the entry.
+    fn set(&self, key: &str, value: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// The process-wide store: the desktop Secret Service when one is running,
+/// otherwise an encrypted file in the data directory.
+pub fn store() -> &'static dyn SecretStore {
+    static STORE: OnceLock<Box<dyn SecretStore>> = OnceLock::new();
+    STORE.get_or_init(open).as_ref()
+}
+
+fn open() -> Box<dyn SecretStore> {
+    #[cfg(target_os = "linux")]
+    {
+        match SecretServiceStore::connect() {
+            Ok(store) => {
+                log::info!("Storing API keys in the Secret Service");
+                return Box::new(store);
+            }
+            Err(e) => log::warn!("Secret Service unavailable ({e}), using encrypted file"),
+        }
+    }
+    Box::new(EncryptedFileStore::new(EncryptedFileStore::default_dir()))
+}
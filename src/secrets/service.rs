@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+
+use secret_service::blocking::SecretService;
+use secret_service::EncryptionType;
+
+use super::SecretStore;
+
+/// Attribute identifying our items in the keyring.
+const APPLICATION: &str = "voice-prompt";
+
+/// API keys stored through the freedesktop Secret Service (GNOME Keyring,
+/// KWallet, KeePassXC, ...) on the session bus.
+pub struct SecretServiceStore {
+    service: SecretService<'static>,
+}
+
+impl SecretServiceStore {
+    /// Connect to the Secret Service on the session bus named by
+    /// `DBUS_SESSION_BUS_ADDRESS`.
+    pub fn connect() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let service = SecretService::connect(EncryptionType::Dh)?;
+        // Fail now rather than on first use if there is no usable collection
+        service.get_default_collection()?;
+        Ok(Self { service })
+    }
+
+    fn attributes(key: &str) -> HashMap<&str, &str> {
+        HashMap::from([("application", APPLICATION), ("key", key)])
+    }
+}
+
+impl SecretStore for SecretServiceStore {
+    fn name(&self) -> &'static str {
+        "Secret Service"
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+        let collection = self.service.get_default_collection()?;
+        collection.ensure_unlocked()?;
+        let Some(item) = collection
+            .search_items(Self::attributes(key))?
+            .into_iter()
+            .next()
+        else {
+            return Ok(None);
+        };
+        let secret = item.get_secret()?;
+        Ok(Some(String::from_utf8(secret)?))
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let collection = self.service.get_default_collection()?;
+        collection.ensure_unlocked()?;
+        if value.is_empty() {
+            for item in collection.search_items(Self::attributes(key))? {
+                item.delete()?;
+            }
+            return Ok(());
+        }
+        collection.create_item(
+            &format!("Voice Prompt: {key}"),
+            Self::attributes(key),
+            value.as_bytes(),
+            true,
+            "text/plain",
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::{Arc, Mutex};
+
+    use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+
+    use super::*;
+
+    const SERVICE_PATH: &str = "/org/freedesktop/secrets";
+    const COLLECTION_PATH: &str = "/org/freedesktop/secrets/collection/login";
+
+    /// A private session bus, stopped when dropped.
+    struct Bus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl Bus {
+        /// `None` when dbus-daemon is not installed.
+        fn start() -> Option<Self> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?)
+                .read_line(&mut address)
+                .ok()?;
+            Some(Self {
+                daemon,
+                address: address.trim().to_string(),
+            })
+        }
+    }
+
+    impl Drop for Bus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    /// Session path, IV, encrypted value and content type, as on the wire.
+    type Secret = (OwnedObjectPath, Vec<u8>, Vec<u8>, String);
+
+    struct StoredItem {
+        attributes: HashMap<String, String>,
+        secret: Secret,
+    }
+
+    type Items = Arc<Mutex<BTreeMap<u32, StoredItem>>>;
+
+    fn item_path(id: u32) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(format!("{COLLECTION_PATH}/{id}")).unwrap()
+    }
+
+    fn no_prompt() -> OwnedObjectPath {
+        OwnedObjectPath::try_from("/").unwrap()
+    }
+
+    /// Stand-in for a keyring daemon. It never decrypts: secrets are kept
+    /// as the client encrypted them and handed back as they are.
+    struct Service;
+
+    #[zbus::interface(name = "org.freedesktop.Secret.Service")]
+    impl Service {
+        fn open_session(
+            &self,
+            _algorithm: &str,
+            _input: OwnedValue,
+        ) -> zbus::fdo::Result<(OwnedValue, OwnedObjectPath)> {
+            // Any public key will do, as the secrets are never decrypted here
+            let output = OwnedValue::try_from(Value::from(vec![2u8]))
+                .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))?;
+            let session = OwnedObjectPath::try_from(format!("{SERVICE_PATH}/session/1")).unwrap();
+            Ok((output, session))
+        }
+
+        fn read_alias(&self, _name: &str) -> OwnedObjectPath {
+            OwnedObjectPath::try_from(COLLECTION_PATH).unwrap()
+        }
+    }
+
+    struct Collection {
+        items: Items,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.Secret.Collection")]
+    impl Collection {
+        fn search_items(&self, attributes: HashMap<String, String>) -> Vec<OwnedObjectPath> {
+            let items = self.items.lock().unwrap();
+            items
+                .iter()
+                .filter(|(_, item)| {
+                    attributes
+                        .iter()
+                        .all(|(k, v)| item.attributes.get(k) == Some(v))
+                })
+                .map(|(id, _)| item_path(*id))
+                .collect()
+        }
+
+        async fn create_item(
+            &self,
+            #[zbus(object_server)] server: &zbus::ObjectServer,
+            properties: HashMap<String, OwnedValue>,
+            secret: Secret,
+            replace: bool,
+        ) -> zbus::fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+            let attributes = properties
+                .get("org.freedesktop.Secret.Item.Attributes")
+                .and_then(|v| HashMap::<String, String>::try_from(v.try_clone().ok()?).ok())
+                .ok_or_else(|| zbus::fdo::Error::InvalidArgs("no attributes".into()))?;
+            let (id, new) = {
+                let mut items = self.items.lock().unwrap();
+                let existing = items
+                    .iter()
+                    .find(|(_, item)| replace && item.attributes == attributes)
+                    .map(|(id, _)| *id);
+                let id = existing.unwrap_or_else(|| items.keys().last().map_or(1, |id| id + 1));
+                items.insert(id, StoredItem { attributes, secret });
+                (id, existing.is_none())
+            };
+            if new {
+                let item = Item {
+                    id,
+                    items: self.items.clone(),
+                };
+                server.at(item_path(id), item).await?;
+            }
+            Ok((item_path(id), no_prompt()))
+        }
+
+        #[zbus(property)]
+        fn locked(&self) -> bool {
+            false
+        }
+    }
+
+    struct Item {
+        id: u32,
+        items: Items,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.Secret.Item")]
+    impl Item {
+        fn get_secret(&self, _session: OwnedObjectPath) -> zbus::fdo::Result<Secret> {
+            let items = self.items.lock().unwrap();
+            let item = items
+                .get(&self.id)
+                .ok_or_else(|| zbus::fdo::Error::UnknownObject("deleted".into()))?;
+            Ok(item.secret.clone())
+        }
+
+        fn delete(&self) -> OwnedObjectPath {
+            self.items.lock().unwrap().remove(&self.id);
+            no_prompt()
+        }
+
+        #[zbus(property)]
+        fn locked(&self) -> bool {
+            false
+        }
+    }
+
+    /// Set in the child process that `keys_roundtrip_through_a_stand_in_service`
+    /// runs the store in.
+    const CHILD_ENV: &str = "VOICE_PROMPT_SECRET_SERVICE_TEST";
+
+    #[test]
+    fn keys_roundtrip_through_a_stand_in_service() {
+        let Some(bus) = Bus::start() else {
+            eprintln!("skipping: dbus-daemon is not installed");
+            return;
+        };
+        let items = Items::default();
+        let _service = zbus::blocking::connection::Builder::address(bus.address.as_str())
+            .unwrap()
+            .name("org.freedesktop.secrets")
+            .unwrap()
+            .serve_at(SERVICE_PATH, Service)
+            .unwrap()
+            .serve_at(
+                COLLECTION_PATH,
+                Collection {
+                    items: items.clone(),
+                },
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // secret-service only connects to the bus named by the environment.
+        // Changing it here would redirect every other test running in this
+        // process, so the store runs in a child with its own environment.
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args([
+                "secrets::service::tests::store_in_child",
+                "--exact",
+                "--nocapture",
+                "--test-threads=1",
+            ])
+            .env("DBUS_SESSION_BUS_ADDRESS", &bus.address)
+            .env(CHILD_ENV, "1")
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+        assert!(String::from_utf8_lossy(&output.stdout).contains("1 passed"));
+
+        let items = items.lock().unwrap();
+        assert_eq!(items.len(), 1);
+        let item = items.values().next().unwrap();
+        assert_eq!(item.attributes["application"], APPLICATION);
+        assert_eq!(item.attributes["key"], "openai_api_key");
+        // The session is encrypted, so the service never sees the key
+        assert!(!String::from_utf8_lossy(&item.secret.2).contains("sk-other"));
+    }
+
+    /// The client half of `keys_roundtrip_through_a_stand_in_service`;
+    /// does nothing unless started by it.
+    #[test]
+    fn store_in_child() {
+        if std::env::var_os(CHILD_ENV).is_none() {
+            return;
+        }
+        let store = SecretServiceStore::connect().unwrap();
+        assert_eq!(store.get("gemini_api_key").unwrap(), None);
+
+        store.set("gemini_api_key", "AIza-first").unwrap();
+        store.set("openai_api_key", "sk-other").unwrap();
+        assert_eq!(
+            store.get("gemini_api_key").unwrap().as_deref(),
+            Some("AIza-first")
+        );
+
+        // Storing again replaces the item rather than adding one
+        store.set("gemini_api_key", "AIza-second").unwrap();
+        assert_eq!(
+            store.get("gemini_api_key").unwrap().as_deref(),
+            Some("AIza-second")
+        );
+
+        // An empty value deletes the entry
+        store.set("gemini_api_key", "").unwrap();
+        assert_eq!(store.get("gemini_api_key").unwrap(), None);
+        assert_eq!(
+            store.get("openai_api_key").unwrap().as_deref(),
+            Some("sk-other")
+        );
+    }
+}
@@ -9,6 +9,32 @@ pub struct PromptRecord {
     pub text: String,
     pub word_count: usize,
     pub timestamp: String,
+    #[serde(flatten)]
+    pub details: PromptDetails,
+}
+
+/// How a prompt was produced. Empty or zero for entries recorded by
+/// older versions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromptDetails {
+    /// Transcript as spoken, before commands, cleanup and refinement
+    pub raw: String,
+    /// False when refinement failed and the raw transcript was copied
+    #[serde(default = "default_refined")]
+    pub refined: bool,
+    pub profile: String,
+    /// Refiner that produced the text: "gemini", "openai", "ollama" or "rules"
+    pub provider: String,
+    pub model: String,
+    pub language: String,
+    pub recording_ms: u64,
+    pub transcription_ms: u64,
+    pub refinement_ms: u64,
+}
+
+fn default_refined() -> bool {
+    true
 }
 
 /// Persistent usage statistics.
@@ -51,7 +77,7 @@ impl Stats {
     }
 
     /// Record a completed prompt and its word count.
-    pub fn record_prompt(&mut self, text: &str) {
+    pub fn record_prompt(&mut self, text: &str, details: PromptDetails) {
         let word_count = text.split_whitespace().count();
         self.total_prompts += 1;
         self.total_words += word_count;
@@ -59,6 +85,108 @@ impl Stats {
             text: text.to_string(),
             word_count,
             timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            details,
         });
     }
+
+    /// Replace the most recent prompt (a retried refinement of the same
+    /// recording), keeping the totals consistent.
+    pub fn replace_last_prompt(&mut self, text: &str, details: PromptDetails) {
+        let Some(last) = self.history.pop() else {
+            self.record_prompt(text, details);
+            return;
+        };
+        self.total_prompts = self.total_prompts.saturating_sub(1);
+        self.total_words = self.total_words.saturating_sub(last.word_count);
+        self.record_prompt(text, details);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details(provider: &str, refined: bool) -> PromptDetails {
+        PromptDetails {
+            provider: provider.into(),
+            refined,
+            ..PromptDetails::default()
+        }
+    }
+
+    /// Totals match the history, as when it was recorded prompt by prompt.
+    fn assert_consistent(stats: &Stats) {
+        assert_eq!(stats.total_prompts, stats.history.len());
+        let words: usize = stats.history.iter().map(|r| r.word_count).sum();
+        assert_eq!(stats.total_words, words);
+    }
+
+    #[test]
+    fn replacing_keeps_totals_consistent() {
+        let mut stats = Stats::default();
+        stats.record_prompt("Add a test", details("gemini", true));
+        stats.record_prompt("um fix the bug in main", details("rules", false));
+        stats.replace_last_prompt("Fix the bug.", details("gemini", true));
+
+        assert_consistent(&stats);
+        assert_eq!((stats.total_prompts, stats.total_words), (2, 6));
+        let last = stats.history.last().unwrap();
+        assert_eq!((last.text.as_str(), last.word_count), ("Fix the bug.", 3));
+        assert_eq!(last.details.provider, "gemini");
+        assert!(last.details.refined);
+        assert_eq!(stats.history[0].text, "Add a test");
+    }
+
+    #[test]
+    fn replacing_with_empty_history_records() {
+        let mut stats = Stats::default();
+        stats.replace_last_prompt("Fix the bug", details("ollama", true));
+
+        assert_consistent(&stats);
+        assert_eq!((stats.total_prompts, stats.total_words), (1, 3));
+        assert_eq!(stats.history[0].text, "Fix the bug");
+    }
+
+    #[test]
+    fn replacing_with_empty_text() {
+        let mut stats = Stats::default();
+        stats.record_prompt("Fix the bug", details("gemini", true));
+        stats.replace_last_prompt("", details("gemini", true));
+
+        assert_consistent(&stats);
+        assert_eq!((stats.total_prompts, stats.total_words), (1, 0));
+    }
+
+    #[test]
+    fn old_records_load_as_refined() {
+        let json = r#"{
+            "total_words": 3,
+            "total_prompts": 1,
+            "history": [
+                {"text": "Fix the bug", "word_count": 3, "timestamp": "2025-01-01 10:00:00"}
+            ]
+        }"#;
+        let stats: Stats = serde_json::from_str(json).unwrap();
+        let record = &stats.history[0];
+        assert_eq!(record.text, "Fix the bug");
+        assert!(record.details.refined);
+        assert_eq!(record.details.raw, "");
+        assert_eq!(record.details.provider, "");
+        assert_eq!(record.details.refinement_ms, 0);
+
+        // And before there was a history at all
+        let stats: Stats =
+            serde_json::from_str(r#"{"total_words": 3, "total_prompts": 1}"#).unwrap();
+        assert!(stats.history.is_empty());
+    }
+
+    #[test]
+    fn unrefined_records_round_trip() {
+        let mut stats = Stats::default();
+        stats.record_prompt("um fix the bug", details("rules", false));
+        let json = serde_json::to_string(&stats).unwrap();
+        let loaded: Stats = serde_json::from_str(&json).unwrap();
+        assert!(!loaded.history[0].details.refined);
+        assert_eq!(loaded.history[0].details.provider, "rules");
+    }
 }
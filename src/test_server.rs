@@ -0,0 +1,151 @@
+//! A local HTTP server that stands in for remote APIs in tests.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A request as the server received it.
+#[derive(Debug, Clone)]
+pub struct Request {
+    /// Method and path, e.g. `POST /v1/chat/completions`
+    pub line: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+}
+
+/// A canned response.
+pub struct Reply {
+    status: u16,
+    headers: Vec<(String, String)>,
+    /// Written one after the other with a pause in between, so the client
+    /// reads them as separate chunks
+    body: Vec<String>,
+}
+
+impl Reply {
+    pub fn new(status: u16, body: impl Into<String>) -> Self {
+        Self::streamed(status, &[&body.into()])
+    }
+
+    /// A body that arrives in the given pieces.
+    pub fn streamed(status: u16, pieces: &[&str]) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: pieces.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    pub fn json(value: serde_json::Value) -> Self {
+        Self::new(200, value.to_string()).header("content-type", "application/json")
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// Answers successive requests with the given replies, one connection each.
+pub struct Server {
+    /// Base URL, e.g. `http://127.0.0.1:40123`
+    pub url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl Server {
+    pub fn start(replies: Vec<Reply>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let received = requests.clone();
+        std::thread::spawn(move || {
+            for reply in replies {
+                let Ok((stream, _)) = listener.accept() else {
+                    return;
+                };
+                let mut reader = BufReader::new(stream);
+                let request = read_request(&mut reader);
+                received.lock().unwrap().push(request);
+
+                let mut response = format!(
+                    "HTTP/1.1 {} Stand-in\r\ncontent-length: {}\r\nconnection: close\r\n",
+                    reply.status,
+                    reply.body.iter().map(String::len).sum::<usize>()
+                );
+                for (name, value) in &reply.headers {
+                    response.push_str(&format!("{name}: {value}\r\n"));
+                }
+                response.push_str("\r\n");
+                let stream = reader.get_mut();
+                let _ = stream.write_all(response.as_bytes());
+                for (i, piece) in reply.body.iter().enumerate() {
+                    if i > 0 {
+                        let _ = stream.flush();
+                        std::thread::sleep(Duration::from_millis(20));
+                    }
+                    let _ = stream.write_all(piece.as_bytes());
+                }
+            }
+        });
+        Self { url, requests }
+    }
+
+    /// The requests received so far.
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn read_request(reader: &mut impl BufRead) -> Request {
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    let line = line
+        .trim_end()
+        .rsplit_once(' ')
+        .map_or(line.trim_end(), |(request, _version)| request)
+        .to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header).unwrap();
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    let length = headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .map_or(0, |(_, value)| value.parse().unwrap());
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+    Request {
+        line,
+        headers,
+        body,
+    }
+}
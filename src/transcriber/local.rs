@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+use super::{model_path, Transcriber};
+
+/// In-process transcription with whisper-rs.
+pub struct LocalTranscriber {
+    ctx: Arc<WhisperContext>,
+    language: String,
+}
+
+impl LocalTranscriber {
+    /// Load the whisper model from disk. This is CPU-heavy; call from a blocking context.
+    pub fn load(language: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let path = model_path();
+        let ctx = WhisperContext::new_with_params(
+            path.to_str().ok_or("Invalid model path")?,
+            WhisperContextParameters::default(),
+        )
+        .map_err(|e| format!("Failed to load whisper model: {e}"))?;
+        log::info!("Whisper model loaded");
+        Ok(Self {
+            ctx: Arc::new(ctx),
+            language: language.to_string(),
+        })
+    }
+}
+
+impl Transcriber for LocalTranscriber {
+    fn name(&self) -> &'static str {
+        "whisper-rs"
+    }
+
+    fn transcribe(
+        &self,
+        samples: Vec<f32>,
+        _sample_rate: u32,
+    ) -> BoxFuture<'static, Result<String, Box<dyn std::error::Error + Send + Sync>>> {
+        let ctx = self.ctx.clone();
+        let language = self.language.clone();
+        Box::pin(async move {
+            tokio::task::spawn_blocking(move || transcribe_blocking(&ctx, &language, &samples))
+                .await
+                .map_err(|e| format!("Transcription task panicked: {e}"))?
+        })
+    }
+}
+
+/// Transcribe audio samples (16kHz mono f32). CPU-heavy — call from `spawn_blocking`.
+fn transcribe_blocking(
+    ctx: &WhisperContext,
+    language: &str,
+    samples: &[f32],
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let mut state = ctx
+        .create_state()
+        .map_err(|e| format!("State error: {e}"))?;
+
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_language(Some(language));
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_timestamps(false);
+
+    let cpus = std::thread::available_parallelism()
+        .map(|n| n.get() as i32)
+        .unwrap_or(4);
+    params.set_n_threads(cpus);
+
+    state
+        .full(params, samples)
+        .map_err(|e| format!("Transcription failed: {e}"))?;
+
+    let mut text = String::new();
+    for segment in state.as_iter() {
+        // WhisperSegment implements Display
+        let seg_text = format!("{segment}");
+        text.push_str(&seg_text);
+        text.push(' ');
+    }
+
+    Ok(text.trim().to_string())
+}
@@ -1,10 +1,53 @@
+mod local;
+mod remote;
+
 use std::path::PathBuf;
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+
+use crate::config::{TranscriptionBackend, TranscriptionConfig};
+
+pub use local::LocalTranscriber;
+pub use remote::RemoteTranscriber;
 
 const MODEL_URL: &str =
     "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.en.bin";
 const MODEL_FILENAME: &str = "ggml-base.en.bin";
 
+/// A speech-to-text engine.
+///
+/// Implementations own whatever they need (model context, HTTP client) so the
+/// returned future is `'static` and can be spawned on the tokio runtime.
+pub trait Transcriber: Send + Sync {
+    /// Short name used in logs and the dashboard.
+    fn name(&self) -> &'static str;
+
+    /// Transcribe mono f32 samples captured at `sample_rate`.
+    fn transcribe(
+        &self,
+        samples: Vec<f32>,
+        sample_rate: u32,
+    ) -> BoxFuture<'static, Result<String, Box<dyn std::error::Error + Send + Sync>>>;
+}
+
+/// Whether the configured backend needs the local whisper model on disk.
+pub fn needs_local_model(config: &TranscriptionConfig) -> bool {
+    config.backend == TranscriptionBackend::Local
+}
+
+/// Build the remote transcriber described by `config`.
+/// The local backend is built separately once the model has been loaded.
+pub fn build_remote(config: &TranscriptionConfig) -> Result<RemoteTranscriber, reqwest::Error> {
+    RemoteTranscriber::new(
+        &config.server_url,
+        &config.model,
+        &config.api_key,
+        &config.language,
+        Duration::from_secs(config.timeout_secs.max(1)),
+    )
+}
+
 /// Directory for model storage: ~/.local/share/voice-prompt/models/
 fn models_dir() -> PathBuf {
     let mut p = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
@@ -55,50 +98,3 @@ where
     log::info!("Model downloaded to {}", path.display());
     Ok(())
 }
-
-/// Load the whisper model from disk. This is CPU-heavy; call from a blocking context.
-pub fn load_model() -> Result<WhisperContext, Box<dyn std::error::Error + Send + Sync>> {
-    let path = model_path();
-    let ctx = WhisperContext::new_with_params(
-        path.to_str().ok_or("Invalid model path")?,
-        WhisperContextParameters::default(),
-    )
-    .map_err(|e| format!("Failed to load whisper model: {e}"))?;
-    log::info!("Whisper model loaded");
-    Ok(ctx)
-}
-
-/// Transcribe audio samples (16kHz mono f32). CPU-heavy — call from `spawn_blocking`.
-pub fn transcribe(
-    ctx: &WhisperContext,
-    samples: &[f32],
-) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let mut state = ctx
-        .create_state()
-        .map_err(|e| format!("State error: {e}"))?;
-
-    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
-    params.set_language(Some("en"));
-    params.set_print_progress(false);
-    params.set_print_realtime(false);
-    params.set_print_timestamps(false);
-
-    let cpus = std::thread::available_parallelism()
-        .map(|n| n.get() as i32)
-        .unwrap_or(4);
-    params.set_n_threads(cpus);
-
-    state
-        .full(params, samples)
-        .map_err(|e| format!("Transcription failed: {e}"))?;
-
-    let mut text = String::new();
-    for segment in state.as_iter() {
-        // WhisperSegment implements Display
-        let seg_text = format!("{segment}");
-        text.push_str(&seg_text);
-        text.push(' ');
-    }
-
-    Ok(text.trim().to_string())
-}
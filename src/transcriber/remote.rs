@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+
+use super::Transcriber;
+
+/// Transcription via an OpenAI-compatible `/audio/transcriptions` endpoint,
+/// e.g. a shared whisper.cpp server or a hosted Whisper API.
+pub struct RemoteTranscriber {
+    client: reqwest::Client,
+    url: String,
+    model: String,
+    api_key: String,
+    language: String,
+}
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize)]
+struct TranscriptionResponse {
+    text: String,
+}
+
+impl RemoteTranscriber {
+    /// `base_url` is the API root including the version, e.g. `http://host:8080/v1`.
+    /// A request fails once it has taken longer than `timeout`.
+    pub fn new(
+        base_url: &str,
+        model: &str,
+        api_key: &str,
+        language: &str,
+        timeout: Duration,
+    ) -> Result<Self, reqwest::Error> {
+        let client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(timeout)
+            .build()?;
+        Ok(Self {
+            client,
+            url: format!("{}/audio/transcriptions", base_url.trim_end_matches('/')),
+            model: model.to_string(),
+            api_key: api_key.to_string(),
+            language: language.to_string(),
+        })
+    }
+}
+
+impl Transcriber for RemoteTranscriber {
+    fn name(&self) -> &'static str {
+        "remote"
+    }
+
+    fn transcribe(
+        &self,
+        samples: Vec<f32>,
+        sample_rate: u32,
+    ) -> BoxFuture<'static, Result<String, Box<dyn std::error::Error + Send + Sync>>> {
+        let client = self.client.clone();
+        let url = self.url.clone();
+        let model = self.model.clone();
+        let api_key = self.api_key.clone();
+        let language = self.language.clone();
+
+        Box::pin(async move {
+            let wav = crate::recorder::samples_to_wav(&samples, sample_rate)
+                .map_err(|e| format!("WAV encoding failed: {e}"))?;
+
+            let file = reqwest::multipart::Part::bytes(wav)
+                .file_name("audio.wav")
+                .mime_str("audio/wav")?;
+            let form = reqwest::multipart::Form::new()
+                .part("file", file)
+                .text("model", model)
+                .text("language", language)
+                .text("response_format", "json");
+
+            let mut req = client.post(&url).multipart(form);
+            if !api_key.is_empty() {
+                req = req.bearer_auth(&api_key);
+            }
+            let resp = req.send().await.map_err(scrubbed)?;
+
+            if !resp.status().is_success() {
+                let status = resp.status();
+                let text = resp.text().await.unwrap_or_default();
+                let text = crate::redact::scrub(&text);
+                return Err(format!("Transcription server error {status}: {text}").into());
+            }
+
+            let body: TranscriptionResponse = resp.json().await.map_err(scrubbed)?;
+            Ok(body.text.trim().to_string())
+        })
+    }
+}
+
+/// reqwest errors include the request URL, which may embed credentials.
+fn scrubbed(e: reqwest::Error) -> String {
+    if e.is_timeout() {
+        return "server did not answer in time".into();
+    }
+    crate::redact::scrub(&e.without_url().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{Reply, Server};
+
+    fn transcriber(url: &str, api_key: &str) -> RemoteTranscriber {
+        RemoteTranscriber::new(url, "whisper-1", api_key, "en", Duration::from_secs(5)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn uploads_wav_and_reads_text() {
+        let server = Server::start(vec![Reply::json(
+            serde_json::json!({ "text": " Add a test. \n" }),
+        )]);
+        let text = transcriber(&format!("{}/v1/", server.url), "sk-test")
+            .transcribe(vec![0.0; 1600], 16_000)
+            .await
+            .unwrap();
+        assert_eq!(text, "Add a test.");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        assert_eq!(request.line, "POST /v1/audio/transcriptions");
+        assert_eq!(request.header("authorization"), Some("Bearer sk-test"));
+        assert!(request
+            .header("content-type")
+            .unwrap()
+            .starts_with("multipart/form-data"));
+        let body = request.text();
+        assert!(body.contains("name=\"file\"; filename=\"audio.wav\""));
+        assert!(body.contains("RIFF"));
+        assert!(body.contains("name=\"model\"\r\n\r\nwhisper-1"));
+        assert!(body.contains("name=\"language\"\r\n\r\nen"));
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_reported_without_secrets() {
+        let server = Server::start(vec![Reply::new(500, "bad token=abc123")]);
+        let err = transcriber(&server.url, "")
+            .transcribe(vec![0.0; 160], 16_000)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Transcription server error 500"), "{err}");
+        assert!(!err.contains("abc123"), "{err}");
+        assert_eq!(server.requests()[0].header("authorization"), None);
+    }
+}
@@ -1,6 +1,11 @@
 use gtk4::prelude::*;
 use libadwaita::prelude::*;
 
+use crate::refiner::RefineError;
+
+/// Model names shown after a successful connection test.
+const MODELS_SHOWN: usize = 4;
+
 /// Handles returned from building the dashboard window.
 pub struct DashboardWidgets {
     pub window: libadwaita::ApplicationWindow,
@@ -9,7 +14,18 @@ pub struct DashboardWidgets {
     pub prompts_label: gtk4::Label,
     pub hotkey_label: gtk4::Label,
     pub change_hotkey_button: gtk4::Button,
+    pub append_hotkey_label: gtk4::Label,
+    pub change_append_hotkey_button: gtk4::Button,
+    pub primary_row: libadwaita::SwitchRow,
+    pub profile_row: libadwaita::ComboRow,
+    pub edit_profiles_button: gtk4::Button,
+    pub cleanup_row: libadwaita::SwitchRow,
+    pub commands_row: libadwaita::SwitchRow,
     pub api_key_row: libadwaita::PasswordEntryRow,
+    pub test_connection_button: gtk4::Button,
+    pub connection_row: libadwaita::ActionRow,
+    pub connection_label: gtk4::Label,
+    pub last_error_row: libadwaita::ActionRow,
     pub progress_bar: gtk4::ProgressBar,
     pub prompts_row: libadwaita::ActionRow,
 }
@@ -21,6 +37,14 @@ pub fn build_dashboard(
     initial_words: usize,
     initial_prompts: usize,
     initial_hotkey: &str,
+    initial_append_hotkey: &str,
+    clipboard_detail: &str,
+    keyboard_detail: &str,
+    initial_primary: bool,
+    profile_names: &[String],
+    active_profile: u32,
+    initial_cleanup: bool,
+    initial_commands: bool,
     initial_api_key: &str,
 ) -> DashboardWidgets {
     let window = libadwaita::ApplicationWindow::builder()
@@ -114,9 +138,95 @@ pub fn build_dashboard(
     hotkey_row.add_suffix(&change_hotkey_button);
     hotkey_group.add(&hotkey_row);
 
+    let append_hotkey_row = libadwaita::ActionRow::builder()
+        .title("Append Take")
+        .subtitle("Dictate one prompt in several takes")
+        .build();
+    let append_hotkey_label = gtk4::Label::new(Some(initial_append_hotkey));
+    append_hotkey_label.add_css_class("dim-label");
+    append_hotkey_row.add_suffix(&append_hotkey_label);
+
+    let change_append_hotkey_button = gtk4::Button::builder()
+        .label("Change")
+        .valign(gtk4::Align::Center)
+        .build();
+    append_hotkey_row.add_suffix(&change_append_hotkey_button);
+    hotkey_group.add(&append_hotkey_row);
+
     content.append(&hotkey_group);
     content.append(&gtk4::Separator::new(gtk4::Orientation::Horizontal));
 
+    // --- Clipboard group ---
+    let clipboard_group = libadwaita::PreferencesGroup::new();
+    clipboard_group.set_title("Clipboard");
+    clipboard_group.set_margin_top(12);
+
+    let clipboard_row = libadwaita::ActionRow::builder()
+        .title("Copy Method")
+        .subtitle(clipboard_detail)
+        .subtitle_lines(2)
+        .build();
+    clipboard_group.add(&clipboard_row);
+
+    // uinput and ydotool press keys by position, so say so where they're used
+    let keyboard_row = libadwaita::ActionRow::builder()
+        .title("Typing & Auto-paste")
+        .subtitle(keyboard_detail)
+        .subtitle_lines(3)
+        .tooltip_text(
+            "uinput and ydotool press keys where they sit on a US keyboard: \
+             on other layouts the text comes out garbled, and accented letters \
+             or other non-ASCII text can't be typed. wtype (Wayland) and \
+             xdotool (X11) type any character in any layout.",
+        )
+        .build();
+    clipboard_group.add(&keyboard_row);
+
+    let primary_row = libadwaita::SwitchRow::builder()
+        .title("Primary Selection")
+        .subtitle("Also copy prompts for middle-click pasting")
+        .active(initial_primary)
+        .visible(cfg!(target_os = "linux"))
+        .build();
+    clipboard_group.add(&primary_row);
+
+    content.append(&clipboard_group);
+    content.append(&gtk4::Separator::new(gtk4::Orientation::Horizontal));
+
+    // --- Refinement profile group ---
+    let profile_group = libadwaita::PreferencesGroup::new();
+    profile_group.set_title("Refinement");
+    profile_group.set_margin_top(12);
+
+    let profile_row = libadwaita::ComboRow::builder()
+        .title("Active Profile")
+        .build();
+    set_profile_names(&profile_row, profile_names, active_profile);
+
+    let edit_profiles_button = gtk4::Button::builder()
+        .label("Edit")
+        .valign(gtk4::Align::Center)
+        .build();
+    profile_row.add_suffix(&edit_profiles_button);
+    profile_group.add(&profile_row);
+
+    let cleanup_row = libadwaita::SwitchRow::builder()
+        .title("Rule-Based Pre-Pass")
+        .subtitle("Strip fillers and stutters offline before the LLM sees the text")
+        .active(initial_cleanup)
+        .build();
+    profile_group.add(&cleanup_row);
+
+    let commands_row = libadwaita::SwitchRow::builder()
+        .title("Spoken Commands")
+        .subtitle("\u{201c}new line\u{201d}, \u{201c}bullet point\u{201d}, \u{201c}snake case user id\u{201d}\u{2026}")
+        .active(initial_commands)
+        .build();
+    profile_group.add(&commands_row);
+
+    content.append(&profile_group);
+    content.append(&gtk4::Separator::new(gtk4::Orientation::Horizontal));
+
     // --- API Key group ---
     let api_group = libadwaita::PreferencesGroup::new();
     api_group.set_title("Gemini API");
@@ -125,9 +235,33 @@ pub fn build_dashboard(
     let api_key_row = libadwaita::PasswordEntryRow::builder()
         .title("API Key")
         .text(initial_api_key)
+        .show_apply_button(true)
         .build();
+    let test_connection_button = gtk4::Button::builder()
+        .label("Test")
+        .tooltip_text("Test the active profile's provider")
+        .valign(gtk4::Align::Center)
+        .build();
+    api_key_row.add_suffix(&test_connection_button);
     api_group.add(&api_key_row);
 
+    let connection_row = libadwaita::ActionRow::builder()
+        .title("Connection")
+        .subtitle_lines(2)
+        .visible(false)
+        .build();
+    let connection_label = gtk4::Label::new(None);
+    connection_row.add_suffix(&connection_label);
+    api_group.add(&connection_row);
+
+    let last_error_row = libadwaita::ActionRow::builder()
+        .title("Last Refinement Error")
+        .subtitle_lines(3)
+        .visible(false)
+        .build();
+    last_error_row.add_css_class("error");
+    api_group.add(&last_error_row);
+
     content.append(&api_group);
 
     // --- Download progress bar ---
@@ -153,8 +287,105 @@ pub fn build_dashboard(
         prompts_label,
         hotkey_label,
         change_hotkey_button,
+        append_hotkey_label,
+        change_append_hotkey_button,
+        primary_row,
+        profile_row,
+        edit_profiles_button,
+        cleanup_row,
+        commands_row,
         api_key_row,
+        test_connection_button,
+        connection_row,
+        connection_label,
+        last_error_row,
         progress_bar,
         prompts_row,
     }
 }
+
+/// Replace the profile choices and select `active`.
+pub fn set_profile_names(row: &libadwaita::ComboRow, names: &[String], active: u32) {
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    row.set_model(Some(&gtk4::StringList::new(&names)));
+    row.set_selected(active);
+}
+
+/// Show that a connection test is running.
+pub fn set_connection_testing(dash: &DashboardWidgets) {
+    dash.test_connection_button.set_sensitive(false);
+    dash.connection_row.set_visible(true);
+    dash.connection_row.set_subtitle("Testing\u{2026}");
+    set_status_class(&dash.connection_label, None);
+    dash.connection_label.set_text("");
+}
+
+/// Show the outcome of a connection test against `provider`.
+pub fn set_connection_status(
+    dash: &DashboardWidgets,
+    provider: &str,
+    result: &Result<Vec<String>, RefineError>,
+) {
+    dash.test_connection_button.set_sensitive(true);
+    dash.connection_row.set_visible(true);
+    let (status, class, detail) = match result {
+        Ok(models) => {
+            let mut detail = format!("{provider}: {} models", models.len());
+            if !models.is_empty() {
+                let shown: Vec<&str> = models
+                    .iter()
+                    .take(MODELS_SHOWN)
+                    .map(String::as_str)
+                    .collect();
+                detail.push_str(&format!(" \u{2014} {}", shown.join(", ")));
+                if models.len() > MODELS_SHOWN {
+                    detail.push('\u{2026}');
+                }
+            }
+            ("Valid", "success", detail)
+        }
+        Err(e @ RefineError::Auth(_)) => ("Invalid key", "error", format!("{provider}: {e}")),
+        Err(e @ RefineError::Quota(_)) => ("Quota exceeded", "warning", format!("{provider}: {e}")),
+        Err(e) => ("Unreachable", "error", format!("{provider}: {e}")),
+    };
+    dash.connection_label.set_text(status);
+    set_status_class(&dash.connection_label, Some(class));
+    dash.connection_row.set_subtitle(&escape_markup(&detail));
+}
+
+/// Show whether the API key could be stored, in the connection row.
+pub fn set_api_key_status(dash: &DashboardWidgets, result: &Result<(), String>) {
+    dash.connection_row.set_visible(true);
+    let (status, class, detail) = match result {
+        Ok(()) => ("Saved", "success", "API key stored".to_string()),
+        Err(e) => ("Not saved", "error", e.clone()),
+    };
+    dash.connection_label.set_text(status);
+    set_status_class(&dash.connection_label, Some(class));
+    dash.connection_row.set_subtitle(&escape_markup(&detail));
+}
+
+/// Show the most recent refinement failure, or hide the row.
+pub fn set_last_refinement_error(dash: &DashboardWidgets, error: Option<&str>) {
+    match error {
+        Some(error) => {
+            dash.last_error_row.set_subtitle(&escape_markup(error));
+            dash.last_error_row.set_visible(true);
+        }
+        None => dash.last_error_row.set_visible(false),
+    }
+}
+
+fn set_status_class(label: &gtk4::Label, class: Option<&str>) {
+    for c in ["success", "warning", "error"] {
+        label.remove_css_class(c);
+    }
+    if let Some(class) = class {
+        label.add_css_class(class);
+    }
+}
+
+/// Row subtitles are Pango markup; provider messages are plain text.
+fn escape_markup(text: &str) -> String {
+    gtk4::glib::markup_escape_text(text).to_string()
+}
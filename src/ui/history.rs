@@ -1,12 +1,25 @@
+use std::rc::Rc;
+
 use gtk4::prelude::*;
 use libadwaita::prelude::*;
 
-use crate::stats::PromptRecord;
+use crate::diff::{diff_words, Change};
+use crate::stats::{PromptDetails, PromptRecord};
+use crate::ui::refine_again::{show_refine_again_window, RefineRequest, SaveRequest};
+
+/// Profiles and callbacks behind each row's "Refine again…" action.
+pub struct RefineActions {
+    pub profile_names: Vec<String>,
+    pub active_profile: u32,
+    pub on_refine: RefineRequest,
+    pub on_save: SaveRequest,
+}
 
 /// Show a window listing past prompt history.
 pub fn show_history_window(
     parent: &impl IsA<gtk4::Window>,
     history: &[PromptRecord],
+    actions: Rc<RefineActions>,
 ) {
     let window = libadwaita::Window::builder()
         .title("Prompt History")
@@ -49,7 +62,7 @@ pub fn show_history_window(
         group.set_title("Recent Prompts");
 
         for record in history.iter().rev() {
-            let row = build_prompt_row(record, &toast_overlay);
+            let row = build_prompt_row(record, &window, &toast_overlay, &actions);
             group.add(&row);
         }
 
@@ -69,7 +82,9 @@ pub fn show_history_window(
 /// Build an ExpanderRow for a single prompt record.
 fn build_prompt_row(
     record: &PromptRecord,
+    window: &libadwaita::Window,
     toast_overlay: &libadwaita::ToastOverlay,
+    actions: &Rc<RefineActions>,
 ) -> libadwaita::ExpanderRow {
     let row = libadwaita::ExpanderRow::builder()
         .title(&record.timestamp)
@@ -90,6 +105,13 @@ fn build_prompt_row(
     count_label.add_css_class("dim-label");
     row.add_suffix(&count_label);
 
+    if !record.details.refined {
+        let raw_label = gtk4::Label::new(Some("raw"));
+        raw_label.add_css_class("warning");
+        raw_label.set_tooltip_text(Some("Refinement failed; the raw transcript was copied"));
+        row.add_suffix(&raw_label);
+    }
+
     // Copy button suffix
     let copy_btn = gtk4::Button::from_icon_name("edit-copy-symbolic");
     copy_btn.set_valign(gtk4::Align::Center);
@@ -105,6 +127,25 @@ fn build_prompt_row(
     });
     row.add_suffix(&copy_btn);
 
+    // Re-run the raw transcript through any profile
+    let refine_btn = gtk4::Button::from_icon_name("view-refresh-symbolic");
+    refine_btn.set_valign(gtk4::Align::Center);
+    refine_btn.set_tooltip_text(Some("Refine again\u{2026}"));
+    let record_for_refine = record.clone();
+    let window_for_refine = window.clone();
+    let actions = actions.clone();
+    refine_btn.connect_clicked(move |_| {
+        show_refine_again_window(
+            &window_for_refine,
+            &record_for_refine,
+            &actions.profile_names,
+            actions.active_profile,
+            actions.on_refine.clone(),
+            actions.on_save.clone(),
+        );
+    });
+    row.add_suffix(&refine_btn);
+
     // Full text child row (visible when expanded)
     let full_text_row = libadwaita::ActionRow::new();
     let label = gtk4::Label::new(Some(&record.text));
@@ -118,5 +159,122 @@ fn build_prompt_row(
     full_text_row.set_child(Some(&label));
     row.add_row(&full_text_row);
 
+    // What was actually said, and what refinement changed
+    let raw = &record.details.raw;
+    if !raw.is_empty() {
+        row.add_row(&build_raw_row(raw, &record.text, toast_overlay));
+    }
+
+    let summary = details_summary(&record.details);
+    if !summary.is_empty() {
+        let details_row = libadwaita::ActionRow::builder()
+            .title("Details")
+            .subtitle(gtk4::glib::markup_escape_text(&summary).as_str())
+            .subtitle_lines(2)
+            .build();
+        row.add_row(&details_row);
+    }
+
     row
 }
+
+/// Diff of the raw transcript against the copied text, with a copy button.
+fn build_raw_row(
+    raw: &str,
+    text: &str,
+    toast_overlay: &libadwaita::ToastOverlay,
+) -> libadwaita::ActionRow {
+    let raw_row = libadwaita::ActionRow::new();
+    let vbox = gtk4::Box::new(gtk4::Orientation::Vertical, 6);
+    vbox.set_margin_top(6);
+    vbox.set_margin_bottom(6);
+    vbox.set_margin_start(8);
+    vbox.set_margin_end(8);
+
+    let header = gtk4::Box::new(gtk4::Orientation::Horizontal, 6);
+    let title = gtk4::Label::new(Some(if raw == text {
+        "Transcript (unchanged)"
+    } else {
+        "Transcript \u{2192} prompt"
+    }));
+    title.add_css_class("dim-label");
+    title.set_xalign(0.0);
+    title.set_hexpand(true);
+    header.append(&title);
+
+    let copy_raw_btn = gtk4::Button::with_label("Copy Raw");
+    copy_raw_btn.add_css_class("flat");
+    copy_raw_btn.set_tooltip_text(Some("Copy the transcript as spoken"));
+    let raw_for_copy = raw.to_string();
+    let toast_for_copy = toast_overlay.clone();
+    copy_raw_btn.connect_clicked(move |_| {
+        let _ = crate::clipboard::copy_to_clipboard(&raw_for_copy);
+        let toast = libadwaita::Toast::new("Raw transcript copied to clipboard");
+        toast.set_timeout(2);
+        toast_for_copy.add_toast(toast);
+    });
+    header.append(&copy_raw_btn);
+    vbox.append(&header);
+
+    let diff_label = gtk4::Label::new(None);
+    diff_label.set_markup(&diff_markup(raw, text));
+    diff_label.set_wrap(true);
+    diff_label.set_xalign(0.0);
+    diff_label.set_selectable(true);
+    vbox.append(&diff_label);
+
+    raw_row.set_child(Some(&vbox));
+    raw_row
+}
+
+/// Pango markup: removed words struck through in red, added words in green.
+fn diff_markup(raw: &str, text: &str) -> String {
+    let mut markup = String::new();
+    let mut line_start = true;
+    for change in diff_words(raw, text) {
+        let (Change::Same(run) | Change::Removed(run) | Change::Added(run)) = &change;
+        if !line_start && !run.starts_with('\n') {
+            markup.push(' ');
+        }
+        line_start = run.ends_with('\n');
+        let escaped = gtk4::glib::markup_escape_text(run);
+        match &change {
+            Change::Same(_) => markup.push_str(&escaped),
+            Change::Removed(_) => markup.push_str(&format!(
+                "<span foreground=\"#e01b24\" strikethrough=\"true\">{escaped}</span>"
+            )),
+            Change::Added(_) => {
+                markup.push_str(&format!("<span foreground=\"#26a269\">{escaped}</span>"))
+            }
+        }
+    }
+    markup
+}
+
+/// "Claude Code · gemini (gemini-2.5-flash) · en · 12.4 s recorded · …"
+fn details_summary(details: &PromptDetails) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    if !details.profile.is_empty() {
+        parts.push(details.profile.clone());
+    }
+    match (details.provider.is_empty(), details.model.is_empty()) {
+        (false, false) => parts.push(format!("{} ({})", details.provider, details.model)),
+        (false, true) => parts.push(details.provider.clone()),
+        _ => {}
+    }
+    if !details.language.is_empty() {
+        parts.push(details.language.clone());
+    }
+    let secs = |ms: u64| ms as f64 / 1000.0;
+    if details.recording_ms > 0 {
+        parts.push(format!("{:.1} s recorded", secs(details.recording_ms)));
+    }
+    if details.transcription_ms > 0 {
+        let transcribed = secs(details.transcription_ms);
+        parts.push(format!("transcribed in {transcribed:.1} s"));
+    }
+    if details.refinement_ms > 0 {
+        parts.push(format!("refined in {:.1} s", secs(details.refinement_ms)));
+    }
+    parts.join(" \u{00b7} ")
+}
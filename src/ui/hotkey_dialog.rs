@@ -5,7 +5,7 @@ use crate::config::HotkeyConfig;
 
 /// Show a modal dialog that captures a new hotkey combo via evdev.
 /// Calls `on_result` on the GTK main thread with the captured config.
-pub fn show_hotkey_dialog<F>(parent: &libadwaita::ApplicationWindow, on_result: F)
+pub fn show_hotkey_dialog<F>(parent: &impl IsA<gtk4::Widget>, on_result: F)
 where
     F: Fn(Option<HotkeyConfig>) + 'static,
 {
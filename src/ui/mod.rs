@@ -2,3 +2,6 @@ pub mod dashboard;
 pub mod history;
 pub mod hotkey_dialog;
 pub mod overlay;
+pub mod profiles;
+pub mod refine_again;
+pub mod review;
@@ -22,6 +22,23 @@ pub struct OverlayWidgets {
     pub recording_label: gtk4::Label,
     pub hbox: gtk4::Box,
     pub status_label: gtk4::Label,
+    pub profile_button: gtk4::Button,
+    pub preview_label: gtk4::Label,
+    pub retry_button: gtk4::Button,
+    pub finish_button: gtk4::Button,
+}
+
+/// Show the active refinement profile on the overlay's profile button.
+pub fn set_overlay_profile(overlay: &OverlayWidgets, name: &str) {
+    overlay.profile_button.set_label(name);
+}
+
+/// Show the refined text streamed so far next to the "Refining…" status.
+pub fn set_overlay_preview(overlay: &OverlayWidgets, text: &str) {
+    // Single line: the label ellipsizes at the start so the newest words stay visible
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    overlay.preview_label.set_text(&flat);
+    overlay.preview_label.set_visible(!flat.is_empty());
 }
 
 /// Update overlay widgets to reflect the current pipeline phase.
@@ -33,7 +50,12 @@ pub fn set_overlay_phase(overlay: &OverlayWidgets, phase: &OverlayPhase) {
             overlay.waveform.set_visible(true);
             overlay.timer_label.set_visible(true);
             overlay.status_label.set_visible(false);
+            overlay.profile_button.set_visible(true);
+            overlay.preview_label.set_visible(false);
+            overlay.retry_button.set_visible(false);
+            overlay.finish_button.set_visible(false);
             overlay.hbox.remove_css_class("done-bar");
+            overlay.hbox.remove_css_class("fallback-bar");
         }
         OverlayPhase::Transcribing => {
             overlay.dot.set_visible(false);
@@ -42,7 +64,12 @@ pub fn set_overlay_phase(overlay: &OverlayWidgets, phase: &OverlayPhase) {
             overlay.timer_label.set_visible(false);
             overlay.status_label.set_text("Transcribing\u{2026}");
             overlay.status_label.set_visible(true);
+            overlay.profile_button.set_visible(true);
+            overlay.preview_label.set_visible(false);
+            overlay.retry_button.set_visible(false);
+            overlay.finish_button.set_visible(false);
             overlay.hbox.remove_css_class("done-bar");
+            overlay.hbox.remove_css_class("fallback-bar");
         }
         OverlayPhase::Refining => {
             overlay.dot.set_visible(false);
@@ -51,7 +78,11 @@ pub fn set_overlay_phase(overlay: &OverlayWidgets, phase: &OverlayPhase) {
             overlay.timer_label.set_visible(false);
             overlay.status_label.set_text("Refining\u{2026}");
             overlay.status_label.set_visible(true);
+            overlay.profile_button.set_visible(false);
+            overlay.retry_button.set_visible(false);
+            overlay.finish_button.set_visible(false);
             overlay.hbox.remove_css_class("done-bar");
+            overlay.hbox.remove_css_class("fallback-bar");
         }
         OverlayPhase::Done(_) => {
             overlay.dot.set_visible(false);
@@ -60,8 +91,44 @@ pub fn set_overlay_phase(overlay: &OverlayWidgets, phase: &OverlayPhase) {
             overlay.timer_label.set_visible(false);
             overlay.status_label.set_text("Done \u{2713}");
             overlay.status_label.set_visible(true);
+            overlay.profile_button.set_visible(false);
+            overlay.preview_label.set_visible(false);
+            overlay.retry_button.set_visible(false);
+            overlay.finish_button.set_visible(false);
+            overlay.hbox.remove_css_class("fallback-bar");
             overlay.hbox.add_css_class("done-bar");
         }
+        OverlayPhase::Fallback(_) => {
+            overlay.dot.set_visible(false);
+            overlay.recording_label.set_visible(false);
+            overlay.waveform.set_visible(false);
+            overlay.timer_label.set_visible(false);
+            overlay.status_label.set_text("Raw transcript (refinement failed)");
+            overlay.status_label.set_visible(true);
+            overlay.profile_button.set_visible(false);
+            overlay.preview_label.set_visible(false);
+            overlay.retry_button.set_visible(true);
+            overlay.finish_button.set_visible(false);
+            overlay.hbox.remove_css_class("done-bar");
+            overlay.hbox.add_css_class("fallback-bar");
+        }
+        OverlayPhase::Draft(takes) => {
+            overlay.dot.set_visible(false);
+            overlay.recording_label.set_visible(false);
+            overlay.waveform.set_visible(false);
+            overlay.timer_label.set_visible(false);
+            let plural = if *takes == 1 { "" } else { "s" };
+            overlay
+                .status_label
+                .set_text(&format!("Draft \u{00B7} {takes} take{plural}"));
+            overlay.status_label.set_visible(true);
+            overlay.profile_button.set_visible(true);
+            overlay.preview_label.set_visible(false);
+            overlay.retry_button.set_visible(false);
+            overlay.finish_button.set_visible(true);
+            overlay.hbox.remove_css_class("done-bar");
+            overlay.hbox.remove_css_class("fallback-bar");
+        }
     }
 }
 
@@ -69,6 +136,7 @@ pub fn set_overlay_phase(overlay: &OverlayWidgets, phase: &OverlayPhase) {
 pub fn build_overlay(
     app: &libadwaita::Application,
     backend_sender: async_channel::Sender<BackendEvent>,
+    initial_profile: &str,
 ) -> OverlayWidgets {
     let window = gtk4::Window::builder()
         .application(app)
@@ -95,6 +163,9 @@ pub fn build_overlay(
         .recording-bar.done-bar {
             background-color: rgba(30, 100, 30, 0.90);
         }
+        .recording-bar.fallback-bar {
+            background-color: rgba(130, 80, 10, 0.90);
+        }
         .recording-dot {
             color: #ff3b30;
             font-size: 18px;
@@ -114,6 +185,16 @@ pub fn build_overlay(
             font-weight: bold;
             font-size: 14px;
         }
+        .overlay-preview {
+            color: rgba(255, 255, 255, 0.85);
+            font-size: 13px;
+        }
+        .overlay-button {
+            color: rgba(255, 255, 255, 0.8);
+            font-size: 12px;
+            padding: 0 8px;
+            min-height: 0;
+        }
         "#,
     );
     gtk4::style_context_add_provider_for_display(
@@ -151,11 +232,56 @@ pub fn build_overlay(
     status_label.add_css_class("overlay-status");
     status_label.set_visible(false);
 
+    // Streamed refinement text, shown while refining
+    let preview_label = gtk4::Label::new(None);
+    preview_label.add_css_class("overlay-preview");
+    preview_label.set_ellipsize(gtk4::pango::EllipsizeMode::Start);
+    preview_label.set_max_width_chars(48);
+    preview_label.set_single_line_mode(true);
+    preview_label.set_visible(false);
+
+    // Active profile; clicking cycles to the next one
+    let profile_button = gtk4::Button::with_label(initial_profile);
+    profile_button.add_css_class("flat");
+    profile_button.add_css_class("overlay-button");
+    profile_button.set_valign(Align::Center);
+    profile_button.set_tooltip_text(Some("Switch refinement profile"));
+    let sender_for_profile = backend_sender.clone();
+    profile_button.connect_clicked(move |_| {
+        let _ = sender_for_profile.try_send(BackendEvent::CycleProfile);
+    });
+
+    // Re-run a failed refinement on the same transcript
+    let retry_button = gtk4::Button::with_label("Retry");
+    retry_button.add_css_class("flat");
+    retry_button.add_css_class("overlay-button");
+    retry_button.set_valign(Align::Center);
+    retry_button.set_visible(false);
+    let sender_for_retry = backend_sender.clone();
+    retry_button.connect_clicked(move |_| {
+        let _ = sender_for_retry.try_send(BackendEvent::RetryRefinement);
+    });
+
+    // Refine the draft without waiting for the timeout
+    let finish_button = gtk4::Button::with_label("Finish");
+    finish_button.add_css_class("flat");
+    finish_button.add_css_class("overlay-button");
+    finish_button.set_valign(Align::Center);
+    finish_button.set_visible(false);
+    let sender_for_finish = backend_sender.clone();
+    finish_button.connect_clicked(move |_| {
+        let _ = sender_for_finish.try_send(BackendEvent::FinishDraft);
+    });
+
     hbox.append(&dot);
     hbox.append(&recording_label);
     hbox.append(&waveform);
     hbox.append(&timer_label);
     hbox.append(&status_label);
+    hbox.append(&preview_label);
+    hbox.append(&profile_button);
+    hbox.append(&retry_button);
+    hbox.append(&finish_button);
 
     window.set_child(Some(&hbox));
 
@@ -207,6 +333,10 @@ pub fn build_overlay(
         recording_label,
         hbox,
         status_label,
+        profile_button,
+        preview_label,
+        retry_button,
+        finish_button,
     }
 }
 
@@ -0,0 +1,387 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use gtk4::prelude::*;
+use libadwaita::prelude::*;
+
+use crate::config::{HotkeyConfig, RefinementProfile, RefinementProvider};
+
+/// Widgets for editing a single profile.
+struct ProfileEditor {
+    expander: libadwaita::ExpanderRow,
+    name_row: libadwaita::EntryRow,
+    provider_row: libadwaita::ComboRow,
+    model_row: libadwaita::EntryRow,
+    temperature_row: libadwaita::SpinRow,
+    max_tokens_row: libadwaita::SpinRow,
+    review_row: libadwaita::SwitchRow,
+    auto_paste_row: libadwaita::SwitchRow,
+    paste_chord_row: libadwaita::EntryRow,
+    prompt_buffer: gtk4::TextBuffer,
+    hotkey: Rc<RefCell<Option<HotkeyConfig>>>,
+}
+
+impl ProfileEditor {
+    fn to_profile(&self) -> RefinementProfile {
+        let buffer = &self.prompt_buffer;
+        RefinementProfile {
+            name: self.name_row.text().trim().to_string(),
+            provider: RefinementProvider::ALL
+                .get(self.provider_row.selected() as usize)
+                .copied()
+                .unwrap_or_default(),
+            model: self.model_row.text().trim().to_string(),
+            system_prompt: buffer
+                .text(&buffer.start_iter(), &buffer.end_iter(), false)
+                .to_string(),
+            temperature: self.temperature_row.value() as f32,
+            max_tokens: self.max_tokens_row.value() as u32,
+            hotkey: self.hotkey.borrow().clone(),
+            review: self.review_row.is_active(),
+            auto_paste: self.auto_paste_row.is_active(),
+            paste_chord: self.paste_chord_row.text().trim().to_string(),
+        }
+    }
+}
+
+type Editors = Rc<RefCell<Vec<Rc<ProfileEditor>>>>;
+
+/// Show a window for adding, editing and deleting refinement profiles.
+/// Calls `on_save` with the full edited list when the user clicks Save.
+pub fn show_profiles_window<F>(
+    parent: &impl IsA<gtk4::Window>,
+    profiles: &[RefinementProfile],
+    on_save: F,
+) where
+    F: Fn(Vec<RefinementProfile>) + 'static,
+{
+    let window = libadwaita::Window::builder()
+        .title("Refinement Profiles")
+        .default_width(560)
+        .default_height(640)
+        .transient_for(parent)
+        .modal(true)
+        .build();
+
+    let toast_overlay = libadwaita::ToastOverlay::new();
+
+    let toolbar_view = libadwaita::ToolbarView::new();
+    let header = libadwaita::HeaderBar::new();
+    header.set_show_end_title_buttons(false);
+
+    let cancel_btn = gtk4::Button::with_label("Cancel");
+    header.pack_start(&cancel_btn);
+
+    let save_btn = gtk4::Button::with_label("Save");
+    save_btn.add_css_class("suggested-action");
+    header.pack_end(&save_btn);
+
+    let add_btn = gtk4::Button::from_icon_name("list-add-symbolic");
+    add_btn.set_tooltip_text(Some("Add profile"));
+    header.pack_end(&add_btn);
+
+    toolbar_view.add_top_bar(&header);
+
+    let content = gtk4::Box::new(gtk4::Orientation::Vertical, 0);
+    content.set_margin_start(16);
+    content.set_margin_end(16);
+    content.set_margin_top(12);
+    content.set_margin_bottom(12);
+
+    let group = libadwaita::PreferencesGroup::new();
+    group.set_title("Profiles");
+    group.set_description(Some(
+        "Each profile has its own system prompt, sampling settings and provider. \
+         A profile hotkey selects the profile and toggles recording.",
+    ));
+    content.append(&group);
+
+    let editors: Editors = Rc::new(RefCell::new(Vec::new()));
+    for profile in profiles {
+        add_editor(&window, &group, &editors, &toast_overlay, profile);
+    }
+
+    {
+        let window = window.clone();
+        let group = group.clone();
+        let editors = editors.clone();
+        let toast_overlay = toast_overlay.clone();
+        add_btn.connect_clicked(move |_| {
+            let profile = RefinementProfile {
+                name: unique_name(&editors),
+                hotkey: None,
+                ..RefinementProfile::default()
+            };
+            let editor = add_editor(&window, &group, &editors, &toast_overlay, &profile);
+            editor.expander.set_expanded(true);
+        });
+    }
+
+    {
+        let window = window.clone();
+        cancel_btn.connect_clicked(move |_| window.close());
+    }
+
+    {
+        let window = window.clone();
+        let editors = editors.clone();
+        let toast_overlay = toast_overlay.clone();
+        save_btn.connect_clicked(move |_| {
+            let edited: Vec<RefinementProfile> =
+                editors.borrow().iter().map(|e| e.to_profile()).collect();
+            if let Err(msg) = validate(&edited) {
+                toast_overlay.add_toast(libadwaita::Toast::new(&msg));
+                return;
+            }
+            on_save(edited);
+            window.close();
+        });
+    }
+
+    let scrolled = gtk4::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk4::PolicyType::Never)
+        .child(&content)
+        .build();
+    toolbar_view.set_content(Some(&scrolled));
+    toast_overlay.set_child(Some(&toolbar_view));
+    window.set_content(Some(&toast_overlay));
+    window.present();
+}
+
+/// Build an ExpanderRow with editing widgets for `profile` and add it to the group.
+fn add_editor(
+    window: &libadwaita::Window,
+    group: &libadwaita::PreferencesGroup,
+    editors: &Editors,
+    toast_overlay: &libadwaita::ToastOverlay,
+    profile: &RefinementProfile,
+) -> Rc<ProfileEditor> {
+    let expander = libadwaita::ExpanderRow::builder()
+        .title(&profile.name)
+        .subtitle(profile.provider.label())
+        .build();
+
+    let name_row = libadwaita::EntryRow::builder()
+        .title("Name")
+        .text(&profile.name)
+        .build();
+    let expander_for_name = expander.clone();
+    name_row.connect_changed(move |row| {
+        expander_for_name.set_title(&row.text());
+    });
+    expander.add_row(&name_row);
+
+    let labels: Vec<&str> = RefinementProvider::ALL.iter().map(|p| p.label()).collect();
+    let provider_row = libadwaita::ComboRow::builder()
+        .title("Provider")
+        .model(&gtk4::StringList::new(&labels))
+        .build();
+    let selected = RefinementProvider::ALL
+        .iter()
+        .position(|p| *p == profile.provider)
+        .unwrap_or(0);
+    provider_row.set_selected(selected as u32);
+    let expander_for_provider = expander.clone();
+    provider_row.connect_selected_notify(move |row| {
+        if let Some(p) = RefinementProvider::ALL.get(row.selected() as usize) {
+            expander_for_provider.set_subtitle(p.label());
+        }
+    });
+    expander.add_row(&provider_row);
+
+    let model_row = libadwaita::EntryRow::builder()
+        .title("Model (empty for provider default)")
+        .text(&profile.model)
+        .build();
+    expander.add_row(&model_row);
+
+    let temperature_row = libadwaita::SpinRow::with_range(0.0, 2.0, 0.05);
+    temperature_row.set_title("Temperature");
+    temperature_row.set_digits(2);
+    temperature_row.set_value(profile.temperature as f64);
+    expander.add_row(&temperature_row);
+
+    let max_tokens_row = libadwaita::SpinRow::with_range(64.0, 32768.0, 64.0);
+    max_tokens_row.set_title("Max Tokens");
+    max_tokens_row.set_value(profile.max_tokens as f64);
+    expander.add_row(&max_tokens_row);
+
+    // Per-profile hotkey
+    let hotkey = Rc::new(RefCell::new(profile.hotkey.clone()));
+    let hotkey_row = libadwaita::ActionRow::builder().title("Hotkey").build();
+    let hotkey_label = gtk4::Label::new(Some(&hotkey_display(&profile.hotkey)));
+    hotkey_label.add_css_class("dim-label");
+    hotkey_row.add_suffix(&hotkey_label);
+
+    let set_hotkey_btn = gtk4::Button::builder()
+        .label("Change")
+        .valign(gtk4::Align::Center)
+        .build();
+    {
+        let window = window.clone();
+        let hotkey = hotkey.clone();
+        let hotkey_label = hotkey_label.clone();
+        set_hotkey_btn.connect_clicked(move |_| {
+            let hotkey = hotkey.clone();
+            let hotkey_label = hotkey_label.clone();
+            crate::ui::hotkey_dialog::show_hotkey_dialog(&window, move |result| {
+                if let Some(new_hotkey) = result {
+                    hotkey_label.set_text(&new_hotkey.display_name);
+                    *hotkey.borrow_mut() = Some(new_hotkey);
+                }
+            });
+        });
+    }
+    hotkey_row.add_suffix(&set_hotkey_btn);
+
+    let clear_hotkey_btn = gtk4::Button::from_icon_name("edit-clear-symbolic");
+    clear_hotkey_btn.set_valign(gtk4::Align::Center);
+    clear_hotkey_btn.set_tooltip_text(Some("Remove hotkey"));
+    {
+        let hotkey = hotkey.clone();
+        let hotkey_label = hotkey_label.clone();
+        clear_hotkey_btn.connect_clicked(move |_| {
+            *hotkey.borrow_mut() = None;
+            hotkey_label.set_text(&hotkey_display(&None));
+        });
+    }
+    hotkey_row.add_suffix(&clear_hotkey_btn);
+    expander.add_row(&hotkey_row);
+
+    let review_row = libadwaita::SwitchRow::builder()
+        .title("Review Before Copying")
+        .subtitle("Edit the prompt, or record more, before it is copied")
+        .active(profile.review)
+        .build();
+    expander.add_row(&review_row);
+
+    let auto_paste_row = libadwaita::SwitchRow::builder()
+        .title("Paste Automatically")
+        .subtitle("Press the paste shortcut in the focused window after copying")
+        .active(profile.auto_paste)
+        .build();
+    expander.add_row(&auto_paste_row);
+
+    let paste_chord_row = libadwaita::EntryRow::builder()
+        .title("Paste Shortcut (empty for default, e.g. ctrl+shift+v)")
+        .text(&profile.paste_chord)
+        .build();
+    auto_paste_row
+        .bind_property("active", &paste_chord_row, "sensitive")
+        .sync_create()
+        .build();
+    expander.add_row(&paste_chord_row);
+
+    // System prompt editor
+    let prompt_box = gtk4::Box::new(gtk4::Orientation::Vertical, 6);
+    prompt_box.set_margin_top(8);
+    prompt_box.set_margin_bottom(8);
+    prompt_box.set_margin_start(12);
+    prompt_box.set_margin_end(12);
+    let prompt_label = gtk4::Label::new(Some("System Prompt"));
+    prompt_label.set_xalign(0.0);
+    prompt_label.add_css_class("dim-label");
+    prompt_box.append(&prompt_label);
+
+    let prompt_view = gtk4::TextView::builder()
+        .wrap_mode(gtk4::WrapMode::WordChar)
+        .top_margin(6)
+        .bottom_margin(6)
+        .left_margin(6)
+        .right_margin(6)
+        .build();
+    prompt_view.add_css_class("card");
+    let prompt_buffer = prompt_view.buffer();
+    prompt_buffer.set_text(&profile.system_prompt);
+    let prompt_scroll = gtk4::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk4::PolicyType::Never)
+        .min_content_height(160)
+        .child(&prompt_view)
+        .build();
+    prompt_box.append(&prompt_scroll);
+    expander.add_row(&prompt_box);
+
+    // Delete button
+    let delete_btn = gtk4::Button::from_icon_name("user-trash-symbolic");
+    delete_btn.set_valign(gtk4::Align::Center);
+    delete_btn.add_css_class("flat");
+    delete_btn.set_tooltip_text(Some("Delete profile"));
+    expander.add_suffix(&delete_btn);
+
+    group.add(&expander);
+
+    let editor = Rc::new(ProfileEditor {
+        expander: expander.clone(),
+        name_row,
+        provider_row,
+        model_row,
+        temperature_row,
+        max_tokens_row,
+        review_row,
+        auto_paste_row,
+        paste_chord_row,
+        prompt_buffer,
+        hotkey,
+    });
+    editors.borrow_mut().push(editor.clone());
+
+    {
+        let group = group.clone();
+        let editors = editors.clone();
+        let toast_overlay = toast_overlay.clone();
+        delete_btn.connect_clicked(move |_| {
+            if editors.borrow().len() <= 1 {
+                toast_overlay.add_toast(libadwaita::Toast::new(
+                    "At least one profile is required",
+                ));
+                return;
+            }
+            group.remove(&expander);
+            editors.borrow_mut().retain(|e| e.expander != expander);
+        });
+    }
+
+    editor
+}
+
+fn hotkey_display(hotkey: &Option<HotkeyConfig>) -> String {
+    hotkey
+        .as_ref()
+        .map(|hk| hk.display_name.clone())
+        .unwrap_or_else(|| "None".into())
+}
+
+/// Pick "New profile", "New profile 2", ... not already in use.
+fn unique_name(editors: &Editors) -> String {
+    let taken: HashSet<String> = editors
+        .borrow()
+        .iter()
+        .map(|e| e.name_row.text().trim().to_string())
+        .collect();
+    let mut name = "New profile".to_string();
+    let mut n = 2;
+    while taken.contains(&name) {
+        name = format!("New profile {n}");
+        n += 1;
+    }
+    name
+}
+
+fn validate(profiles: &[RefinementProfile]) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for p in profiles {
+        if p.name.is_empty() {
+            return Err("Profile names cannot be empty".into());
+        }
+        if !seen.insert(p.name.as_str()) {
+            return Err(format!("Duplicate profile name \"{}\"", p.name));
+        }
+        if !p.paste_chord.is_empty() {
+            if let Err(e) = crate::keyboard::Chord::parse(&p.paste_chord) {
+                return Err(format!("{}: invalid paste shortcut, {e}", p.name));
+            }
+        }
+    }
+    Ok(())
+}
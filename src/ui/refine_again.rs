@@ -0,0 +1,255 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gtk4::prelude::*;
+use libadwaita::prelude::*;
+
+use crate::app::{RefineInfo, RefineOutcome};
+use crate::config::RefinementProvider;
+use crate::stats::{PromptDetails, PromptRecord};
+
+/// Receives the refinement result on the GTK main loop.
+pub type RefineDone = Box<dyn FnOnce(RefineOutcome, RefineInfo)>;
+
+/// Runs `transcript` through the named profile, optionally with another
+/// provider and model (`None` and "" keep the profile's), and calls back
+/// when done.
+pub type RefineRequest = Rc<dyn Fn(&str, &str, Option<RefinementProvider>, &str, RefineDone)>;
+
+/// Saves text and its details as a new history entry.
+pub type SaveRequest = Rc<dyn Fn(&str, PromptDetails)>;
+
+/// Show a window that re-refines `record`'s raw transcript with a chosen
+/// profile, provider and model, next to the text that was copied originally.
+pub fn show_refine_again_window(
+    parent: &impl IsA<gtk4::Window>,
+    record: &PromptRecord,
+    profile_names: &[String],
+    active_profile: u32,
+    on_refine: RefineRequest,
+    on_save: SaveRequest,
+) {
+    let window = libadwaita::Window::builder()
+        .title("Refine Again")
+        .default_width(760)
+        .default_height(520)
+        .transient_for(parent)
+        .modal(true)
+        .build();
+
+    let toast_overlay = libadwaita::ToastOverlay::new();
+    let toolbar_view = libadwaita::ToolbarView::new();
+    let header = libadwaita::HeaderBar::new();
+
+    let refine_btn = gtk4::Button::with_label("Refine");
+    refine_btn.add_css_class("suggested-action");
+    header.pack_start(&refine_btn);
+
+    toolbar_view.add_top_bar(&header);
+
+    let content = gtk4::Box::new(gtk4::Orientation::Vertical, 12);
+    content.set_margin_start(16);
+    content.set_margin_end(16);
+    content.set_margin_top(12);
+    content.set_margin_bottom(12);
+
+    // Older entries have no raw transcript; refine what was copied instead
+    let source = if record.details.raw.is_empty() {
+        record.text.clone()
+    } else {
+        record.details.raw.clone()
+    };
+
+    let group = libadwaita::PreferencesGroup::new();
+    let names: Vec<&str> = profile_names.iter().map(String::as_str).collect();
+    let profile_row = libadwaita::ComboRow::builder()
+        .title("Profile")
+        .model(&gtk4::StringList::new(&names))
+        .selected(active_profile)
+        .build();
+    group.add(&profile_row);
+
+    let mut provider_labels = vec!["Profile's own"];
+    provider_labels.extend(RefinementProvider::ALL.iter().map(|p| p.label()));
+    let provider_row = libadwaita::ComboRow::builder()
+        .title("Provider")
+        .model(&gtk4::StringList::new(&provider_labels))
+        .build();
+    group.add(&provider_row);
+
+    let model_row = libadwaita::EntryRow::builder()
+        .title("Model (empty for the profile's or provider default)")
+        .build();
+    group.add(&model_row);
+
+    let source_row = libadwaita::ActionRow::builder()
+        .title(if record.details.raw.is_empty() {
+            "Source (copied text)"
+        } else {
+            "Source (raw transcript)"
+        })
+        .subtitle(gtk4::glib::markup_escape_text(&source).as_str())
+        .subtitle_lines(3)
+        .build();
+    group.add(&source_row);
+    content.append(&group);
+
+    // Original vs. new result, side by side
+    let columns = gtk4::Box::new(gtk4::Orientation::Horizontal, 12);
+    columns.set_homogeneous(true);
+    columns.set_vexpand(true);
+
+    let (original_box, _) = text_column("Original", &record.text);
+    columns.append(&original_box);
+
+    let (result_box, result_label) = text_column("New", "");
+    result_label.add_css_class("dim-label");
+    result_label.set_text("Pick a profile and click Refine.");
+    columns.append(&result_box);
+    content.append(&columns);
+
+    let actions = gtk4::Box::new(gtk4::Orientation::Horizontal, 6);
+    actions.set_halign(gtk4::Align::End);
+    let copy_btn = gtk4::Button::with_label("Copy");
+    copy_btn.set_sensitive(false);
+    let save_btn = gtk4::Button::with_label("Save as New Entry");
+    save_btn.set_sensitive(false);
+    actions.append(&copy_btn);
+    actions.append(&save_btn);
+    content.append(&actions);
+
+    toolbar_view.set_content(Some(&content));
+    toast_overlay.set_child(Some(&toolbar_view));
+    window.set_content(Some(&toast_overlay));
+
+    // Latest result, with the details it would be saved with
+    let result: Rc<RefCell<Option<(String, PromptDetails)>>> = Rc::new(RefCell::new(None));
+
+    {
+        let profile_row = profile_row.clone();
+        let provider_row = provider_row.clone();
+        let model_row = model_row.clone();
+        let result_label = result_label.clone();
+        let copy_btn = copy_btn.clone();
+        let save_btn = save_btn.clone();
+        let result = result.clone();
+        let base = record.details.clone();
+        let raw = source.clone();
+        refine_btn.connect_clicked(move |btn| {
+            let Some(profile) = profile_row
+                .selected_item()
+                .and_downcast::<gtk4::StringObject>()
+                .map(|obj| obj.string().to_string())
+            else {
+                return;
+            };
+            // The first entry keeps the profile's provider
+            let provider = (provider_row.selected() as usize)
+                .checked_sub(1)
+                .and_then(|i| RefinementProvider::ALL.get(i).copied());
+            let model = model_row.text().trim().to_string();
+            btn.set_sensitive(false);
+            copy_btn.set_sensitive(false);
+            save_btn.set_sensitive(false);
+            result_label.add_css_class("dim-label");
+            result_label.set_text("Refining\u{2026}");
+
+            let btn = btn.clone();
+            let result_label = result_label.clone();
+            let copy_btn = copy_btn.clone();
+            let save_btn = save_btn.clone();
+            let result = result.clone();
+            let base = base.clone();
+            let raw = raw.clone();
+            on_refine(
+                &source,
+                &profile,
+                provider,
+                &model,
+                Box::new(move |outcome, info| {
+                    btn.set_sensitive(true);
+                    result_label.remove_css_class("dim-label");
+                    result_label.set_text(outcome.text());
+                    if let RefineOutcome::Fallback { ref reason, .. } = outcome {
+                        result_label.set_tooltip_text(Some(&format!(
+                            "Refinement failed, showing the unrefined text: {reason}"
+                        )));
+                    } else {
+                        result_label.set_tooltip_text(None);
+                    }
+                    let details = PromptDetails {
+                        raw: raw.clone(),
+                        refined: outcome.is_refined(),
+                        profile: info.profile,
+                        provider: info.provider,
+                        model: info.model,
+                        refinement_ms: info.latency.as_millis() as u64,
+                        ..base.clone()
+                    };
+                    *result.borrow_mut() = Some((outcome.text().to_string(), details));
+                    copy_btn.set_sensitive(true);
+                    save_btn.set_sensitive(true);
+                }),
+            );
+        });
+    }
+
+    {
+        let result = result.clone();
+        let toast_overlay = toast_overlay.clone();
+        copy_btn.connect_clicked(move |_| {
+            if let Some((ref text, _)) = *result.borrow() {
+                let _ = crate::clipboard::copy_to_clipboard(text);
+                let toast = libadwaita::Toast::new("Prompt copied to clipboard");
+                toast.set_timeout(2);
+                toast_overlay.add_toast(toast);
+            }
+        });
+    }
+
+    save_btn.connect_clicked(move |btn| {
+        let current = result.borrow().clone();
+        if let Some((text, details)) = current {
+            on_save(&text, details);
+            btn.set_sensitive(false);
+            let toast = libadwaita::Toast::new("Saved as a new history entry");
+            toast.set_timeout(2);
+            toast_overlay.add_toast(toast);
+        }
+    });
+
+    window.present();
+}
+
+/// A titled, scrollable, selectable text column. Returns the column and its label.
+fn text_column(title: &str, text: &str) -> (gtk4::Box, gtk4::Label) {
+    let column = gtk4::Box::new(gtk4::Orientation::Vertical, 6);
+
+    let title_label = gtk4::Label::new(Some(title));
+    title_label.add_css_class("heading");
+    title_label.set_xalign(0.0);
+    column.append(&title_label);
+
+    let label = gtk4::Label::new(Some(text));
+    label.set_wrap(true);
+    label.set_xalign(0.0);
+    label.set_yalign(0.0);
+    label.set_selectable(true);
+
+    let frame = gtk4::Frame::new(None);
+    frame.add_css_class("view");
+    let scrolled = gtk4::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk4::PolicyType::Never)
+        .min_content_height(200)
+        .vexpand(true)
+        .child(&label)
+        .build();
+    label.set_margin_top(8);
+    label.set_margin_bottom(8);
+    label.set_margin_start(8);
+    label.set_margin_end(8);
+    frame.set_child(Some(&scrolled));
+    column.append(&frame);
+
+    (column, label)
+}
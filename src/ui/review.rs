@@ -0,0 +1,177 @@
+use gtk4::gdk;
+use gtk4::prelude::*;
+use libadwaita::prelude::*;
+
+use crate::app::{BackendEvent, Retake};
+
+/// Handles for the open review window.
+pub struct ReviewWidgets {
+    pub window: libadwaita::Window,
+    pub buffer: gtk4::TextBuffer,
+}
+
+/// Current contents of the review editor.
+pub fn review_text(review: &ReviewWidgets) -> String {
+    let buffer = &review.buffer;
+    buffer
+        .text(&buffer.start_iter(), &buffer.end_iter(), false)
+        .to_string()
+}
+
+/// Close the review window without sending `ReviewDiscarded`.
+pub fn close_review_window(review: &ReviewWidgets) {
+    review.window.destroy();
+}
+
+/// Show `text` in an editable window. Enter copies it, Shift+Enter inserts a
+/// line break, Esc discards it; the buttons record another take. Results
+/// are sent to the main loop as `BackendEvent`s.
+pub fn show_review_window(
+    text: &str,
+    profile: &str,
+    failure: Option<&str>,
+    backend_sender: async_channel::Sender<BackendEvent>,
+) -> ReviewWidgets {
+    let window = libadwaita::Window::builder()
+        .title("Review Prompt")
+        .default_width(520)
+        .default_height(300)
+        .build();
+
+    let toolbar_view = libadwaita::ToolbarView::new();
+    let header = libadwaita::HeaderBar::new();
+    header.set_show_end_title_buttons(false);
+    header.set_title_widget(Some(&libadwaita::WindowTitle::new(
+        "Review Prompt",
+        profile,
+    )));
+
+    let discard_btn = gtk4::Button::with_label("Discard");
+    header.pack_start(&discard_btn);
+
+    let copy_btn = gtk4::Button::with_label("Copy");
+    copy_btn.add_css_class("suggested-action");
+    header.pack_end(&copy_btn);
+    toolbar_view.add_top_bar(&header);
+
+    if let Some(reason) = failure {
+        let banner =
+            libadwaita::Banner::new("Refinement failed \u{2014} this is the raw transcript");
+        banner.set_tooltip_text(Some(reason));
+        banner.set_revealed(true);
+        toolbar_view.add_top_bar(&banner);
+    }
+
+    let content = gtk4::Box::new(gtk4::Orientation::Vertical, 8);
+    content.set_margin_start(12);
+    content.set_margin_end(12);
+    content.set_margin_top(12);
+    content.set_margin_bottom(12);
+
+    let view = gtk4::TextView::builder()
+        .wrap_mode(gtk4::WrapMode::WordChar)
+        .top_margin(8)
+        .bottom_margin(8)
+        .left_margin(8)
+        .right_margin(8)
+        .build();
+    view.add_css_class("card");
+    let buffer = view.buffer();
+    buffer.set_text(text);
+    buffer.place_cursor(&buffer.end_iter());
+    let scrolled = gtk4::ScrolledWindow::builder()
+        .hscrollbar_policy(gtk4::PolicyType::Never)
+        .vexpand(true)
+        .child(&view)
+        .build();
+    content.append(&scrolled);
+
+    let bottom = gtk4::Box::new(gtk4::Orientation::Horizontal, 6);
+    let hint = gtk4::Label::new(Some(
+        "Enter to copy \u{00B7} Shift+Enter for a new line \u{00B7} Esc to discard",
+    ));
+    hint.add_css_class("dim-label");
+    hint.add_css_class("caption");
+    hint.set_hexpand(true);
+    hint.set_xalign(0.0);
+    bottom.append(&hint);
+
+    let append_btn = gtk4::Button::with_label("Record More");
+    append_btn.set_tooltip_text(Some("Record another take and append it"));
+    let replace_btn = gtk4::Button::with_label("Re-record");
+    replace_btn.set_tooltip_text(Some("Record a new take that replaces this text"));
+    bottom.append(&append_btn);
+    bottom.append(&replace_btn);
+    content.append(&bottom);
+
+    toolbar_view.set_content(Some(&content));
+    window.set_content(Some(&toolbar_view));
+
+    let current = {
+        let buffer = buffer.clone();
+        move || {
+            buffer
+                .text(&buffer.start_iter(), &buffer.end_iter(), false)
+                .to_string()
+        }
+    };
+
+    {
+        let sender = backend_sender.clone();
+        let current = current.clone();
+        copy_btn.connect_clicked(move |_| {
+            let _ = sender.try_send(BackendEvent::ReviewConfirmed(current()));
+        });
+    }
+    {
+        let sender = backend_sender.clone();
+        let current = current.clone();
+        append_btn.connect_clicked(move |_| {
+            let _ = sender.try_send(BackendEvent::ReviewRetake(current(), Retake::Append));
+        });
+    }
+    {
+        let sender = backend_sender.clone();
+        let current = current.clone();
+        replace_btn.connect_clicked(move |_| {
+            let _ = sender.try_send(BackendEvent::ReviewRetake(current(), Retake::Replace));
+        });
+    }
+    {
+        let window = window.clone();
+        discard_btn.connect_clicked(move |_| window.close());
+    }
+
+    // Enter and Esc are caught before the text view sees them
+    let keys = gtk4::EventControllerKey::new();
+    keys.set_propagation_phase(gtk4::PropagationPhase::Capture);
+    {
+        let sender = backend_sender.clone();
+        let window = window.clone();
+        keys.connect_key_pressed(move |_, key, _, modifiers| match key {
+            gdk::Key::Return | gdk::Key::KP_Enter
+                if !modifiers.contains(gdk::ModifierType::SHIFT_MASK) =>
+            {
+                let _ = sender.try_send(BackendEvent::ReviewConfirmed(current()));
+                gtk4::glib::Propagation::Stop
+            }
+            gdk::Key::Escape => {
+                window.close();
+                gtk4::glib::Propagation::Stop
+            }
+            _ => gtk4::glib::Propagation::Proceed,
+        });
+    }
+    window.add_controller(keys);
+
+    // Closing the window (Esc, Discard, window manager) discards the prompt
+    window.connect_close_request(move |_| {
+        let _ = backend_sender.try_send(BackendEvent::ReviewDiscarded);
+        gtk4::glib::Propagation::Proceed
+    });
+
+    window.present();
+    view.grab_focus();
+
+    ReviewWidgets { window, buffer }
+}